@@ -4,6 +4,10 @@
 //! The encoded path replaces `/` with `-` in the absolute repo path.
 //! For example, a repo at `/Users/foo/bar` produces a directory named
 //! `-Users-foo-bar` under `~/.claude/projects/`.
+//!
+//! Project directories can also hold JSONL files that are not transcripts
+//! (summaries, caches). Only files whose first lines carry a `sessionId` or
+//! `cwd` are reported as sessions.
 
 use std::path::{Path, PathBuf};
 
@@ -34,16 +38,112 @@ pub struct ClaudeExplorer;
 impl AgentExplorer for ClaudeExplorer {
     async fn discover_recent(&self, now: i64, since_secs: i64) -> Vec<SessionLog> {
         let dirs = all_log_dirs().await;
-        recent_files_with_exts(&dirs, now, since_secs, &["jsonl"])
-            .await
-            .into_iter()
-            .map(|file| SessionLog {
+        let mut logs = Vec::new();
+        for file in recent_files_with_exts(&dirs, now, since_secs, &["jsonl"]).await {
+            if !is_session_transcript(&file.path).await {
+                continue;
+            }
+            logs.push(SessionLog {
                 agent_type: AgentType::Claude,
                 source: SessionSource::File(file.path),
                 updated_at: Some(file.mtime_epoch),
-            })
+            });
+        }
+        logs
+    }
+}
+
+/// Lines read when sniffing a file; transcripts may open with a few summary
+/// or snapshot records before the first message.
+const SNIFF_MAX_LINES: usize = 16;
+
+/// Bytes read when sniffing a file, decompressed for gzip logs.
+const SNIFF_MAX_BYTES: u64 = 64 * 1024;
+
+/// Whether `path` looks like a session transcript: one of its first lines is
+/// a JSON object with a `sessionId` or `cwd` string.
+async fn is_session_transcript(path: &Path) -> bool {
+    is_session_transcript_within(path, super::max_session_log_bytes()).await
+}
+
+/// Files over `max_bytes` are reported as transcripts without being opened,
+/// so the caller skips them with its usual oversized-log warning.
+async fn is_session_transcript_within(path: &Path, max_bytes: u64) -> bool {
+    match tokio::fs::metadata(path).await {
+        Ok(metadata) if metadata.len() > max_bytes => return true,
+        Ok(_) => {}
+        Err(_) => return false,
+    }
+    let path = path.to_path_buf();
+    let head = tokio::task::spawn_blocking(move || {
+        use std::io::Read;
+        let file = std::fs::File::open(&path)?;
+        let reader: Box<dyn Read> = if super::is_gzip_log(&path) {
+            Box::new(flate2::read::MultiGzDecoder::new(std::io::BufReader::new(
+                file,
+            )))
+        } else {
+            Box::new(file)
+        };
+        let mut head = Vec::new();
+        reader.take(SNIFF_MAX_BYTES).read_to_end(&mut head)?;
+        std::io::Result::Ok(head)
+    })
+    .await;
+    let Ok(Ok(head)) = head else {
+        return false;
+    };
+    let head = String::from_utf8_lossy(&head);
+    let truncated = head.len() as u64 >= SNIFF_MAX_BYTES;
+    let mut lines = head.lines().take(SNIFF_MAX_LINES).peekable();
+    while let Some(line) = lines.next() {
+        if is_transcript_line(line) {
+            return true;
+        }
+        // A line cut off by the sniff limit cannot be parsed; transcripts
+        // name the session near the start of each record.
+        if truncated && lines.peek().is_none() && is_truncated_transcript_line(line) {
+            return true;
+        }
+    }
+    false
+}
+
+fn is_truncated_transcript_line(line: &str) -> bool {
+    line.starts_with('{')
+        && ["\"sessionId\":\"", "\"session_id\":\"", "\"cwd\":\""]
+            .iter()
+            .any(|key| line.contains(key))
+}
+
+/// Whether logs in the project directory `dir_name` may come from sessions
+/// working in `repo_root` or below it, or, with `match_parent`, in one of its
+/// parent directories. Decided from the name alone: every character that is
+/// not an ASCII letter or digit is compared as `-`, since Claude Code has
+/// encoded separators and dots that way.
+pub fn project_dir_may_match(dir_name: &str, repo_root: &Path, match_parent: bool) -> bool {
+    fn encode(s: &str) -> String {
+        s.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
             .collect()
     }
+    let dir = encode(dir_name);
+    let repo = encode(&repo_root.to_string_lossy());
+    let nested = |outer: &str, inner: &str| {
+        inner
+            .strip_prefix(outer)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+    };
+    nested(&repo, &dir) || (match_parent && nested(&dir, &repo))
+}
+
+fn is_transcript_line(line: &str) -> bool {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+        return false;
+    };
+    ["sessionId", "session_id", "cwd"]
+        .iter()
+        .any(|key| value.get(key).is_some_and(serde_json::Value::is_string))
 }
 
 /// Internal: find ALL Claude log directories under a given home directory.
@@ -127,6 +227,60 @@ mod tests {
         assert_eq!(result.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_only_session_transcripts_are_recognized() {
+        let dir = TempDir::new().unwrap();
+        let write = |name: &str, content: &str| {
+            std::fs::write(dir.path().join(name), content).unwrap();
+            dir.path().join(name)
+        };
+
+        let transcript = write(
+            "a.jsonl",
+            "{\"type\":\"summary\",\"summary\":\"Fix parser\"}\n\
+             {\"type\":\"user\",\"sessionId\":\"a\",\"cwd\":\"/repo\"}\n",
+        );
+        let summaries = write(
+            "summaries.jsonl",
+            "{\"type\":\"summary\",\"summary\":\"Fix parser\"}\n",
+        );
+        let cache = write("cache.jsonl", "not json at all\n");
+        let empty = write("empty.jsonl", "");
+
+        assert!(is_session_transcript(&transcript).await);
+        assert!(!is_session_transcript(&summaries).await);
+        assert!(!is_session_transcript(&cache).await);
+        assert!(!is_session_transcript(&empty).await);
+        assert!(!is_session_transcript(&dir.path().join("missing.jsonl")).await);
+    }
+
+    #[tokio::test]
+    async fn test_sniffing_reads_a_bounded_prefix() {
+        let dir = TempDir::new().unwrap();
+        // A first record longer than the sniff limit.
+        let long = dir.path().join("long.jsonl");
+        let text = "x".repeat(SNIFF_MAX_BYTES as usize * 2);
+        std::fs::write(
+            &long,
+            format!("{{\"type\":\"user\",\"sessionId\":\"a\",\"text\":\"{text}\"}}\n"),
+        )
+        .unwrap();
+        assert!(is_session_transcript_within(&long, u64::MAX).await);
+
+        let gz = dir.path().join("long.jsonl.gz");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, &std::fs::read(&long).unwrap()).unwrap();
+        std::fs::write(&gz, encoder.finish().unwrap()).unwrap();
+        assert!(is_session_transcript_within(&gz, u64::MAX).await);
+
+        // Over the size limit: reported without being read, so the caller
+        // can skip it with a warning.
+        let big = dir.path().join("big.jsonl");
+        std::fs::write(&big, "not json at all\n").unwrap();
+        assert!(is_session_transcript_within(&big, 4).await);
+        assert!(!is_session_transcript_within(&big, 1024).await);
+    }
+
     // -----------------------------------------------------------------------
     // Phase 12 hardening: missing ~/.claude/ directory
     // -----------------------------------------------------------------------
@@ -138,4 +292,20 @@ mod tests {
         let result = all_log_dirs_in(home.path()).await;
         assert!(result.is_empty());
     }
+
+    #[test]
+    fn project_dir_matches_the_repo_and_its_subdirectories() {
+        let repo = Path::new("/Users/foo/my.repo");
+        assert!(project_dir_may_match("-Users-foo-my-repo", repo, false));
+        assert!(project_dir_may_match("-Users-foo-my.repo", repo, false));
+        assert!(project_dir_may_match("-Users-foo-my-repo-src", repo, false));
+        assert!(!project_dir_may_match(
+            "-Users-foo-my-repository",
+            repo,
+            false
+        ));
+        assert!(!project_dir_may_match("-Users-foo-other", repo, false));
+        assert!(!project_dir_may_match("-Users-foo", repo, false));
+        assert!(project_dir_may_match("-Users-foo", repo, true));
+    }
 }
@@ -96,7 +96,7 @@ async fn collect_dirs_with_jsonl(root: &Path, results: &mut Vec<PathBuf>) {
                 stack.push(path);
             } else if file_type.is_file()
                 && !has_jsonl
-                && path.extension().and_then(|e| e.to_str()) == Some("jsonl")
+                && super::log_extension(&path).as_deref() == Some("jsonl")
             {
                 has_jsonl = true;
             }
@@ -2,6 +2,9 @@
 //!
 //! Discovers AI coding agent session logs on disk
 //! and filters candidate files by modification time relative to a cutoff window.
+//!
+//! Rotated logs compressed with gzip (`session.jsonl.gz`) are discovered
+//! alongside plain ones and decompressed when read; see [`read_session_file`].
 
 pub mod amp_code;
 pub mod antigravity;
@@ -46,6 +49,14 @@ impl SessionLog {
             SessionSource::Inline { label, .. } => label.clone(),
         }
     }
+
+    /// The log file on disk, for sources that have one.
+    pub fn file_path(&self) -> Option<&Path> {
+        match &self.source {
+            SessionSource::File(path) => Some(path),
+            SessionSource::Inline { .. } => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -75,8 +86,8 @@ pub async fn recent_files_with_exts(
             let path = entry.path();
 
             // Only consider files with matching extensions
-            let ext = match path.extension().and_then(|e| e.to_str()) {
-                Some(e) => e.to_ascii_lowercase(),
+            let ext = match log_extension(&path) {
+                Some(e) => e,
                 None => continue,
             };
             if !exts
@@ -136,8 +147,10 @@ pub async fn collect_dirs_with_exts(root: &Path, results: &mut Vec<PathBuf>, ext
                 stack.push(path);
             } else if file_type.is_file()
                 && !has_match
-                && let Some(ext) = path.extension().and_then(|e| e.to_str())
-                && exts.iter().any(|allowed| allowed.eq_ignore_ascii_case(ext))
+                && let Some(ext) = log_extension(&path)
+                && exts
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(&ext))
             {
                 has_match = true;
             }
@@ -149,6 +162,55 @@ pub async fn collect_dirs_with_exts(root: &Path, results: &mut Vec<PathBuf>, ext
     }
 }
 
+/// Whether `path` is a gzip-compressed log, such as `session.jsonl.gz`.
+pub fn is_gzip_log(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("gz"))
+}
+
+/// The lowercased extension naming a log's format: `jsonl` for both
+/// `session.jsonl` and `session.jsonl.gz`.
+pub fn log_extension(path: &Path) -> Option<String> {
+    let path = if is_gzip_log(path) {
+        Path::new(path.file_stem()?)
+    } else {
+        path
+    };
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+}
+
+/// Read a session log file, decompressing gzip logs as they are read.
+///
+/// Decompressed output is capped at [`max_session_log_bytes`], like plain
+/// logs, so a small archive cannot expand without bound.
+pub async fn read_session_file(path: &Path) -> std::io::Result<String> {
+    if !is_gzip_log(path) {
+        return tokio::fs::read_to_string(path).await;
+    }
+    let path = path.to_path_buf();
+    let limit = max_session_log_bytes();
+    tokio::task::spawn_blocking(move || {
+        use std::io::Read;
+        let file = std::fs::File::open(&path)?;
+        let mut content = String::new();
+        flate2::read::MultiGzDecoder::new(std::io::BufReader::new(file))
+            .take(limit + 1)
+            .read_to_string(&mut content)?;
+        if content.len() as u64 > limit {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("{} decompresses to more than {limit} bytes", path.display()),
+            ));
+        }
+        Ok(content)
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
 /// Resolve the user's home directory.
 ///
 /// Returns `None` if the home directory cannot be determined.
@@ -268,6 +330,106 @@ pub async fn discover_recent_sessions(now: i64, since_secs: i64) -> Vec<SessionL
     results
 }
 
+/// Collect recent session logs for a single agent.
+pub async fn discover_recent_sessions_for_agent(
+    agent: &AgentType,
+    now: i64,
+    since_secs: i64,
+) -> Vec<SessionLog> {
+    match agent {
+        AgentType::Claude => {
+            claude::ClaudeExplorer
+                .discover_recent(now, since_secs)
+                .await
+        }
+        AgentType::Codex => codex::CodexExplorer.discover_recent(now, since_secs).await,
+        AgentType::Cursor => {
+            cursor::CursorExplorer
+                .discover_recent(now, since_secs)
+                .await
+        }
+        AgentType::Copilot => {
+            copilot::CopilotExplorer
+                .discover_recent(now, since_secs)
+                .await
+        }
+        AgentType::Cline => cline::ClineExplorer.discover_recent(now, since_secs).await,
+        AgentType::RooCode => {
+            roo_code::RooCodeExplorer
+                .discover_recent(now, since_secs)
+                .await
+        }
+        AgentType::OpenCode => {
+            opencode::OpenCodeExplorer
+                .discover_recent(now, since_secs)
+                .await
+        }
+        AgentType::Kiro => kiro::KiroExplorer.discover_recent(now, since_secs).await,
+        AgentType::AmpCode => {
+            amp_code::AmpCodeExplorer
+                .discover_recent(now, since_secs)
+                .await
+        }
+        AgentType::Antigravity => {
+            antigravity::AntigravityExplorer
+                .discover_recent(now, since_secs)
+                .await
+        }
+        AgentType::Windsurf => {
+            windsurf::WindsurfExplorer
+                .discover_recent(now, since_secs)
+                .await
+        }
+        AgentType::Warp => warp::WarpExplorer.discover_recent(now, since_secs).await,
+    }
+}
+
+/// Session log files larger than this are skipped instead of read into memory.
+pub const DEFAULT_MAX_SESSION_LOG_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Size limit for session log files, overridable with
+/// `CADENCE_MAX_SESSION_LOG_BYTES`.
+pub fn max_session_log_bytes() -> u64 {
+    std::env::var("CADENCE_MAX_SESSION_LOG_BYTES")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_MAX_SESSION_LOG_BYTES)
+}
+
+/// A session log file skipped for exceeding the size limit.
+#[derive(Debug, Clone)]
+pub struct OversizedLog {
+    pub path: PathBuf,
+    pub bytes: u64,
+}
+
+/// Split `logs` into those safe to read and files larger than `max_bytes`.
+///
+/// Inline sources are already in memory and always kept. Files whose size
+/// cannot be read are kept too; reading them fails the usual way.
+pub async fn partition_oversized(
+    logs: Vec<SessionLog>,
+    max_bytes: u64,
+) -> (Vec<SessionLog>, Vec<OversizedLog>) {
+    let mut kept = Vec::with_capacity(logs.len());
+    let mut oversized = Vec::new();
+    for log in logs {
+        if let SessionSource::File(path) = &log.source
+            && let Ok(metadata) = tokio::fs::metadata(path).await
+            && metadata.len() > max_bytes
+        {
+            oversized.push(OversizedLog {
+                path: path.clone(),
+                bytes: metadata.len(),
+            });
+            continue;
+        }
+        kept.push(log);
+    }
+    (kept, oversized)
+}
+
 /// Set a file's modification time to a specific Unix epoch timestamp.
 ///
 /// This is a test helper exposed at the module level for use by submodule tests.
@@ -289,6 +451,90 @@ mod tests {
     use serial_test::serial;
     use tempfile::TempDir;
 
+    fn write_gzip(path: &Path, content: &str) {
+        use std::io::Write;
+        let file = std::fs::File::create(path).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        encoder.write_all(content.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn gzip_logs_are_discovered_and_decompressed() {
+        let dir = TempDir::new().unwrap();
+        let session_id = "0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a12";
+        let rotated = dir.path().join(format!("rollout-{session_id}.jsonl.gz"));
+        let content = format!(
+            "{{\"type\":\"session_meta\",\"payload\":{{\"id\":\"{session_id}\",\"cwd\":\"/repo\"}}}}\n"
+        );
+        write_gzip(&rotated, &content);
+        std::fs::write(dir.path().join("notes.txt.gz"), "not a log").unwrap();
+        assert_eq!(log_extension(&rotated).as_deref(), Some("jsonl"));
+
+        let now = std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let found = recent_files_with_exts(&[dir.path().to_path_buf()], now, 60, &["jsonl"]).await;
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].path, rotated);
+
+        assert_eq!(read_session_file(&rotated).await.unwrap(), content);
+        let metadata =
+            crate::scanner::parse_agent_session_metadata(&AgentType::Codex, &rotated).await;
+        assert_eq!(metadata.session_id.as_deref(), Some(session_id));
+        assert_eq!(metadata.cwd.as_deref(), Some("/repo"));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn gzip_logs_are_capped_after_decompression() {
+        let dir = TempDir::new().unwrap();
+        let bomb = dir.path().join("big.jsonl.gz");
+        write_gzip(&bomb, &"x".repeat(4096));
+        unsafe { std::env::set_var("CADENCE_MAX_SESSION_LOG_BYTES", "1024") };
+        let result = read_session_file(&bomb).await;
+        unsafe { std::env::remove_var("CADENCE_MAX_SESSION_LOG_BYTES") };
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn oversized_session_files_are_partitioned_out() {
+        let dir = TempDir::new().unwrap();
+        let small = dir.path().join("small.jsonl");
+        let large = dir.path().join("large.jsonl");
+        std::fs::write(&small, vec![b'x'; 64]).unwrap();
+        std::fs::write(&large, vec![b'x'; 65]).unwrap();
+        let log = |source| SessionLog {
+            agent_type: AgentType::Claude,
+            source,
+            updated_at: None,
+        };
+
+        let (kept, oversized) = partition_oversized(
+            vec![
+                log(SessionSource::File(small.clone())),
+                log(SessionSource::File(large.clone())),
+                log(SessionSource::Inline {
+                    label: "inline".to_string(),
+                    content: "x".repeat(100),
+                }),
+            ],
+            64,
+        )
+        .await;
+
+        let kept: Vec<String> = kept.iter().map(SessionLog::source_label).collect();
+        assert_eq!(
+            kept,
+            [small.to_string_lossy().to_string(), "inline".to_string()]
+        );
+        assert_eq!(oversized.len(), 1);
+        assert_eq!(oversized[0].path, large);
+        assert_eq!(oversized[0].bytes, 65);
+    }
+
     // -----------------------------------------------------------------------
     // recent_files
     // -----------------------------------------------------------------------
@@ -4,11 +4,15 @@
 //! - Public key retrieval for encryption setup
 //! - CLI auth exchange + revoke
 //! - Backfill-complete reporting
+//! - Commit attribution events
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::path::Path;
 use std::time::Duration;
 
+use crate::config::CliConfig;
+
 // ---------------------------------------------------------------------------
 // Endpoint path constants
 // ---------------------------------------------------------------------------
@@ -17,6 +21,11 @@ const KEYS_PUBLIC_PATH: &str = "/api/keys/public";
 const AUTH_EXCHANGE_PATH: &str = "/api/auth/exchange";
 const AUTH_REVOKE_PATH: &str = "/api/auth";
 const BACKFILL_COMPLETE_PATH: &str = "/api/onboarding/backfill-complete";
+const ATTRIBUTION_EVENTS_PATH: &str = "/api/attribution/events";
+
+/// Longest `Retry-After` delay a GET request will sleep through before retrying.
+/// Longer delays are surfaced to the user instead of blocking the CLI.
+const MAX_RETRY_AFTER_WAIT: Duration = Duration::from_secs(30);
 
 // ---------------------------------------------------------------------------
 // Response DTOs
@@ -70,6 +79,30 @@ pub struct BackfillCompleteResponse {
     pub next_step: String,
 }
 
+/// One commit attributed to an agent session, for `POST /api/attribution/events`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttributionEvent {
+    pub commit: String,
+    pub agent: String,
+    pub session_id: String,
+    /// Organization from the repository's push remote, when it has one.
+    pub repo_org: Option<String>,
+    /// RFC 3339 time the commit was attributed.
+    pub timestamp: String,
+}
+
+/// Request body for `POST /api/attribution/events`.
+#[derive(Debug, Serialize)]
+struct AttributionEventsRequest<'a> {
+    events: &'a [AttributionEvent],
+}
+
+/// Data payload from `POST /api/attribution/events`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AttributionEventsResponse {
+    pub accepted: usize,
+}
+
 /// Standard API response envelope used by backend endpoints.
 #[derive(Debug, Deserialize)]
 struct ApiResponseEnvelope<T> {
@@ -77,36 +110,63 @@ struct ApiResponseEnvelope<T> {
 }
 
 // ---------------------------------------------------------------------------
-// Classified authenticated request errors
+// Classified API errors
 // ---------------------------------------------------------------------------
 
-/// Classified failures for authenticated CLI requests.
+/// Classified failures for Cadence API requests.
+///
+/// Every [`ApiClient`] method returns this, so callers can react to the kind
+/// of failure (re-authenticate, back off, keep data locally) without
+/// matching on message text. `Display` gives the user-facing message; it
+/// converts into `anyhow::Error` at the command boundary.
 #[derive(Debug)]
-pub enum AuthenticatedRequestError {
+pub enum ApiError {
+    /// 401: the token or credentials were rejected.
     Unauthorized,
-    NotFound,
-    Server(String),
+    /// 429, with the server's `Retry-After` delay when it sent one.
+    RateLimited {
+        retry_after: Option<Duration>,
+    },
+    NotFound(String),
     BadRequest(String),
+    /// Any 5xx response.
+    Server(String),
+    /// The request could not be sent or the response could not be read.
     Network(String),
+    /// The response body was not the expected JSON.
     Parse(String),
+    /// Any other status.
     Unexpected(String),
 }
 
-impl std::fmt::Display for AuthenticatedRequestError {
+impl std::fmt::Display for ApiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Unauthorized => write!(f, "unauthorized"),
-            Self::NotFound => write!(f, "not_found"),
-            Self::Server(msg) => write!(f, "server_error: {msg}"),
-            Self::BadRequest(msg) => write!(f, "bad_request: {msg}"),
-            Self::Network(msg) => write!(f, "network_error: {msg}"),
-            Self::Parse(msg) => write!(f, "parse_error: {msg}"),
-            Self::Unexpected(msg) => write!(f, "unexpected_error: {msg}"),
+            Self::Unauthorized => write!(f, "Unauthorized: API credentials rejected."),
+            Self::RateLimited {
+                retry_after: Some(wait),
+            } => write!(
+                f,
+                "Rate limited: too many requests. Retry in {} seconds.",
+                wait.as_secs().max(1)
+            ),
+            Self::RateLimited { retry_after: None } => {
+                write!(
+                    f,
+                    "Rate limited: too many requests. Wait a moment and retry."
+                )
+            }
+            Self::NotFound(msg) => write!(f, "Not found: {msg}"),
+            Self::BadRequest(msg) => write!(f, "Bad request: {msg}"),
+            Self::Server(msg) => write!(f, "Server error: {msg}"),
+            Self::Network(msg) => write!(f, "Network error: {msg}"),
+            Self::Parse(msg) => write!(f, "Invalid response: {msg}"),
+            Self::Unexpected(msg) => write!(f, "Unexpected response: {msg}"),
         }
     }
 }
 
-impl std::error::Error for AuthenticatedRequestError {}
+impl std::error::Error for ApiError {}
 
 // ---------------------------------------------------------------------------
 // ApiClient
@@ -121,45 +181,57 @@ pub struct ApiClient {
 }
 
 impl ApiClient {
-    /// Create a new API client.
+    /// Create an API client honoring the network settings in `cfg`.
     ///
     /// `base_url` is trimmed and stripped of a trailing slash to prevent
     /// double-slash issues when joining endpoint paths.
-    pub fn new(base_url: &str) -> Self {
-        let normalized = base_url.trim().trim_end_matches('/').to_string();
-        let mut headers = reqwest::header::HeaderMap::new();
-        headers.insert(
-            reqwest::header::HeaderName::from_static("x-cadence-cli-version"),
-            reqwest::header::HeaderValue::from_static(env!("CARGO_PKG_VERSION")),
-        );
-        let client = reqwest::Client::builder()
-            .default_headers(headers)
+    ///
+    /// With `ca_bundle` set, only the certificates in that PEM file are
+    /// trusted, which pins the API server to an internal CA; otherwise the
+    /// built-in roots are used. `proxy` routes every request through that
+    /// proxy; without it, `HTTPS_PROXY`/`HTTP_PROXY`/`NO_PROXY` apply.
+    pub fn configured(base_url: &str, cfg: &CliConfig) -> Result<Self> {
+        let mut builder = client_builder();
+        if let Some(path) = cfg.ca_bundle.as_deref() {
+            builder = builder.tls_built_in_root_certs(false);
+            for cert in load_ca_bundle(Path::new(path))? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        if let Some(proxy) = cfg.proxy.as_deref() {
+            crate::config::validate_proxy_url(proxy)?;
+            let proxy = reqwest::Proxy::all(proxy)
+                .with_context(|| format!("invalid proxy URL '{proxy}'"))?;
+            builder = builder.proxy(proxy);
+        }
+        let client = builder
             .build()
-            .expect("build Cadence API HTTP client");
+            .context("failed to build Cadence API HTTP client")?;
+        Ok(Self::with_client(base_url, client))
+    }
+
+    fn with_client(base_url: &str, client: reqwest::Client) -> Self {
         Self {
             client,
-            base_url: normalized,
+            base_url: base_url.trim().trim_end_matches('/').to_string(),
         }
     }
 
+    /// The normalized base URL requests are sent to.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
     // -----------------------------------------------------------------------
     // Public endpoint methods
     // -----------------------------------------------------------------------
 
     /// Fetch the current API public key.
-    pub async fn get_api_public_key(&self) -> Result<ApiPublicKey> {
+    pub async fn get_api_public_key(&self) -> Result<ApiPublicKey, ApiError> {
         let url = self.url(KEYS_PUBLIC_PATH);
-        let resp = self
-            .client
-            .get(&url)
-            .send()
-            .await
-            .with_context(|| format!("failed to connect to API at {url}"))?;
-
-        let body = map_http_error(resp).await?;
-        let envelope: ApiResponseEnvelope<ApiPublicKey> =
-            serde_json::from_str(&body).context("failed to parse api public key response")?;
-        Ok(envelope.data)
+        let resp = self.get_honoring_retry_after(&url).await?;
+        let body = read_success_body(resp).await?;
+        parse_envelope(&body, "api public key response")
     }
 
     /// Exchange a short-lived CLI exchange code for a long-lived CLI JWT.
@@ -167,7 +239,7 @@ impl ApiClient {
         &self,
         code: &str,
         timeout: Duration,
-    ) -> Result<CliTokenExchangeResult> {
+    ) -> Result<CliTokenExchangeResult, ApiError> {
         let url = self.url(AUTH_EXCHANGE_PATH);
         let resp = self
             .client
@@ -176,20 +248,13 @@ impl ApiClient {
             .json(&ExchangeRequest { code })
             .send()
             .await
-            .with_context(|| format!("failed to connect to API at {url}"))?;
-
-        let body = map_http_error(resp).await?;
-        let envelope: ApiResponseEnvelope<CliTokenExchangeResult> =
-            serde_json::from_str(&body).context("failed to parse auth exchange response")?;
-        Ok(envelope.data)
+            .map_err(|e| connect_error(&url, e))?;
+        let body = read_success_body(resp).await?;
+        parse_envelope(&body, "auth exchange response")
     }
 
     /// Revoke a bearer token via `DELETE /api/auth`.
-    pub async fn revoke_token(
-        &self,
-        token: &str,
-        timeout: Duration,
-    ) -> std::result::Result<(), AuthenticatedRequestError> {
+    pub async fn revoke_token(&self, token: &str, timeout: Duration) -> Result<(), ApiError> {
         let url = self.url(AUTH_REVOKE_PATH);
         let resp = self
             .client
@@ -198,15 +263,8 @@ impl ApiClient {
             .timeout(timeout)
             .send()
             .await
-            .map_err(|e| AuthenticatedRequestError::Network(e.to_string()))?;
-
-        if resp.status().is_success() {
-            return Ok(());
-        }
-
-        let status = resp.status().as_u16();
-        let body = resp.text().await.unwrap_or_default();
-        Err(map_authenticated_http_error(status, &body))
+            .map_err(|e| connect_error(&url, e))?;
+        read_success_body(resp).await.map(|_| ())
     }
 
     /// Report backfill completion to onboarding state.
@@ -215,7 +273,7 @@ impl ApiClient {
         token: &str,
         report: &BackfillCompleteRequest,
         timeout: Duration,
-    ) -> std::result::Result<BackfillCompleteResponse, AuthenticatedRequestError> {
+    ) -> Result<BackfillCompleteResponse, ApiError> {
         let url = self.url(BACKFILL_COMPLETE_PATH);
         let resp = self
             .client
@@ -225,22 +283,30 @@ impl ApiClient {
             .json(report)
             .send()
             .await
-            .map_err(|e| AuthenticatedRequestError::Network(e.to_string()))?;
-
-        if !resp.status().is_success() {
-            let status = resp.status().as_u16();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(map_authenticated_http_error(status, &body));
-        }
+            .map_err(|e| connect_error(&url, e))?;
+        let body = read_success_body(resp).await?;
+        parse_envelope(&body, "backfill-complete response")
+    }
 
-        let body = resp
-            .text()
+    /// Report commit attribution events.
+    pub async fn report_attribution(
+        &self,
+        token: &str,
+        events: &[AttributionEvent],
+        timeout: Duration,
+    ) -> Result<AttributionEventsResponse, ApiError> {
+        let url = self.url(ATTRIBUTION_EVENTS_PATH);
+        let resp = self
+            .client
+            .post(&url)
+            .bearer_auth(token)
+            .timeout(timeout)
+            .json(&AttributionEventsRequest { events })
+            .send()
             .await
-            .map_err(|e| AuthenticatedRequestError::Network(e.to_string()))?;
-        let envelope: ApiResponseEnvelope<BackfillCompleteResponse> =
-            serde_json::from_str(&body)
-                .map_err(|e| AuthenticatedRequestError::Parse(e.to_string()))?;
-        Ok(envelope.data)
+            .map_err(|e| connect_error(&url, e))?;
+        let body = read_success_body(resp).await?;
+        parse_envelope(&body, "attribution events response")
     }
 
     // -----------------------------------------------------------------------
@@ -251,6 +317,59 @@ impl ApiClient {
     fn url(&self, path: &str) -> String {
         format!("{}{}", self.base_url, path)
     }
+
+    /// Send a GET request, retrying once when the server answers 429 with a
+    /// short enough `Retry-After`.
+    ///
+    /// GETs are idempotent, so a single delayed retry is safe. If the header is
+    /// missing or asks for more than [`MAX_RETRY_AFTER_WAIT`], the 429 response
+    /// is returned as-is for [`read_success_body`] to report.
+    async fn get_honoring_retry_after(&self, url: &str) -> Result<reqwest::Response, ApiError> {
+        let resp = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| connect_error(url, e))?;
+
+        if resp.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Ok(resp);
+        }
+        let Some(wait) = retry_after_from_headers(resp.headers()) else {
+            return Ok(resp);
+        };
+        if wait > MAX_RETRY_AFTER_WAIT {
+            return Ok(resp);
+        }
+
+        tokio::time::sleep(wait).await;
+        self.client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| connect_error(url, e))
+    }
+}
+
+fn client_builder() -> reqwest::ClientBuilder {
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert(
+        reqwest::header::HeaderName::from_static("x-cadence-cli-version"),
+        reqwest::header::HeaderValue::from_static(env!("CARGO_PKG_VERSION")),
+    );
+    reqwest::Client::builder().default_headers(headers)
+}
+
+/// Root certificates from the PEM bundle at `path`; an error if it holds none.
+fn load_ca_bundle(path: &Path) -> Result<Vec<reqwest::Certificate>> {
+    let pem = std::fs::read(path)
+        .with_context(|| format!("failed to read CA bundle at {}", path.display()))?;
+    let certs = reqwest::Certificate::from_pem_bundle(&pem)
+        .with_context(|| format!("failed to parse CA bundle at {}", path.display()))?;
+    if certs.is_empty() {
+        anyhow::bail!("CA bundle at {} holds no certificates", path.display());
+    }
+    Ok(certs)
 }
 
 fn deserialize_optional_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
@@ -271,46 +390,76 @@ where
 // HTTP error mapping (FR-8)
 // ---------------------------------------------------------------------------
 
-/// Read a response body and return it as a string, or map non-success status
-/// codes to user-friendly errors.
-async fn map_http_error(resp: reqwest::Response) -> Result<String> {
+/// Read a response body and return it as a string, or classify a
+/// non-success status as an [`ApiError`].
+async fn read_success_body(resp: reqwest::Response) -> Result<String, ApiError> {
     let status = resp.status();
     if status.is_success() {
-        let body = resp.text().await.unwrap_or_default();
-        return Ok(body);
+        return resp
+            .text()
+            .await
+            .map_err(|e| ApiError::Network(e.to_string()));
     }
 
+    let retry_after = retry_after_from_headers(resp.headers());
     let body = resp.text().await.unwrap_or_default();
-
-    match status.as_u16() {
-        401 => anyhow::bail!("Unauthorized: API credentials rejected."),
-        400 => {
-            let detail = extract_error_message(&body);
-            anyhow::bail!("Bad request: {detail}");
-        }
-        404 => {
-            let detail = extract_error_message(&body);
-            anyhow::bail!("Not found: {detail}");
-        }
-        500..=599 => {
-            let detail = extract_error_message(&body);
-            anyhow::bail!("Server error: {detail}");
-        }
-        _ => {
-            anyhow::bail!("Unexpected response (HTTP {status}): {body}");
-        }
-    }
+    Err(classify_status(status.as_u16(), retry_after, &body))
 }
 
-fn map_authenticated_http_error(status: u16, body: &str) -> AuthenticatedRequestError {
+fn classify_status(status: u16, retry_after: Option<Duration>, body: &str) -> ApiError {
     let detail = extract_error_message(body);
     match status {
-        401 => AuthenticatedRequestError::Unauthorized,
-        400 | 409 => AuthenticatedRequestError::BadRequest(detail),
-        404 => AuthenticatedRequestError::NotFound,
-        500..=599 => AuthenticatedRequestError::Server(detail),
-        _ => AuthenticatedRequestError::Unexpected(format!("HTTP {status}: {detail}")),
+        401 => ApiError::Unauthorized,
+        429 => ApiError::RateLimited { retry_after },
+        400 | 409 => ApiError::BadRequest(detail),
+        404 => ApiError::NotFound(detail),
+        500..=599 => ApiError::Server(detail),
+        _ => ApiError::Unexpected(format!("HTTP {status}: {detail}")),
+    }
+}
+
+fn connect_error(url: &str, err: reqwest::Error) -> ApiError {
+    ApiError::Network(format!("failed to connect to API at {url}: {err}"))
+}
+
+/// Parse the `data` field of a standard response envelope.
+///
+/// Only the enveloped form is accepted. A bare payload without `data` is
+/// reported as such rather than as a missing field of the payload type.
+fn parse_envelope<T: serde::de::DeserializeOwned>(body: &str, what: &str) -> Result<T, ApiError> {
+    let value: serde_json::Value = serde_json::from_str(body)
+        .map_err(|e| ApiError::Parse(format!("failed to parse {what}: {e}")))?;
+    if value.get("data").is_none() {
+        return Err(ApiError::Parse(format!(
+            "failed to parse {what}: expected a {{\"data\": ...}} envelope"
+        )));
+    }
+    serde_json::from_value::<ApiResponseEnvelope<T>>(value)
+        .map(|envelope| envelope.data)
+        .map_err(|e| ApiError::Parse(format!("failed to parse {what}: {e}")))
+}
+
+/// Read the `Retry-After` header from a response, if present and parseable.
+fn retry_after_from_headers(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value, time::OffsetDateTime::now_utc())
+}
+
+/// Parse a `Retry-After` value relative to `now`.
+///
+/// Accepts both forms allowed by RFC 9110: a delay in whole seconds, or an
+/// HTTP-date (`Wed, 21 Oct 2015 07:28:00 GMT`). Dates in the past yield a
+/// zero delay.
+fn parse_retry_after(value: &str, now: time::OffsetDateTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
     }
+
+    let at =
+        time::OffsetDateTime::parse(value, &time::format_description::well_known::Rfc2822).ok()?;
+    let delta = (at - now).whole_seconds().max(0) as u64;
+    Some(Duration::from_secs(delta))
 }
 
 /// Try to extract a `message` or `error` field from a JSON error body.
@@ -343,10 +492,78 @@ fn extract_error_message(body: &str) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve one canned raw HTTP response per accepted connection, in order.
+    async fn spawn_mock_server(responses: Vec<String>) -> String {
+        spawn_recording_server(responses).await.0
+    }
+
+    /// Like [`spawn_mock_server`], but also hands back each raw request it
+    /// received, headers and body, in order.
+    async fn spawn_recording_server(
+        responses: Vec<String>,
+    ) -> (String, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock server");
+        let addr = listener.local_addr().expect("local addr");
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            for response in responses {
+                let (mut stream, _) = listener.accept().await.expect("accept connection");
+                let _ = tx.send(read_request(&mut stream).await);
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            }
+        });
+        (format!("http://{addr}"), rx)
+    }
+
+    /// Read one request: the headers, then as much body as `Content-Length`
+    /// announces.
+    async fn read_request(stream: &mut tokio::net::TcpStream) -> String {
+        let mut raw = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap_or(0);
+            if n == 0 {
+                break;
+            }
+            raw.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&raw);
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text[..end]
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().ok())?
+                    })
+                    .unwrap_or(0);
+                if raw.len() >= end + 4 + length {
+                    break;
+                }
+            }
+        }
+        String::from_utf8_lossy(&raw).to_string()
+    }
+
+    fn test_client(base_url: &str) -> ApiClient {
+        ApiClient::configured(base_url, &CliConfig::default()).expect("build client")
+    }
+
+    fn raw_response(status_line: &str, extra_headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status_line}\r\nContent-Type: application/json\r\n{extra_headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
 
     #[test]
     fn url_joins_paths() {
-        let client = ApiClient::new("https://api.example.com/");
+        let client = test_client("https://api.example.com/");
         assert_eq!(
             client.url("/api/keys/public"),
             "https://api.example.com/api/keys/public"
@@ -354,18 +571,297 @@ mod tests {
     }
 
     #[test]
-    fn authenticated_error_maps_statuses() {
+    fn configured_client_loads_the_ca_bundle() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mut cfg = CliConfig {
+            ca_bundle: Some(
+                concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/tls/ca.pem").to_string(),
+            ),
+            ..Default::default()
+        };
+        let client = ApiClient::configured("https://api.example.com/", &cfg).unwrap();
+        assert_eq!(client.base_url(), "https://api.example.com");
+
+        let empty = tmp.path().join("empty.pem");
+        std::fs::write(&empty, "not a certificate\n").unwrap();
+        cfg.ca_bundle = Some(empty.to_string_lossy().to_string());
+        let err = ApiClient::configured("https://api.example.com", &cfg)
+            .err()
+            .expect("a bundle without certificates is rejected");
+        assert!(format!("{err:#}").contains("holds no certificates"));
+
+        cfg.ca_bundle = Some(tmp.path().join("missing.pem").to_string_lossy().to_string());
+        assert!(ApiClient::configured("https://api.example.com", &cfg).is_err());
+    }
+
+    #[tokio::test]
+    async fn configured_proxy_receives_api_requests() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind proxy");
+        let proxy = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.expect("accept connection");
+            let mut buf = [0u8; 4096];
+            let n = stream.read(&mut buf).await.unwrap_or(0);
+            let _ = tx.send(String::from_utf8_lossy(&buf[..n]).to_string());
+            let body = r#"{"data":{"fingerprint":"via-proxy","armored_public_key":"KEY"}}"#;
+            let _ = stream
+                .write_all(raw_response("200 OK", "", body).as_bytes())
+                .await;
+            let _ = stream.shutdown().await;
+        });
+
+        let cfg = CliConfig {
+            proxy: Some(proxy),
+            ..Default::default()
+        };
+        let key = ApiClient::configured("http://api.example.invalid", &cfg)
+            .unwrap()
+            .get_api_public_key()
+            .await
+            .expect("request through proxy");
+        assert_eq!(key.fingerprint, "via-proxy");
+        let request = rx.await.unwrap();
+        assert!(
+            request.starts_with("GET http://api.example.invalid/api/keys/public "),
+            "{request}"
+        );
+    }
+
+    #[test]
+    fn classify_status_maps_statuses() {
         assert!(matches!(
-            map_authenticated_http_error(401, ""),
-            AuthenticatedRequestError::Unauthorized
+            classify_status(401, None, ""),
+            ApiError::Unauthorized
         ));
         assert!(matches!(
-            map_authenticated_http_error(404, ""),
-            AuthenticatedRequestError::NotFound
+            classify_status(404, None, ""),
+            ApiError::NotFound(_)
         ));
         assert!(matches!(
-            map_authenticated_http_error(503, "{\"error\":\"bad\"}"),
-            AuthenticatedRequestError::Server(_)
+            classify_status(429, Some(Duration::from_secs(5)), ""),
+            ApiError::RateLimited {
+                retry_after: Some(_)
+            }
+        ));
+        assert!(matches!(
+            classify_status(409, None, "{\"message\":\"conflict\"}"),
+            ApiError::BadRequest(ref msg) if msg == "conflict"
+        ));
+        assert!(matches!(
+            classify_status(503, None, "{\"error\":\"bad\"}"),
+            ApiError::Server(ref msg) if msg == "bad"
+        ));
+        assert!(matches!(
+            classify_status(418, None, ""),
+            ApiError::Unexpected(_)
+        ));
+    }
+
+    #[test]
+    fn parse_retry_after_accepts_seconds_and_http_date() {
+        let now = time::OffsetDateTime::from_unix_timestamp(1_445_412_480).unwrap();
+        assert_eq!(
+            parse_retry_after("120", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn rate_limit_reports_retry_after() {
+        let base = spawn_mock_server(vec![raw_response(
+            "429 Too Many Requests",
+            "Retry-After: 45\r\n",
+            r#"{"error":"slow down"}"#,
+        )])
+        .await;
+        let resp = reqwest::get(format!("{base}/x")).await.expect("send");
+
+        let err = read_success_body(resp).await.unwrap_err();
+        assert!(matches!(
+            err,
+            ApiError::RateLimited {
+                retry_after: Some(wait)
+            } if wait == Duration::from_secs(45)
         ));
+        assert_eq!(
+            err.to_string(),
+            "Rate limited: too many requests. Retry in 45 seconds."
+        );
+    }
+
+    #[tokio::test]
+    async fn get_api_public_key_retries_after_short_rate_limit() {
+        let body = r#"{"data":{"fingerprint":"abc","armored_public_key":"KEY"}}"#;
+        let base = spawn_mock_server(vec![
+            raw_response("429 Too Many Requests", "Retry-After: 0\r\n", ""),
+            raw_response("200 OK", "", body),
+        ])
+        .await;
+
+        let key = test_client(&base)
+            .get_api_public_key()
+            .await
+            .expect("retry should succeed");
+        assert_eq!(key.fingerprint, "abc");
+    }
+
+    #[tokio::test]
+    async fn get_api_public_key_surfaces_long_rate_limit_without_waiting() {
+        let base = spawn_mock_server(vec![raw_response(
+            "429 Too Many Requests",
+            "Retry-After: 3600\r\n",
+            "",
+        )])
+        .await;
+
+        let err = test_client(&base)
+            .get_api_public_key()
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Retry in 3600 seconds"), "got: {err}");
+    }
+
+    #[tokio::test]
+    async fn report_attribution_parses_accepted_count_and_maps_errors() {
+        let event = AttributionEvent {
+            commit: "abc123".to_string(),
+            agent: "codex".to_string(),
+            session_id: "s-1".to_string(),
+            repo_org: Some("example-org".to_string()),
+            timestamp: "2026-10-16T12:00:00Z".to_string(),
+        };
+        let base = spawn_mock_server(vec![
+            raw_response("200 OK", "", r#"{"data":{"accepted":1}}"#),
+            raw_response("401 Unauthorized", "", r#"{"error":"expired"}"#),
+        ])
+        .await;
+        let client = test_client(&base);
+
+        let response = client
+            .report_attribution(
+                "token",
+                std::slice::from_ref(&event),
+                Duration::from_secs(5),
+            )
+            .await
+            .expect("report should succeed");
+        assert_eq!(response.accepted, 1);
+
+        let err = client
+            .report_attribution("token", &[event], Duration::from_secs(5))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ApiError::Unauthorized));
+    }
+
+    #[tokio::test]
+    async fn get_api_public_key_maps_unauthorized_and_malformed_bodies() {
+        let base = spawn_mock_server(vec![
+            raw_response("401 Unauthorized", "", r#"{"error":"no"}"#),
+            raw_response("200 OK", "", "<html>gateway</html>"),
+            raw_response("200 OK", "", r#"{"fingerprint":"abc"}"#),
+        ])
+        .await;
+        let client = test_client(&base);
+
+        let err = client.get_api_public_key().await.unwrap_err();
+        assert!(matches!(err, ApiError::Unauthorized), "{err}");
+        let err = client.get_api_public_key().await.unwrap_err();
+        assert!(matches!(err, ApiError::Parse(_)), "{err}");
+        let err = client.get_api_public_key().await.unwrap_err();
+        assert!(matches!(err, ApiError::Parse(_)), "{err}");
+        assert_eq!(
+            err.to_string(),
+            r#"Invalid response: failed to parse api public key response: expected a {"data": ...} envelope"#
+        );
+    }
+
+    #[tokio::test]
+    async fn exchange_cli_code_posts_the_code_and_maps_errors() {
+        let ok = r#"{"data":{"token":"jwt","login":"octo","expires_at":"2026-11-01T00:00:00Z"}}"#;
+        let (base, mut requests) = spawn_recording_server(vec![
+            raw_response("200 OK", "", ok),
+            raw_response("401 Unauthorized", "", r#"{"error":"code expired"}"#),
+            raw_response("429 Too Many Requests", "Retry-After: 1\r\n", ""),
+            raw_response("200 OK", "", r#"{"data":{"token":"jwt"}}"#),
+        ])
+        .await;
+        let client = test_client(&base);
+        let timeout = Duration::from_secs(5);
+
+        let result = client.exchange_cli_code("abc", timeout).await.unwrap();
+        assert_eq!(result.token, "jwt");
+        assert_eq!(result.login, "octo");
+        let request = requests.recv().await.unwrap();
+        assert!(request.starts_with("POST /api/auth/exchange "), "{request}");
+        assert!(request.ends_with(r#"{"code":"abc"}"#), "{request}");
+
+        let err = client.exchange_cli_code("abc", timeout).await.unwrap_err();
+        assert!(matches!(err, ApiError::Unauthorized), "{err}");
+        // POSTs are not retried, even after a short Retry-After.
+        let err = client.exchange_cli_code("abc", timeout).await.unwrap_err();
+        assert!(
+            matches!(err, ApiError::RateLimited { retry_after: Some(wait) } if wait == Duration::from_secs(1)),
+            "{err}"
+        );
+        let err = client.exchange_cli_code("abc", timeout).await.unwrap_err();
+        assert!(matches!(err, ApiError::Parse(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn revoke_token_sends_the_bearer_token_and_maps_errors() {
+        let (base, mut requests) = spawn_recording_server(vec![
+            raw_response("204 No Content", "", ""),
+            raw_response("401 Unauthorized", "", ""),
+            raw_response("429 Too Many Requests", "", ""),
+            raw_response("502 Bad Gateway", "", r#"{"message":"upstream down"}"#),
+        ])
+        .await;
+        let client = test_client(&base);
+        let timeout = Duration::from_secs(5);
+
+        client.revoke_token("tok-1", timeout).await.unwrap();
+        let request = requests.recv().await.unwrap();
+        assert!(request.starts_with("DELETE /api/auth "), "{request}");
+        assert!(
+            request
+                .to_ascii_lowercase()
+                .contains("authorization: bearer tok-1"),
+            "{request}"
+        );
+
+        let err = client.revoke_token("tok-1", timeout).await.unwrap_err();
+        assert!(matches!(err, ApiError::Unauthorized), "{err}");
+        let err = client.revoke_token("tok-1", timeout).await.unwrap_err();
+        assert!(
+            matches!(err, ApiError::RateLimited { retry_after: None }),
+            "{err}"
+        );
+        let err = client.revoke_token("tok-1", timeout).await.unwrap_err();
+        assert_eq!(err.to_string(), "Server error: upstream down");
+    }
+
+    #[tokio::test]
+    async fn unreachable_server_is_a_network_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        let err = test_client(&base).get_api_public_key().await.unwrap_err();
+        assert!(matches!(err, ApiError::Network(_)), "{err}");
+        assert!(err.to_string().contains(&base), "{err}");
     }
 }
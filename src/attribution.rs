@@ -0,0 +1,396 @@
+//! Opt-in commit attribution events for organization dashboards.
+//!
+//! With `ai.cadence.reportAttribution` enabled, the post-commit hook appends
+//! one event per stored session to a local JSONL queue instead of calling
+//! the API. The queue is sent in batches by the deferred sync worker, or by
+//! the hook itself once [`FLUSH_THRESHOLD`] events are waiting and no hook
+//! has tried in the last [`HOOK_FLUSH_INTERVAL`]. A failed send puts the
+//! events back, so they survive being offline; the queue keeps at most
+//! [`MAX_QUEUED_EVENTS`], dropping the oldest. With the global
+//! `ai.cadence.autopush = never` nothing is sent.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+use crate::api_client::{self, AttributionEvent};
+use crate::settings::Settings;
+use crate::{config, deferred_sync, git, log, push, state};
+
+/// Git config key that opts a user or repository into attribution events.
+pub const REPORT_ATTRIBUTION_KEY: &str = "ai.cadence.reportAttribution";
+
+/// Queued events that make the hook send the queue right away.
+const FLUSH_THRESHOLD: usize = 50;
+
+/// Events per API request.
+const MAX_BATCH: usize = 100;
+
+/// Oldest events beyond this are dropped when the queue is appended to.
+const MAX_QUEUED_EVENTS: usize = 5_000;
+
+/// Minimum time between two sends started by the hook, successful or not,
+/// so an offline machine or a missing login does not cost every commit
+/// [`REPORT_TIMEOUT`].
+const HOOK_FLUSH_INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Sends may run inside the post-commit hook, so they get a short timeout.
+const REPORT_TIMEOUT: Duration = Duration::from_secs(3);
+
+const QUEUE_FILE: &str = "attribution-queue.jsonl";
+
+/// Holds the epoch seconds of the last send the hook started.
+const HOOK_FLUSH_STAMP_FILE: &str = "attribution-flush.stamp";
+
+/// Queue attribution events for `commit` in `repo_root`, made from
+/// `sessions` (agent, session id pairs). Does nothing unless `settings`
+/// enable them.
+pub async fn record_commit(
+    repo_root: &Path,
+    settings: &Settings,
+    commit: &str,
+    sessions: &[(String, String)],
+) {
+    if sessions.is_empty() || !settings.report_attribution {
+        return;
+    }
+    let Ok(dir) = state::state_dir().await else {
+        return;
+    };
+
+    let repo_org = push_remote_org(repo_root).await;
+    let timestamp = crate::note::now_rfc3339();
+    let events: Vec<AttributionEvent> = sessions
+        .iter()
+        .map(|(agent, session_id)| AttributionEvent {
+            commit: commit.to_string(),
+            agent: agent.clone(),
+            session_id: session_id.clone(),
+            repo_org: repo_org.clone(),
+            timestamp: timestamp.clone(),
+        })
+        .collect();
+
+    let queue = dir.join(QUEUE_FILE);
+    match append_events(&queue, &events).await {
+        Ok(queued) if queued >= FLUSH_THRESHOLD && claim_hook_flush(&dir, now_epoch()).await => {
+            if let Err(e) = flush_in(&dir).await {
+                log::debug(&format!("attribution flush failed: {e:#}"));
+            }
+        }
+        Ok(_) => {}
+        Err(e) => log::debug(&format!("could not queue attribution events: {e:#}")),
+    }
+}
+
+/// Record that the hook is starting a send at `now`, unless one was started
+/// less than [`HOOK_FLUSH_INTERVAL`] ago. Returns whether to send.
+async fn claim_hook_flush(dir: &Path, now: i64) -> bool {
+    let stamp = dir.join(HOOK_FLUSH_STAMP_FILE);
+    let last = tokio::fs::read_to_string(&stamp)
+        .await
+        .ok()
+        .and_then(|s| s.trim().parse::<i64>().ok());
+    if last.is_some_and(|last| (0..HOOK_FLUSH_INTERVAL.as_secs() as i64).contains(&(now - last))) {
+        return false;
+    }
+    tokio::fs::write(&stamp, now.to_string()).await.is_ok()
+}
+
+fn now_epoch() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Send queued events, if any. Events that cannot be sent stay queued.
+pub async fn flush() -> Result<usize> {
+    let dir = state::state_dir().await?;
+    flush_in(&dir).await
+}
+
+async fn flush_in(dir: &Path) -> Result<usize> {
+    if push::autopush_disabled_globally().await {
+        log::debug("autopush=never; attribution events stay queued");
+        return Ok(0);
+    }
+    let cfg = config::CliConfig::load().await.unwrap_or_default();
+    let Some(token) = deferred_sync::resolve_cli_notification_token(&cfg).await else {
+        return Ok(0);
+    };
+    let client = api_client::ApiClient::configured(
+        &cfg.resolve_api_url(crate::api_url_override()).url,
+        &cfg,
+    )?;
+    flush_queue(&dir.join(QUEUE_FILE), |batch| {
+        let client = &client;
+        let token = &token;
+        async move {
+            client
+                .report_attribution(token, &batch, REPORT_TIMEOUT)
+                .await
+                .map(|response| response.accepted)
+                .map_err(anyhow::Error::from)
+        }
+    })
+    .await
+}
+
+/// Claim the queue at `queue`, pass it to `send` in batches and re-queue
+/// whatever was not sent. Returns the number of events the API accepted.
+///
+/// The queue is claimed by renaming it, so hooks appending meanwhile start a
+/// fresh file and nothing is lost or sent twice. The claimed file is only
+/// removed once its events are sent or back in the queue.
+async fn flush_queue<F, Fut>(queue: &Path, mut send: F) -> Result<usize>
+where
+    F: FnMut(Vec<AttributionEvent>) -> Fut,
+    Fut: std::future::Future<Output = Result<usize>>,
+{
+    let claimed = claimed_path(queue);
+    match tokio::fs::rename(queue, &claimed).await {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).context("claim attribution queue"),
+    }
+    let events: Vec<AttributionEvent> = tokio::fs::read_to_string(&claimed)
+        .await
+        .context("read attribution queue")?
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+
+    let mut sent = 0;
+    let mut accepted = 0;
+    let mut result = Ok(());
+    for batch in events.chunks(MAX_BATCH) {
+        match send(batch.to_vec()).await {
+            Ok(n) => accepted += n,
+            Err(e) => {
+                result = Err(e);
+                break;
+            }
+        }
+        sent += batch.len();
+    }
+
+    if sent < events.len() {
+        append_events(queue, &events[sent..]).await?;
+    }
+    let _ = tokio::fs::remove_file(&claimed).await;
+    result.map(|()| accepted)
+}
+
+/// Whether `name`, in the state directory, is the queue, a claimed copy of
+/// it, or the hook's flush stamp.
+pub fn is_queue_file(name: &str) -> bool {
+    name == QUEUE_FILE
+        || name == HOOK_FLUSH_STAMP_FILE
+        || (name.starts_with("attribution-queue.") && name.ends_with(".sending"))
+}
+
+fn claimed_path(queue: &Path) -> PathBuf {
+    let nonce = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    queue.with_extension(format!("{}.{nonce}.sending", std::process::id()))
+}
+
+/// Append `events` to the queue in one write and return roughly how many
+/// events the queue now holds.
+///
+/// The count is estimated from the queue's size and the size of the events
+/// just written, which are all about the same length, so appending does not
+/// read the queue. Past [`MAX_QUEUED_EVENTS`] the queue is rewritten keeping
+/// the newest 90%, so the rewrite happens once per few hundred events.
+async fn append_events(queue: &Path, events: &[AttributionEvent]) -> Result<usize> {
+    if events.is_empty() {
+        return Ok(0);
+    }
+    let mut data = String::new();
+    for event in events {
+        data.push_str(&serde_json::to_string(event)?);
+        data.push('\n');
+    }
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(queue)
+        .await
+        .with_context(|| format!("open {}", queue.display()))?;
+    file.write_all(data.as_bytes()).await?;
+    let len = file.metadata().await?.len() as usize;
+    drop(file);
+
+    let event_len = data.len().div_ceil(events.len()).max(1);
+    let queued = len.div_ceil(event_len);
+    if queued <= MAX_QUEUED_EVENTS {
+        return Ok(queued);
+    }
+    trim_queue(queue, MAX_QUEUED_EVENTS - MAX_QUEUED_EVENTS / 10).await
+}
+
+/// Keep only the newest `keep` events in the queue. Events appended by a
+/// hook while the queue is rewritten may be lost; the queue is full anyway.
+async fn trim_queue(queue: &Path, keep: usize) -> Result<usize> {
+    let content = tokio::fs::read_to_string(queue)
+        .await
+        .context("read attribution queue")?;
+    let lines: Vec<&str> = content.lines().collect();
+    let kept = &lines[lines.len().saturating_sub(keep)..];
+    let mut data = kept.join("\n");
+    data.push('\n');
+    let tmp = claimed_path(queue).with_extension("trim");
+    tokio::fs::write(&tmp, data)
+        .await
+        .context("write attribution queue")?;
+    tokio::fs::rename(&tmp, queue)
+        .await
+        .context("replace attribution queue")?;
+    Ok(kept.len())
+}
+
+/// Organization of the repository's push remote, e.g. `example-org` for
+/// `git@github.com:example-org/repo.git`.
+async fn push_remote_org(repo_root: &Path) -> Option<String> {
+    let remote = git::resolve_push_remote_at(repo_root).await.ok()??;
+    let url = git::remote_url_at(repo_root, &remote).await.ok()??;
+    org_from_full_name(&deferred_sync::parse_repo_full_name_from_remote_url(&url)?)
+}
+
+fn org_from_full_name(full_name: &str) -> Option<String> {
+    let (org, _) = full_name.split_once('/')?;
+    (!org.is_empty()).then(|| org.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn event(commit: &str) -> AttributionEvent {
+        AttributionEvent {
+            commit: commit.to_string(),
+            agent: "codex".to_string(),
+            session_id: "s-1".to_string(),
+            repo_org: None,
+            timestamp: "2026-10-16T12:00:00Z".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn flush_sends_batches_and_requeues_after_a_failure() {
+        let dir = TempDir::new().unwrap();
+        let queue = dir.path().join(QUEUE_FILE);
+        let events: Vec<AttributionEvent> = (0..MAX_BATCH + 5)
+            .map(|i| event(&format!("c{i}")))
+            .collect();
+        assert_eq!(append_events(&queue, &events).await.unwrap(), MAX_BATCH + 5);
+
+        // The first batch goes through, the second fails and stays queued.
+        let mut calls = 0;
+        let err = flush_queue(&queue, |batch| {
+            calls += 1;
+            let fail = calls > 1;
+            async move {
+                assert!(batch.len() <= MAX_BATCH);
+                if fail {
+                    anyhow::bail!("offline");
+                }
+                Ok(batch.len())
+            }
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(err.to_string(), "offline");
+        let content = std::fs::read_to_string(&queue).unwrap();
+        assert_eq!(content.lines().count(), 5);
+        assert!(
+            content.starts_with(&serde_json::to_string(&event(&format!("c{MAX_BATCH}"))).unwrap())
+        );
+
+        let sent = flush_queue(&queue, |batch| async move { Ok(batch.len()) })
+            .await
+            .unwrap();
+        assert_eq!(sent, 5);
+        assert!(!queue.exists());
+        assert_eq!(flush_queue(&queue, |_| async { Ok(0) }).await.unwrap(), 0);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn claimed_queue_is_kept_until_its_events_are_sent() {
+        let dir = TempDir::new().unwrap();
+        let queue = dir.path().join(QUEUE_FILE);
+        append_events(&queue, &[event("c1"), event("c2")])
+            .await
+            .unwrap();
+        let claimed_files = || {
+            std::fs::read_dir(dir.path())
+                .unwrap()
+                .filter(|entry| {
+                    entry
+                        .as_ref()
+                        .unwrap()
+                        .file_name()
+                        .to_string_lossy()
+                        .ends_with(".sending")
+                })
+                .count()
+        };
+
+        let sent = flush_queue(&queue, |batch| {
+            // Were the process to stop here, the events are still on disk.
+            assert_eq!(claimed_files(), 1);
+            async move { Ok(batch.len()) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(sent, 2);
+        assert_eq!(claimed_files(), 0);
+        assert!(!queue.exists());
+    }
+
+    #[tokio::test]
+    async fn append_caps_the_queue_keeping_the_newest_events() {
+        let dir = TempDir::new().unwrap();
+        let queue = dir.path().join(QUEUE_FILE);
+        let events: Vec<AttributionEvent> = (0..MAX_QUEUED_EVENTS)
+            .map(|i| event(&format!("{i:05}")))
+            .collect();
+        assert_eq!(
+            append_events(&queue, &events).await.unwrap(),
+            MAX_QUEUED_EVENTS
+        );
+
+        let queued = append_events(&queue, &[event("99999")]).await.unwrap();
+        let content = std::fs::read_to_string(&queue).unwrap();
+        assert_eq!(content.lines().count(), queued);
+        assert!(queued < MAX_QUEUED_EVENTS);
+        assert!(content.lines().last().unwrap().contains("99999"));
+        assert!(!content.contains("\"00000\""));
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn hook_flushes_at_most_once_per_interval() {
+        let dir = TempDir::new().unwrap();
+        let interval = HOOK_FLUSH_INTERVAL.as_secs() as i64;
+        assert!(claim_hook_flush(dir.path(), 1_000).await);
+        assert!(!claim_hook_flush(dir.path(), 1_001).await);
+        assert!(!claim_hook_flush(dir.path(), 1_000 + interval - 1).await);
+        assert!(claim_hook_flush(dir.path(), 1_000 + interval).await);
+    }
+
+    #[test]
+    fn org_is_taken_from_the_repo_full_name() {
+        assert_eq!(
+            org_from_full_name("example-org/repo").as_deref(),
+            Some("example-org")
+        );
+        assert_eq!(org_from_full_name("repo"), None);
+    }
+}
@@ -6,7 +6,6 @@ use time::OffsetDateTime;
 use time::format_description::well_known::Rfc3339;
 use tokio::io::{AsyncWriteExt, BufWriter};
 use tokio::sync::mpsc::{UnboundedSender, unbounded_channel};
-#[cfg(test)]
 use tokio::sync::oneshot;
 
 #[derive(Clone, Default)]
@@ -21,10 +20,18 @@ struct BackfillLoggerInner {
 
 enum LogMessage {
     Line(String),
-    #[cfg(test)]
     Flush(oneshot::Sender<()>),
 }
 
+/// File in the config directory that collects one metrics row per hook run.
+pub const HOOK_METRICS_FILE: &str = "hook-metrics.log";
+
+/// The metrics file moves here once it grows past [`HOOK_METRICS_MAX_BYTES`],
+/// replacing the previous one, like the hook log.
+pub const HOOK_METRICS_OLD_FILE: &str = "hook-metrics.log.old";
+
+const HOOK_METRICS_MAX_BYTES: u64 = 1024 * 1024;
+
 impl BackfillLogger {
     pub async fn new() -> Result<Self> {
         let dir = crate::config::CliConfig::config_dir()
@@ -32,6 +39,15 @@ impl BackfillLogger {
         Self::new_with_now(&dir, OffsetDateTime::now_utc()).await
     }
 
+    /// A logger that appends to `hook-metrics.log` in the config directory
+    /// instead of starting a new file, so rows from every run accumulate
+    /// until the file is rotated.
+    pub async fn hook_metrics() -> Result<Self> {
+        let dir = crate::config::CliConfig::config_dir()
+            .ok_or_else(|| anyhow!("cannot determine config directory: $HOME is not set"))?;
+        Self::append_in_dir(&dir, HOOK_METRICS_FILE).await
+    }
+
     pub fn disabled() -> Self {
         Self { inner: None }
     }
@@ -59,6 +75,30 @@ impl BackfillLogger {
             .open(&path)
             .await
             .with_context(|| format!("failed to create backfill log file at {}", path.display()))?;
+        Ok(Self::spawn_writer(path, file))
+    }
+
+    async fn append_in_dir(dir: &Path, file_name: &str) -> Result<Self> {
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("failed to create config directory at {}", dir.display()))?;
+        let path = dir.join(file_name);
+        if tokio::fs::metadata(&path)
+            .await
+            .is_ok_and(|m| m.len() > HOOK_METRICS_MAX_BYTES)
+        {
+            let _ = tokio::fs::rename(&path, path.with_extension("log.old")).await;
+        }
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("failed to open log file at {}", path.display()))?;
+        Ok(Self::spawn_writer(path, file))
+    }
+
+    fn spawn_writer(path: PathBuf, file: tokio::fs::File) -> Self {
         let mut writer = BufWriter::new(file);
         let (sender, mut receiver) = unbounded_channel::<LogMessage>();
         tokio::spawn(async move {
@@ -75,7 +115,6 @@ impl BackfillLogger {
                             break;
                         }
                     }
-                    #[cfg(test)]
                     LogMessage::Flush(ack) => {
                         let _ = writer.flush().await;
                         let _ = ack.send(());
@@ -85,9 +124,9 @@ impl BackfillLogger {
             let _ = writer.flush().await;
         });
 
-        Ok(Self {
+        Self {
             inner: Some(Arc::new(BackfillLoggerInner { path, sender })),
-        })
+        }
     }
 
     pub fn path(&self) -> Option<PathBuf> {
@@ -112,7 +151,8 @@ impl BackfillLogger {
         let _ = inner.sender.send(LogMessage::Line(line));
     }
 
-    #[cfg(test)]
+    /// Wait until every row sent so far is written. Short-lived processes
+    /// call this before exiting.
     pub async fn flush(&self) {
         let Some(inner) = &self.inner else {
             return;
@@ -199,4 +239,46 @@ mod tests {
         );
         assert!(row.get("timestamp").is_some());
     }
+
+    #[tokio::test]
+    async fn append_in_dir_keeps_earlier_rows() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        for run in 0..2 {
+            let logger = BackfillLogger::append_in_dir(tmp.path(), HOOK_METRICS_FILE)
+                .await
+                .expect("open logger");
+            logger.event("post_commit", json!({ "run": run }));
+            logger.flush().await;
+        }
+
+        let content = tokio::fs::read_to_string(tmp.path().join(HOOK_METRICS_FILE))
+            .await
+            .expect("read");
+        let runs: Vec<i64> = content
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).expect("json row"))
+            .filter_map(|row| row.get("payload")?.get("run")?.as_i64())
+            .collect();
+        assert_eq!(runs, [0, 1]);
+    }
+
+    #[tokio::test]
+    async fn append_in_dir_rotates_a_full_file() {
+        let tmp = tempfile::TempDir::new().expect("tempdir");
+        let path = tmp.path().join(HOOK_METRICS_FILE);
+        std::fs::write(&path, vec![b'x'; HOOK_METRICS_MAX_BYTES as usize + 1]).unwrap();
+
+        let logger = BackfillLogger::append_in_dir(tmp.path(), HOOK_METRICS_FILE)
+            .await
+            .expect("open logger");
+        logger.event("post_commit", json!({ "run": 0 }));
+        logger.flush().await;
+
+        let old = tmp.path().join(HOOK_METRICS_OLD_FILE);
+        assert_eq!(
+            std::fs::metadata(old).unwrap().len(),
+            HOOK_METRICS_MAX_BYTES + 1
+        );
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 1);
+    }
 }
@@ -62,6 +62,11 @@ pub struct CliConfig {
     pub auto_update: Option<bool>,
     /// How often the passive background version check runs (e.g., "8h", "24h", "1d").
     pub update_check_interval: Option<String>,
+    /// PEM file of root certificates the API client trusts instead of the
+    /// built-in roots, for API servers behind an internal CA.
+    pub ca_bundle: Option<String>,
+    /// Proxy URL for API requests, overriding `HTTPS_PROXY`/`HTTP_PROXY`.
+    pub proxy: Option<String>,
 }
 
 impl CliConfig {
@@ -321,6 +326,8 @@ pub enum ConfigKey {
     AutoUpdate,
     UpdateCheckInterval,
     ApiUrl,
+    CaBundle,
+    Proxy,
 }
 
 /// All user-settable configuration keys, in display order.
@@ -328,6 +335,8 @@ pub const ALL_CONFIG_KEYS: &[ConfigKey] = &[
     ConfigKey::AutoUpdate,
     ConfigKey::UpdateCheckInterval,
     ConfigKey::ApiUrl,
+    ConfigKey::CaBundle,
+    ConfigKey::Proxy,
 ];
 
 /// Keys that are managed by the auth flow and cannot be set via `cadence config`.
@@ -346,6 +355,8 @@ impl ConfigKey {
             ConfigKey::AutoUpdate => "auto_update",
             ConfigKey::UpdateCheckInterval => "update_check_interval",
             ConfigKey::ApiUrl => "api_url",
+            ConfigKey::CaBundle => "ca_bundle",
+            ConfigKey::Proxy => "proxy",
         }
     }
 }
@@ -376,6 +387,8 @@ impl std::str::FromStr for ConfigKey {
             "auto_update" => Ok(ConfigKey::AutoUpdate),
             "update_check_interval" => Ok(ConfigKey::UpdateCheckInterval),
             "api_url" => Ok(ConfigKey::ApiUrl),
+            "ca_bundle" => Ok(ConfigKey::CaBundle),
+            "proxy" => Ok(ConfigKey::Proxy),
             _ => bail!(
                 "unknown config key '{}'. Valid keys: {}",
                 s,
@@ -427,6 +440,14 @@ impl CliConfig {
                 Some(v) => v.clone(),
                 None => "(not set)".to_string(),
             },
+            ConfigKey::CaBundle => match &self.ca_bundle {
+                Some(v) => v.clone(),
+                None => "(not set)".to_string(),
+            },
+            ConfigKey::Proxy => match &self.proxy {
+                Some(v) => v.clone(),
+                None => "(not set)".to_string(),
+            },
         }
     }
 
@@ -435,6 +456,8 @@ impl CliConfig {
     /// For `AutoUpdate`, the value is parsed as a boolean.
     /// For `UpdateCheckInterval`, the value is validated as a duration string.
     /// For `ApiUrl`, the value is stored as-is (trimmed).
+    /// For `CaBundle`, the file must exist; it is stored as an absolute path.
+    /// For `Proxy`, the value must be an `http://` or `https://` URL.
     pub fn set_key(&mut self, key: ConfigKey, value: &str) -> Result<()> {
         match key {
             ConfigKey::AutoUpdate => {
@@ -455,11 +478,42 @@ impl CliConfig {
                     self.api_url = Some(trimmed);
                 }
             }
+            ConfigKey::CaBundle => {
+                let trimmed = value.trim();
+                if trimmed.is_empty() {
+                    self.ca_bundle = None;
+                } else {
+                    let path = std::path::absolute(trimmed)
+                        .with_context(|| format!("invalid CA bundle path '{trimmed}'"))?;
+                    if !path.is_file() {
+                        bail!("CA bundle '{}' is not a file", path.display());
+                    }
+                    self.ca_bundle = Some(path.to_string_lossy().to_string());
+                }
+            }
+            ConfigKey::Proxy => {
+                let trimmed = value.trim();
+                if trimmed.is_empty() {
+                    self.proxy = None;
+                } else {
+                    validate_proxy_url(trimmed)?;
+                    self.proxy = Some(trimmed.to_string());
+                }
+            }
         }
         Ok(())
     }
 }
 
+/// Accept `http://` and `https://` proxy URLs with a host.
+pub fn validate_proxy_url(value: &str) -> Result<()> {
+    let url = reqwest::Url::parse(value).with_context(|| format!("invalid proxy URL '{value}'"))?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        bail!("invalid proxy URL '{value}': expected http://host:port or https://host:port");
+    }
+    Ok(())
+}
+
 /// Return the trimmed value if non-empty after trimming, otherwise `None`.
 fn non_empty_trimmed(value: Option<String>) -> Option<String> {
     value.and_then(|v| {
@@ -1668,8 +1722,48 @@ mod tests {
         assert_eq!(ConfigKey::ApiUrl.to_string(), "api_url");
     }
 
+    #[tokio::test]
+    async fn test_set_ca_bundle_requires_an_existing_file() {
+        let tmp = TempDir::new().unwrap();
+        let bundle = tmp.path().join("ca.pem");
+        let mut cfg = CliConfig::default();
+        assert!(
+            cfg.set_key(ConfigKey::CaBundle, &bundle.to_string_lossy())
+                .is_err()
+        );
+
+        std::fs::write(&bundle, "").unwrap();
+        cfg.set_key(ConfigKey::CaBundle, &bundle.to_string_lossy())
+            .unwrap();
+        assert_eq!(
+            cfg.get_key(ConfigKey::CaBundle),
+            bundle.to_string_lossy().as_ref()
+        );
+        assert_eq!(
+            "ca-bundle".parse::<ConfigKey>().unwrap(),
+            ConfigKey::CaBundle
+        );
+        cfg.set_key(ConfigKey::CaBundle, " ").unwrap();
+        assert_eq!(cfg.ca_bundle, None);
+    }
+
+    #[tokio::test]
+    async fn test_set_proxy_validates_the_url() {
+        let mut cfg = CliConfig::default();
+        cfg.set_key(ConfigKey::Proxy, " http://proxy.corp:3128 ")
+            .unwrap();
+        assert_eq!(cfg.get_key(ConfigKey::Proxy), "http://proxy.corp:3128");
+        assert!(cfg.set_key(ConfigKey::Proxy, "proxy.corp:3128").is_err());
+        assert!(
+            cfg.set_key(ConfigKey::Proxy, "socks5://proxy:1080")
+                .is_err()
+        );
+        cfg.set_key(ConfigKey::Proxy, "").unwrap();
+        assert_eq!(cfg.proxy, None);
+    }
+
     #[tokio::test]
     async fn test_all_config_keys_count() {
-        assert_eq!(ALL_CONFIG_KEYS.len(), 3);
+        assert_eq!(ALL_CONFIG_KEYS.len(), 5);
     }
 }
@@ -19,7 +19,8 @@ use tokio::task::JoinSet;
 use tracing::{info, warn};
 use uuid::Uuid;
 
-use crate::{agents, config, git, push};
+use crate::settings::Settings;
+use crate::{config, git, push, state};
 
 const DEFAULT_LOCK_MAX_AGE_SECS: i64 = 300;
 const DEFAULT_LOG_RETENTION_DAYS: i64 = 7;
@@ -70,6 +71,10 @@ pub struct PendingSyncRecord {
     pub next_attempt_at_epoch: i64,
     /// Last sync error, if any.
     pub last_error: Option<String>,
+    /// Why the job was queued, e.g. by the pre-push hook or after a failed
+    /// push. Empty in records written by older versions.
+    #[serde(default)]
+    pub reason: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -111,11 +116,12 @@ impl Drop for SyncLockGuard {
     }
 }
 
-/// Upsert a pending sync job for `(repo, remote)`.
+/// Upsert a pending sync job for `(repo, remote)`, recording `reason` as
+/// why it was queued.
 ///
-/// If a record already exists, this refreshes `updated_at` and makes it
-/// immediately eligible for retry.
-pub async fn enqueue_pending_sync(repo_root: &Path, remote: &str) -> Result<()> {
+/// If a record already exists, this refreshes `updated_at` and `reason` and
+/// makes it immediately eligible for retry.
+pub async fn enqueue_pending_sync(repo_root: &Path, remote: &str, reason: &str) -> Result<()> {
     let repo_root_str = repo_root.to_string_lossy().to_string();
     let key = pending_key(&repo_root_str, remote);
     let now_epoch = now_epoch();
@@ -123,46 +129,27 @@ pub async fn enqueue_pending_sync(repo_root: &Path, remote: &str) -> Result<()>
     let dir = pending_dir().await?;
     let path = dir.join(format!("{key}.json"));
 
-    let mut record = if tokio::fs::try_exists(&path).await.unwrap_or(false) {
-        match tokio::fs::read_to_string(&path).await {
-            Ok(content) => {
-                serde_json::from_str::<PendingSyncRecord>(&content).unwrap_or(PendingSyncRecord {
-                    repo_root: repo_root_str.clone(),
-                    remote: remote.to_string(),
-                    enqueued_at: now.clone(),
-                    updated_at: now.clone(),
-                    attempt_count: 0,
-                    next_attempt_at_epoch: now_epoch,
-                    last_error: None,
-                })
-            }
-            Err(_) => PendingSyncRecord {
-                repo_root: repo_root_str.clone(),
-                remote: remote.to_string(),
-                enqueued_at: now.clone(),
-                updated_at: now.clone(),
-                attempt_count: 0,
-                next_attempt_at_epoch: now_epoch,
-                last_error: None,
-            },
-        }
-    } else {
-        PendingSyncRecord {
-            repo_root: repo_root_str.clone(),
-            remote: remote.to_string(),
-            enqueued_at: now.clone(),
-            updated_at: now.clone(),
-            attempt_count: 0,
-            next_attempt_at_epoch: now_epoch,
-            last_error: None,
-        }
+    let existing = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => serde_json::from_str::<PendingSyncRecord>(&content).ok(),
+        Err(_) => None,
     };
+    let mut record = existing.unwrap_or_else(|| PendingSyncRecord {
+        repo_root: repo_root_str.clone(),
+        remote: remote.to_string(),
+        enqueued_at: now.clone(),
+        updated_at: now.clone(),
+        attempt_count: 0,
+        next_attempt_at_epoch: now_epoch,
+        last_error: None,
+        reason: String::new(),
+    });
 
     record.updated_at = now;
+    record.reason = reason.to_string();
     if record.next_attempt_at_epoch > now_epoch {
         record.next_attempt_at_epoch = now_epoch;
     }
-    write_json_atomic(&path, &record).await
+    state::write_json_atomic(&path, &record).await
 }
 
 /// Spawn a detached one-shot background worker that runs:
@@ -327,6 +314,13 @@ async fn run_one_pending_job(job: PendingSyncRecord) -> Result<()> {
         "sync worker started"
     );
 
+    // Jobs queued before `autopush = never` was set, or by an explicit
+    // sync run, must not push either. Drop them rather than keep retrying.
+    if push::autopush_disabled_globally().await {
+        info!("autopush=never; dropping pending sync job without pushing");
+        return clear_pending_record(&job.repo_root, &job.remote).await;
+    }
+
     let lock = match acquire_lock(&job.repo_root, &job.remote, &worker_id).await? {
         Some(lock) => lock,
         None => {
@@ -478,7 +472,7 @@ async fn notify_session_ref_push(
     Ok(NotifySessionRefPushResult::Sent)
 }
 
-async fn resolve_cli_notification_token(cfg: &config::CliConfig) -> Option<String> {
+pub(crate) async fn resolve_cli_notification_token(cfg: &config::CliConfig) -> Option<String> {
     let login_token = crate::resolve_cli_auth_token(cfg).await;
     resolve_cli_notification_token_with_login_token(login_token)
 }
@@ -488,7 +482,7 @@ fn resolve_cli_notification_token_with_login_token(login_token: Option<String>)
         .or_else(|| non_empty_trimmed(login_token))
 }
 
-fn parse_repo_full_name_from_remote_url(remote_url: &str) -> Option<String> {
+pub(crate) fn parse_repo_full_name_from_remote_url(remote_url: &str) -> Option<String> {
     let trimmed = remote_url.trim();
     if trimmed.is_empty() {
         return None;
@@ -732,17 +726,34 @@ fn pending_path_for(repo_root: &str, remote: &str, dir: &Path) -> PathBuf {
     dir.join(format!("{}.json", pending_key(repo_root, remote)))
 }
 
+/// Exponential backoff plus a random delay of up to `jitter_percent` of it,
+/// so many repos failing together do not retry (and rescan) in lockstep.
+fn retry_delay_ms(attempt_count: u32, jitter_percent: u64, rng: &mut impl Rng) -> u64 {
+    let backoff_ms = ((1u64 << attempt_count.min(8)) * 1000).min(300_000);
+    backoff_ms + rng.gen_range(0..=backoff_ms * jitter_percent / 100)
+}
+
+/// Record a failed attempt and schedule the next retry.
+///
+/// Only called while the job's sync lock is held, so `attempt_count`
+/// increments from concurrent workers cannot interleave.
 async fn update_pending_retry(job: &PendingSyncRecord, error_message: String) -> Result<()> {
     let dir = pending_dir().await?;
     let path = pending_path_for(&job.repo_root, &job.remote, &dir);
     let mut next = job.clone();
     next.attempt_count = next.attempt_count.saturating_add(1);
-    let jitter = rand08::thread_rng().gen_range(0..=500i64);
-    let backoff_ms = ((1u64 << next.attempt_count.min(8)) * 1000).min(300_000);
-    next.next_attempt_at_epoch = now_epoch() + ((backoff_ms as i64 + jitter) / 1000);
+    let jitter_percent = Settings::load(Some(Path::new(&job.repo_root)))
+        .await
+        .retry_jitter_percent;
+    let delay_ms = retry_delay_ms(
+        next.attempt_count,
+        jitter_percent,
+        &mut rand08::thread_rng(),
+    );
+    next.next_attempt_at_epoch = now_epoch() + delay_ms.div_ceil(1000) as i64;
     next.last_error = Some(error_message);
     next.updated_at = crate::note::now_rfc3339();
-    write_json_atomic(&path, &next).await
+    state::write_json_atomic(&path, &next).await
 }
 
 /// Remove a pending job after successful sync.
@@ -779,6 +790,19 @@ async fn load_pending_records() -> Result<Vec<PendingSyncRecord>> {
     Ok(out)
 }
 
+const EXPLICIT_SYNC_REASON: &str = "requested with `cadence hook deferred-sync`";
+
+/// Pending sync jobs for the repository at `repo_root`, ordered by remote.
+pub async fn pending_sync_jobs_for(repo_root: &Path) -> Result<Vec<PendingSyncRecord>> {
+    let mut jobs: Vec<PendingSyncRecord> = load_pending_records()
+        .await?
+        .into_iter()
+        .filter(|job| crate::scanner::same_path(Path::new(&job.repo_root), repo_root))
+        .collect();
+    jobs.sort_by(|a, b| a.remote.cmp(&b.remote));
+    Ok(jobs)
+}
+
 /// Build a single explicit sync job and ensure it exists in the pending queue.
 async fn build_explicit_jobs(
     repo: Option<&Path>,
@@ -792,13 +816,11 @@ async fn build_explicit_jobs(
     };
     let remote = match remote {
         Some(r) => r.to_string(),
-        None => git::resolve_push_remote_at(&repo_root)
-            .await
-            .ok()
-            .flatten()
-            .unwrap_or_else(|| "origin".to_string()),
+        None => git::session_remote_at(&repo_root, &Settings::load(Some(&repo_root)).await).await,
     };
-    enqueue_pending_sync(&repo_root, &remote).await?;
+    // A misspelled remote would otherwise sit in the queue failing forever.
+    git::ensure_remote_exists_at(&repo_root, &remote).await?;
+    enqueue_pending_sync(&repo_root, &remote, EXPLICIT_SYNC_REASON).await?;
     let rec = PendingSyncRecord {
         repo_root: repo_root.to_string_lossy().to_string(),
         remote,
@@ -807,6 +829,7 @@ async fn build_explicit_jobs(
         attempt_count: 0,
         next_attempt_at_epoch: now_epoch(),
         last_error: None,
+        reason: EXPLICIT_SYNC_REASON.to_string(),
     };
     Ok(vec![rec])
 }
@@ -935,79 +958,29 @@ async fn sweep_log_size() -> Result<()> {
     Ok(())
 }
 
-/// Write JSON atomically via a temp file + rename.
-async fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> Result<()> {
-    let data = serde_json::to_vec_pretty(value)?;
-    let parent = path
-        .parent()
-        .ok_or_else(|| anyhow::anyhow!("missing parent for {}", path.display()))?;
-    let file_name = path
-        .file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("pending");
-    let pid = std::process::id();
-
-    for attempt in 0..8u32 {
-        let nonce = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_nanos();
-        let tmp_name = format!(".{file_name}.{pid}.{nonce}.{attempt}.tmp");
-        let tmp = parent.join(tmp_name);
-
-        let mut opts = tokio::fs::OpenOptions::new();
-        opts.write(true).create_new(true);
-        match opts.open(&tmp).await {
-            Ok(mut file) => {
-                tokio::io::AsyncWriteExt::write_all(&mut file, &data).await?;
-                drop(file);
-                tokio::fs::rename(&tmp, path).await?;
-                return Ok(());
-            }
-            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
-            Err(err) => {
-                return Err(err)
-                    .with_context(|| format!("create temp file for {}", path.display()));
-            }
-        }
-    }
-
-    Err(anyhow::anyhow!(
-        "failed to create unique temp file for {}",
-        path.display()
-    ))
-}
-
-async fn cadence_cli_dir() -> Result<PathBuf> {
-    let home =
-        agents::home_dir().ok_or_else(|| anyhow::anyhow!("cannot resolve home directory"))?;
-    let dir = home.join(".cadence").join("cli");
-    tokio::fs::create_dir_all(&dir).await?;
-    Ok(dir)
-}
-
 async fn pending_dir() -> Result<PathBuf> {
-    let dir = cadence_cli_dir().await?.join("pending-sync");
+    let dir = state::state_dir().await?.join("pending-sync");
     tokio::fs::create_dir_all(&dir).await?;
     Ok(dir)
 }
 
 async fn lock_dir() -> Result<PathBuf> {
-    let dir = cadence_cli_dir().await?.join("locks");
+    let dir = state::state_dir().await?.join("locks");
     tokio::fs::create_dir_all(&dir).await?;
     Ok(dir)
 }
 
 fn log_dir_blocking() -> Result<PathBuf> {
-    let home =
-        agents::home_dir().ok_or_else(|| anyhow::anyhow!("cannot resolve home directory"))?;
-    let dir = home.join(".cadence").join("cli").join("logs").join("sync");
+    let dir = state::state_dir_path()
+        .ok_or_else(|| anyhow::anyhow!("cannot resolve home directory"))?
+        .join("logs")
+        .join("sync");
     std::fs::create_dir_all(&dir)?;
     Ok(dir)
 }
 
 async fn log_dir() -> Result<PathBuf> {
-    let dir = cadence_cli_dir().await?.join("logs").join("sync");
+    let dir = state::state_dir().await?.join("logs").join("sync");
     tokio::fs::create_dir_all(&dir).await?;
     Ok(dir)
 }
@@ -1048,28 +1021,6 @@ mod tests {
         assert!(lock2.is_some());
     }
 
-    #[tokio::test]
-    async fn write_json_atomic_allows_concurrent_writers() {
-        let tmp = TempDir::new().unwrap();
-        let path = tmp.path().join("record.json");
-
-        let mut set = JoinSet::new();
-        for i in 0..24u32 {
-            let path = path.clone();
-            set.spawn(
-                async move { write_json_atomic(&path, &serde_json::json!({ "i": i })).await },
-            );
-        }
-
-        while let Some(done) = set.join_next().await {
-            done.unwrap().unwrap();
-        }
-
-        let content = tokio::fs::read_to_string(&path).await.unwrap();
-        let parsed: serde_json::Value = serde_json::from_str(&content).unwrap();
-        assert!(parsed.get("i").and_then(|v| v.as_u64()).is_some());
-    }
-
     struct EnvBackup {
         home: Option<String>,
         userprofile: Option<String>,
@@ -1078,6 +1029,7 @@ mod tests {
         cadence_api_url: Option<String>,
         legacy_api_url: Option<String>,
         cadence_cli_token: Option<String>,
+        xdg_data_home: Option<String>,
     }
 
     impl EnvBackup {
@@ -1090,6 +1042,7 @@ mod tests {
                 cadence_api_url: std::env::var("CADENCE_API_URL").ok(),
                 legacy_api_url: std::env::var("AI_BAROMETER_API_URL").ok(),
                 cadence_cli_token: std::env::var("CADENCE_CLI_TOKEN").ok(),
+                xdg_data_home: std::env::var("XDG_DATA_HOME").ok(),
             }
         }
 
@@ -1122,6 +1075,10 @@ mod tests {
                 Some(v) => unsafe { std::env::set_var("CADENCE_CLI_TOKEN", v) },
                 None => unsafe { std::env::remove_var("CADENCE_CLI_TOKEN") },
             }
+            match self.xdg_data_home {
+                Some(v) => unsafe { std::env::set_var("XDG_DATA_HOME", v) },
+                None => unsafe { std::env::remove_var("XDG_DATA_HOME") },
+            }
         }
     }
 
@@ -1206,6 +1163,7 @@ mod tests {
             attempt_count: 0,
             next_attempt_at_epoch: now_epoch(),
             last_error: None,
+            reason: String::new(),
         }
     }
 
@@ -1378,11 +1336,12 @@ mod tests {
         let backup = EnvBackup::capture();
         unsafe {
             std::env::set_var("HOME", tmp.path());
+            std::env::remove_var("XDG_DATA_HOME");
         }
 
         assert!(!has_pending_sync_jobs().await);
 
-        let pending = tmp.path().join(".cadence/cli/pending-sync");
+        let pending = state::state_dir_in(tmp.path(), None).join("pending-sync");
         tokio::fs::create_dir_all(&pending).await.unwrap();
         tokio::fs::write(pending.join("ignore.txt"), "x")
             .await
@@ -1399,6 +1358,7 @@ mod tests {
                 attempt_count: 0,
                 next_attempt_at_epoch: now_epoch(),
                 last_error: None,
+                reason: String::new(),
             })
             .unwrap(),
         )
@@ -1409,6 +1369,75 @@ mod tests {
         backup.restore();
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn pending_jobs_record_why_they_were_queued() {
+        let tmp = TempDir::new().unwrap();
+        let backup = EnvBackup::capture();
+        unsafe {
+            std::env::set_var("HOME", tmp.path());
+            std::env::remove_var("XDG_DATA_HOME");
+        }
+        let repo = tmp.path().join("repo");
+        tokio::fs::create_dir_all(&repo).await.unwrap();
+
+        enqueue_pending_sync(&repo, "origin", "queued by the pre-push hook")
+            .await
+            .unwrap();
+        enqueue_pending_sync(&repo, "backup", "push during backfill failed: offline")
+            .await
+            .unwrap();
+        enqueue_pending_sync(&tmp.path().join("other"), "origin", "elsewhere")
+            .await
+            .unwrap();
+        let jobs = pending_sync_jobs_for(&repo).await.unwrap();
+        let reasons: Vec<(&str, &str)> = jobs
+            .iter()
+            .map(|j| (j.remote.as_str(), j.reason.as_str()))
+            .collect();
+        assert_eq!(
+            reasons,
+            [
+                ("backup", "push during backfill failed: offline"),
+                ("origin", "queued by the pre-push hook")
+            ]
+        );
+
+        // Queuing again keeps the job but records the newest reason.
+        enqueue_pending_sync(&repo, "origin", "queued again")
+            .await
+            .unwrap();
+        let jobs = pending_sync_jobs_for(&repo).await.unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[1].reason, "queued again");
+
+        // Records written before reasons existed still load.
+        let legacy = serde_json::json!({
+            "repo_root": "/tmp/repo",
+            "remote": "origin",
+            "enqueued_at": "2026-10-01T00:00:00Z",
+            "updated_at": "2026-10-01T00:00:00Z",
+            "attempt_count": 2,
+            "next_attempt_at_epoch": 0,
+            "last_error": "offline",
+        });
+        let record: PendingSyncRecord = serde_json::from_value(legacy).unwrap();
+        assert_eq!(record.reason, "");
+
+        backup.restore();
+    }
+
+    #[test]
+    fn retry_delay_stays_within_the_jitter_window() {
+        let mut rng = rand08::thread_rng();
+        for _ in 0..200 {
+            let delay = retry_delay_ms(3, 20, &mut rng);
+            assert!((8_000..=9_600).contains(&delay), "delay {delay}");
+        }
+        assert_eq!(retry_delay_ms(3, 0, &mut rng), 8_000);
+        assert_eq!(retry_delay_ms(30, 0, &mut rng), 256_000);
+    }
+
     #[tokio::test]
     #[serial]
     async fn collect_runnable_jobs_filters_by_retry_window_and_max_items() {
@@ -1416,9 +1445,10 @@ mod tests {
         let backup = EnvBackup::capture();
         unsafe {
             std::env::set_var("HOME", tmp.path());
+            std::env::remove_var("XDG_DATA_HOME");
         }
 
-        let pending = tmp.path().join(".cadence/cli/pending-sync");
+        let pending = state::state_dir_in(tmp.path(), None).join("pending-sync");
         tokio::fs::create_dir_all(&pending).await.unwrap();
         let now = now_epoch();
         let mk = |repo_root: &str, remote: &str, next_attempt_at_epoch: i64| PendingSyncRecord {
@@ -1429,6 +1459,7 @@ mod tests {
             attempt_count: 0,
             next_attempt_at_epoch,
             last_error: None,
+            reason: String::new(),
         };
 
         let records = vec![
@@ -1477,6 +1508,7 @@ mod tests {
         let backup = EnvBackup::capture();
         unsafe {
             std::env::set_var("HOME", env_tmp.path());
+            std::env::remove_var("XDG_DATA_HOME");
             std::env::remove_var("AI_BAROMETER_API_URL");
             std::env::set_var("CADENCE_CLI_TOKEN", "notify-token");
         }
@@ -1484,7 +1516,7 @@ mod tests {
         let (local, _remote_root, remote_bare) =
             init_repo_with_file_remote("example-org", "example-repo").await;
         write_session_data_ref(local.path()).await;
-        enqueue_pending_sync(local.path(), "origin")
+        enqueue_pending_sync(local.path(), "origin", "test")
             .await
             .expect("enqueue pending sync");
 
@@ -1536,6 +1568,7 @@ mod tests {
         let backup = EnvBackup::capture();
         unsafe {
             std::env::set_var("HOME", env_tmp.path());
+            std::env::remove_var("XDG_DATA_HOME");
             std::env::remove_var("AI_BAROMETER_API_URL");
             std::env::set_var("CADENCE_CLI_TOKEN", "notify-token");
         }
@@ -1574,6 +1607,7 @@ mod tests {
         let backup = EnvBackup::capture();
         unsafe {
             std::env::set_var("HOME", env_tmp.path());
+            std::env::remove_var("XDG_DATA_HOME");
             std::env::remove_var("AI_BAROMETER_API_URL");
             std::env::set_var("CADENCE_CLI_TOKEN", "expired-token");
         }
@@ -1604,4 +1638,88 @@ mod tests {
 
         backup.restore();
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn global_autopush_never_drops_queued_jobs_without_pushing() {
+        let env_tmp = TempDir::new().unwrap();
+        let backup = EnvBackup::capture();
+        let original_global = std::env::var("GIT_CONFIG_GLOBAL").ok();
+        unsafe {
+            std::env::set_var("HOME", env_tmp.path());
+            std::env::remove_var("XDG_DATA_HOME");
+        }
+
+        let (local, _remote_root, remote_bare) =
+            init_repo_with_file_remote("example-org", "autopush-never").await;
+        write_session_data_ref(local.path()).await;
+        enqueue_pending_sync(local.path(), "origin", "test")
+            .await
+            .expect("enqueue pending sync");
+
+        // The switch is set after the job was queued.
+        let global_config = env_tmp.path().join("fake-global-gitconfig");
+        tokio::fs::write(&global_config, "[ai \"cadence\"]\n    autopush = never\n")
+            .await
+            .expect("write global config");
+        unsafe {
+            std::env::set_var("GIT_CONFIG_GLOBAL", &global_config);
+        }
+
+        let result = run_sync_command(SyncRunOptions {
+            repo: None,
+            remote: None,
+            all_pending: true,
+            background: false,
+            max_items: 10,
+            time_budget_ms: DEFAULT_TIME_BUDGET_MS,
+        })
+        .await;
+        let pending = load_pending_records().await;
+
+        unsafe {
+            match original_global {
+                Some(g) => std::env::set_var("GIT_CONFIG_GLOBAL", g),
+                None => std::env::remove_var("GIT_CONFIG_GLOBAL"),
+            }
+        }
+        backup.restore();
+
+        result.expect("sync run");
+        assert!(
+            !git::ref_exists_at(Some(&remote_bare), git::SESSION_DATA_REF)
+                .await
+                .expect("ref exists check"),
+            "remote should not receive session refs"
+        );
+        assert!(pending.expect("pending records").is_empty());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn explicit_sync_rejects_an_unknown_remote_without_queueing_it() {
+        let env_tmp = TempDir::new().unwrap();
+        let backup = EnvBackup::capture();
+        unsafe {
+            std::env::set_var("HOME", env_tmp.path());
+            std::env::remove_var("XDG_DATA_HOME");
+        }
+        let (local, _remote_root, _remote_bare) =
+            init_repo_with_file_remote("example-org", "unknown-remote").await;
+
+        let err = build_explicit_jobs(Some(local.path()), Some("upstream"))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no remote named 'upstream'"), "{err}");
+        assert!(err.contains("configured remotes: origin"), "{err}");
+        assert!(load_pending_records().await.unwrap().is_empty());
+
+        let jobs = build_explicit_jobs(Some(local.path()), Some("origin"))
+            .await
+            .unwrap();
+        assert_eq!(jobs[0].remote, "origin");
+
+        backup.restore();
+    }
 }
@@ -4,6 +4,7 @@
 //! The notes ref used throughout is `refs/cadence/sessions/data`.
 
 use crate::output;
+use crate::settings::Settings;
 use anyhow::{Context, Result, bail};
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
@@ -21,6 +22,10 @@ pub const LEGACY_SESSION_NOTES_REF: &str = "refs/notes/ai-sessions";
 pub const SESSION_INDEX_BRANCH_REF: &str = "refs/cadence/sessions/index/branch";
 /// Committer-oriented index of session objects.
 pub const SESSION_INDEX_COMMITTER_REF: &str = "refs/cadence/sessions/index/committer";
+/// Namespace every pushed session ref must live under.
+const SESSION_REF_PREFIX: &str = "refs/cadence/";
+/// Optional git config override for the remote session refs are synced to.
+pub const SESSION_REMOTE_KEY: &str = "ai.cadence.remote";
 
 /// Result of fetching a single ref.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -28,6 +33,31 @@ pub struct FetchResult {
     pub fetched: bool,
 }
 
+/// Error message shown when no `git` executable can be started.
+pub const GIT_MISSING_MESSAGE: &str = "git was not found on PATH; install git (https://git-scm.com/downloads) and make sure it is on PATH";
+
+/// Check that a `git` executable can be started, with a friendly error if not.
+pub async fn ensure_available() -> Result<()> {
+    ensure_available_in(None).await
+}
+
+/// Like [`ensure_available`], searching `path` instead of the inherited PATH.
+async fn ensure_available_in(path: Option<&std::ffi::OsStr>) -> Result<()> {
+    let mut cmd = Command::new("git");
+    cmd.arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    if let Some(path) = path {
+        cmd.env("PATH", path);
+    }
+    match cmd.status().await {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => bail!(GIT_MISSING_MESSAGE),
+        Err(e) => Err(e).context("failed to execute git"),
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Internal helper
 // ---------------------------------------------------------------------------
@@ -124,51 +154,6 @@ fn emit_stream_chunk(chunk: &[u8]) {
 // Public API
 // ---------------------------------------------------------------------------
 
-/// Check whether Cadence CLI is enabled for the current repository.
-///
-/// Reads `git config ai.cadence.enabled`. If the value is exactly
-/// `"false"`, returns `false` -- the caller should skip ALL processing
-/// (session scanning, session-ref updates, pending records, push, retry).
-/// Any other value (including unset) returns `true`.
-///
-/// This is placed in the `git` module (not `push`) because it gates
-/// the entire hook lifecycle, not just the push decision.
-pub async fn check_enabled() -> bool {
-    match config_get("ai.cadence.enabled").await {
-        Ok(Some(val)) => val != "false",
-        // Unset or error: default to enabled
-        _ => true,
-    }
-}
-
-/// Check whether Cadence CLI is enabled for a specific repository directory.
-///
-/// This is the directory-parameterised version of [`check_enabled`], for use
-/// by commands that operate on repos other than the CWD (e.g., `backfill`).
-///
-/// Reads `git -C <repo> config ai.cadence.enabled`. If the value is exactly
-/// `"false"`, returns `false`. Any other value (including unset) returns `true`.
-pub(crate) async fn check_enabled_at(repo: &Path) -> bool {
-    let output = match run_git_output_at(
-        Some(repo),
-        &["config", "--get", "ai.cadence.enabled"],
-        &[],
-    )
-    .await
-    {
-        Ok(o) => o,
-        Err(_) => return true,
-    };
-
-    if output.status.success() {
-        let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        value != "false"
-    } else {
-        // Unset (exit code 1) or error: default to enabled
-        true
-    }
-}
-
 /// Return the repository root (`git rev-parse --show-toplevel`).
 pub async fn repo_root() -> Result<PathBuf> {
     let path = git_output(&["rev-parse", "--show-toplevel"]).await?;
@@ -269,11 +254,27 @@ pub(crate) async fn list_tree_entries_at(
 }
 
 /// Check whether a ref exists locally in a repository.
+///
+/// Returns `Ok(false)` only when git reports the ref as missing (exit 1).
+/// Any other failure (not a repository, corrupt refs, ...) is an `Err`, so
+/// callers never mistake a broken repository for an empty one.
 pub(crate) async fn ref_exists_at(repo: Option<&Path>, ref_name: &str) -> Result<bool> {
     let output = run_git_output_at(repo, &["show-ref", "--verify", "--quiet", ref_name], &[])
         .await
         .context("failed to execute git show-ref --verify")?;
-    Ok(output.status.success())
+    match output.status.code() {
+        Some(0) => Ok(true),
+        Some(1) => Ok(false),
+        code => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!(
+                "git show-ref --verify {} failed (exit {}): {}",
+                ref_name,
+                code.unwrap_or(-1),
+                stderr.trim()
+            );
+        }
+    }
 }
 
 /// Get a local ref hash if present, `None` if it does not exist.
@@ -443,6 +444,30 @@ fn force_with_lease_arg(ref_name: &str, remote_hash: &Option<String>) -> String
     }
 }
 
+/// Build the `git push` arguments for a single session ref.
+///
+/// The refspec is always the fully-qualified `<ref>:<ref>` pair, and refs
+/// outside `refs/cadence/` are rejected, so a sync can never push a branch.
+fn push_ref_args(
+    remote: &str,
+    ref_name: &str,
+    expected_remote_hash: &Option<String>,
+) -> Result<Vec<String>> {
+    if remote.is_empty() || remote == "." {
+        bail!("invalid remote name");
+    }
+    if !ref_name.starts_with(SESSION_REF_PREFIX) {
+        bail!("refusing to push non-session ref {ref_name}");
+    }
+    Ok(vec![
+        "push".to_string(),
+        "--no-verify".to_string(),
+        force_with_lease_arg(ref_name, expected_remote_hash),
+        remote.to_string(),
+        format!("{ref_name}:{ref_name}"),
+    ])
+}
+
 /// Push a single ref with `--force-with-lease`.
 pub(crate) async fn push_ref_with_lease_at(
     repo: Option<&Path>,
@@ -450,14 +475,11 @@ pub(crate) async fn push_ref_with_lease_at(
     ref_name: &str,
     expected_remote_hash: &Option<String>,
 ) -> Result<()> {
-    let lease = force_with_lease_arg(ref_name, expected_remote_hash);
-    let output = run_git_output_at(
-        repo,
-        &["push", "--no-verify", &lease, remote, ref_name],
-        &[("GIT_TERMINAL_PROMPT", "0")],
-    )
-    .await
-    .context("failed to execute git push with lease")?;
+    let args = push_ref_args(remote, ref_name, expected_remote_hash)?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = run_git_output_at(repo, &args, &[("GIT_TERMINAL_PROMPT", "0")])
+        .await
+        .context("failed to execute git push with lease")?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -491,6 +513,32 @@ pub(crate) async fn ls_tree_at(repo: Option<&Path>, treeish: &str) -> Result<Vec
         .collect())
 }
 
+/// Number of blobs reachable from `treeish` and their total size in bytes,
+/// as stored uncompressed. `None` if `treeish` does not exist.
+pub(crate) async fn tree_blob_stats_at(
+    repo: Option<&Path>,
+    treeish: &str,
+) -> Result<Option<(usize, u64)>> {
+    let output = run_git_output_at(repo, &["ls-tree", "-r", "-l", treeish], &[])
+        .await
+        .context("failed to execute git ls-tree")?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let stdout =
+        String::from_utf8(output.stdout).context("git ls-tree output was not valid UTF-8")?;
+    let sizes: Vec<u64> = stdout
+        .lines()
+        .filter_map(|line| {
+            let (meta, _) = line.split_once('\t')?;
+            let mut parts = meta.split_whitespace();
+            (parts.nth(1)? == "blob").then_some(())?;
+            parts.nth(1)?.parse().ok()
+        })
+        .collect();
+    Ok(Some((sizes.len(), sizes.iter().sum())))
+}
+
 fn parse_ls_tree_entry(line: &str) -> Option<(String, String, String, String)> {
     let (meta, name) = line.split_once('\t')?;
     let mut parts = meta.split_whitespace();
@@ -500,6 +548,26 @@ fn parse_ls_tree_entry(line: &str) -> Option<(String, String, String, String)> {
     Some((mode, kind, sha, name.to_string()))
 }
 
+/// The blob stored under `path` in `ref_name`, if the ref and path exist.
+pub(crate) async fn blob_in_ref_at(
+    repo: &Path,
+    ref_name: &str,
+    path: &str,
+) -> Result<Option<String>> {
+    let rev = format!("{ref_name}:{path}");
+    let output = run_git_output_at(Some(repo), &["rev-parse", "--verify", "--quiet", &rev], &[])
+        .await
+        .context("failed to execute git rev-parse")?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let sha = String::from_utf8(output.stdout)
+        .context("git rev-parse output was not valid UTF-8")?
+        .trim()
+        .to_string();
+    Ok((!sha.is_empty()).then_some(sha))
+}
+
 /// Ensure a blob is reachable from a ref under a fanout path.
 ///
 /// This is idempotent. If the path already exists with the same SHA, no ref
@@ -877,6 +945,114 @@ pub(crate) async fn rev_parse_at(repo: Option<&Path>, rev: &str) -> Result<Strin
     Ok(sha)
 }
 
+/// Resolve a user-supplied commit (a short or full hash, or any ref
+/// expression such as `HEAD~2`) to its full SHA.
+///
+/// Fails with an "ambiguous" error for a short hash matching several
+/// commits, and an "unknown" one for anything that does not name a commit.
+pub(crate) async fn resolve_commit_at(repo: Option<&Path>, spec: &str) -> Result<String> {
+    let rev = format!("{}^{{commit}}", spec.trim());
+    let output = run_git_output_at(
+        repo,
+        &["rev-parse", "--verify", "--end-of-options", &rev],
+        &[],
+    )
+    .await
+    .context("failed to execute git rev-parse")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("is ambiguous") {
+            bail!("commit '{spec}' is ambiguous; use more characters of the hash");
+        }
+        bail!("unknown commit '{spec}'");
+    }
+
+    let sha = String::from_utf8(output.stdout)
+        .context("git rev-parse output was not valid UTF-8")?
+        .trim()
+        .to_string();
+    Ok(sha)
+}
+
+/// Return the author email and name of HEAD.
+pub(crate) async fn head_author_at(repo: &Path) -> Result<(String, String)> {
+    let output = run_git_output_at(
+        Some(repo),
+        &["show", "-s", "--format=%ae%n%an", "HEAD"],
+        &[],
+    )
+    .await
+    .context("failed to execute git show")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git show -s HEAD failed: {}", stderr.trim());
+    }
+
+    let stdout = String::from_utf8(output.stdout).context("git show output was not valid UTF-8")?;
+    let mut lines = stdout.lines();
+    let email = lines.next().unwrap_or_default().trim().to_string();
+    let name = lines.next().unwrap_or_default().trim().to_string();
+    Ok((email, name))
+}
+
+/// HEAD's commit subject (first line of the message).
+pub(crate) async fn head_subject_at(repo: &Path) -> Result<String> {
+    let output = run_git_output_at(Some(repo), &["show", "-s", "--format=%s", "HEAD"], &[])
+        .await
+        .context("failed to execute git show")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git show -s HEAD failed: {}", stderr.trim());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Which HEAD timestamp anchors the post-commit matching window.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum TimeSource {
+    /// Committer date; equal to "now" for a fresh commit.
+    #[default]
+    Commit,
+    /// Author date, which survives rebases, amends and cherry-picks.
+    Author,
+}
+
+impl TimeSource {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "commit" => Some(TimeSource::Commit),
+            "author" => Some(TimeSource::Author),
+            _ => None,
+        }
+    }
+}
+
+/// Return HEAD's committer or author date as a Unix timestamp.
+pub(crate) async fn head_timestamp_at(repo: &Path, source: TimeSource) -> Result<i64> {
+    let format = match source {
+        TimeSource::Commit => "--format=%ct",
+        TimeSource::Author => "--format=%at",
+    };
+    let output = run_git_output_at(Some(repo), &["show", "-s", format, "HEAD"], &[])
+        .await
+        .context("failed to execute git show")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("git show -s HEAD failed: {}", stderr.trim());
+    }
+
+    let stdout = String::from_utf8(output.stdout).context("git show output was not valid UTF-8")?;
+    stdout
+        .trim()
+        .parse()
+        .with_context(|| format!("unexpected HEAD timestamp {:?}", stdout.trim()))
+}
+
 /// Return the current branch name for a repo, if HEAD is attached.
 pub(crate) async fn current_branch_at(repo: &Path) -> Result<Option<String>> {
     let output = run_git_output_at(
@@ -932,9 +1108,10 @@ pub async fn push_notes(remote: &str) -> Result<()> {
 
 #[allow(dead_code)]
 pub async fn push_notes_at(repo: Option<&Path>, remote: &str) -> Result<()> {
+    let refspec = format!("{NOTES_REF}:{NOTES_REF}");
     let output = run_git_output_at(
         repo,
-        &["push", "--no-verify", remote, NOTES_REF],
+        &["push", "--no-verify", remote, &refspec],
         &[("GIT_TERMINAL_PROMPT", "0")],
     )
     .await
@@ -1053,6 +1230,47 @@ pub async fn remote_url_at(repo: &Path, remote: &str) -> Result<Option<String>>
     Ok(Some(url.trim().to_string()))
 }
 
+/// Error unless `remote` is one of the remotes configured in `repo`, naming
+/// the ones that are.
+pub async fn ensure_remote_exists_at(repo: &Path, remote: &str) -> Result<()> {
+    let remotes = git_output_in(repo, &["remote"]).await?;
+    let names: Vec<&str> = remotes.lines().filter(|r| !r.is_empty()).collect();
+    if names.contains(&remote) {
+        return Ok(());
+    }
+    let known = if names.is_empty() {
+        "none".to_string()
+    } else {
+        names.join(", ")
+    };
+    bail!(
+        "no remote named '{remote}' in {} (configured remotes: {known})",
+        repo.display()
+    )
+}
+
+/// Resolve the remote session refs are synced to for a repository.
+///
+/// Resolution order:
+/// 1) `ai.cadence.remote`
+/// 2) when a global org filter is set, the first remote in that org
+/// 3) the regular push remote resolution
+/// 4) `origin`
+pub async fn session_remote_at(repo: &Path, settings: &Settings) -> String {
+    if let Some(remote) = &settings.session_remote {
+        return remote.clone();
+    }
+    if let Some(org) = &settings.org
+        && let Ok(Some(remote)) = remote_for_org_at(repo, org).await
+    {
+        return remote;
+    }
+    match resolve_push_remote_at(repo).await {
+        Ok(Some(remote)) => remote,
+        _ => "origin".to_string(),
+    }
+}
+
 /// Resolve the push remote for a specific repository.
 pub async fn resolve_push_remote_at(repo: &Path) -> Result<Option<String>> {
     let output = run_git_output_at(
@@ -1200,6 +1418,20 @@ pub async fn remote_orgs_at(repo: &Path) -> Result<Vec<String>> {
     Ok(orgs)
 }
 
+/// Find the first remote (in `git remote` order) whose URL belongs to `org`.
+/// The comparison is case-insensitive.
+pub async fn remote_for_org_at(repo: &Path, org: &str) -> Result<Option<String>> {
+    let remotes = git_output_in(repo, &["remote"]).await?;
+    for remote_name in remotes.lines().filter(|r| !r.is_empty()) {
+        if let Ok(url) = git_output_in(repo, &["remote", "get-url", remote_name]).await
+            && parse_org_from_url(&url).is_some_and(|o| o.eq_ignore_ascii_case(org))
+        {
+            return Ok(Some(remote_name.to_string()));
+        }
+    }
+    Ok(None)
+}
+
 /// Parse the owner/org segment from a git remote URL.
 ///
 /// This is a pure function extracted for testability.
@@ -1289,6 +1521,51 @@ pub async fn config_get_at(repo: &Path, key: &str) -> Result<Option<String>> {
     Ok(Some(value.trim().to_string()))
 }
 
+/// Every config entry whose key matches the regex `pattern`, in git's
+/// precedence order (system, global, then local, so later entries win), as
+/// `(key, value)` pairs. Keys come back lowercased, as git normalizes them;
+/// a bare boolean entry (`[ai "cadence"] enabled`) reads as `"true"`.
+/// `global_only` ignores system and repository config.
+pub(crate) async fn config_entries_at(
+    repo: Option<&Path>,
+    pattern: &str,
+    global_only: bool,
+) -> Result<Vec<(String, String)>> {
+    let mut args = vec!["config", "--null"];
+    if global_only {
+        args.push("--global");
+    }
+    args.extend(["--get-regexp", pattern]);
+    let output = run_git_output_at(repo, &args, &[])
+        .await
+        .context("failed to execute git config --get-regexp")?;
+
+    if !output.status.success() {
+        let code = output.status.code().unwrap_or(-1);
+        if code != 1 {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            bail!(
+                "git config --get-regexp {:?} failed (exit {}): {}",
+                pattern,
+                code,
+                stderr.trim()
+            );
+        }
+        return Ok(Vec::new());
+    }
+
+    let stdout =
+        String::from_utf8(output.stdout).context("git config output was not valid UTF-8")?;
+    Ok(stdout
+        .split('\0')
+        .filter(|record| !record.is_empty())
+        .map(|record| match record.split_once('\n') {
+            Some((key, value)) => (key.to_string(), value.to_string()),
+            None => (record.to_string(), "true".to_string()),
+        })
+        .collect())
+}
+
 /// Read a repo-local git config value (ignores global/system). Returns `Ok(None)` if unset.
 pub async fn config_get_local_at(repo: &Path, key: &str) -> Result<Option<String>> {
     let output = run_git_output_at(Some(repo), &["config", "--local", "--get", key], &[])
@@ -1344,16 +1621,15 @@ pub async fn config_get_global(key: &str) -> Result<Option<String>> {
 
 /// Check org filter for a specific repository. If a global org is configured,
 /// verify that at least one remote matches that org (case-insensitive).
-pub async fn repo_matches_org_filter(repo: &Path) -> Result<bool> {
-    let configured_org = match config_get_global("ai.cadence.org").await {
-        Ok(Some(org)) => org,
-        _ => return Ok(true),
+pub async fn repo_matches_org_filter(repo: &Path, settings: &Settings) -> Result<bool> {
+    let Some(configured_org) = &settings.org else {
+        return Ok(true);
     };
 
     let remote_orgs = remote_orgs_at(repo).await?;
     Ok(remote_orgs
         .iter()
-        .any(|org| org.eq_ignore_ascii_case(&configured_org)))
+        .any(|org| org.eq_ignore_ascii_case(configured_org)))
 }
 
 /// Write a git config value (repo-local scope).
@@ -1421,6 +1697,17 @@ mod tests {
     use serial_test::serial;
     use tempfile::TempDir;
 
+    #[tokio::test]
+    async fn ensure_available_reports_missing_git() {
+        ensure_available().await.unwrap();
+
+        let empty = TempDir::new().unwrap();
+        let err = ensure_available_in(Some(empty.path().as_os_str()))
+            .await
+            .expect_err("git should not be found on an empty PATH");
+        assert_eq!(err.to_string(), GIT_MISSING_MESSAGE);
+    }
+
     /// Helper: create a temporary git repo with one commit.
     /// Returns the TempDir (which cleans up on drop) and sets the
     /// working directory for the test by returning a guard.
@@ -1526,6 +1813,41 @@ mod tests {
         assert!(ts > 1_577_836_800);
     }
 
+    #[tokio::test]
+    async fn test_head_timestamp_uses_the_selected_date() {
+        let dir = init_temp_repo().await;
+        tokio::fs::write(dir.path().join("a.txt"), "a")
+            .await
+            .unwrap();
+        run_git(dir.path(), &["add", "a.txt"]).await;
+        let output = run_git_output_at(
+            Some(dir.path()),
+            &["commit", "-m", "rebased"],
+            &[
+                ("GIT_AUTHOR_DATE", "@1700000000 +0000"),
+                ("GIT_COMMITTER_DATE", "@1700005000 +0000"),
+            ],
+        )
+        .await
+        .unwrap();
+        assert!(output.status.success());
+
+        assert_eq!(
+            head_timestamp_at(dir.path(), TimeSource::Commit)
+                .await
+                .unwrap(),
+            1_700_005_000
+        );
+        assert_eq!(
+            head_timestamp_at(dir.path(), TimeSource::Author)
+                .await
+                .unwrap(),
+            1_700_000_000
+        );
+        assert_eq!(TimeSource::parse(" Author"), Some(TimeSource::Author));
+        assert_eq!(TimeSource::parse("mtime"), None);
+    }
+
     // -----------------------------------------------------------------------
     // has_upstream
     // -----------------------------------------------------------------------
@@ -1920,7 +2242,7 @@ mod tests {
     #[serial]
     async fn test_api_config_get_missing() {
         let (_dir, original_cwd) = enter_temp_repo().await;
-        let val = config_get("ai.cadence.nonexistent")
+        let val = config_get("cadence-test.nonexistent")
             .await
             .expect("config_get failed");
         assert_eq!(val, None);
@@ -1931,64 +2253,16 @@ mod tests {
     #[serial]
     async fn test_api_config_set_then_get() {
         let (_dir, original_cwd) = enter_temp_repo().await;
-        config_set("ai.cadence.enabled", "true")
+        config_set("cadence-test.value", "true")
             .await
             .expect("config_set failed");
-        let val = config_get("ai.cadence.enabled")
+        let val = config_get("cadence-test.value")
             .await
             .expect("config_get failed");
         assert_eq!(val, Some("true".to_string()));
         std::env::set_current_dir(original_cwd).unwrap();
     }
 
-    // -----------------------------------------------------------------------
-    // check_enabled
-    // -----------------------------------------------------------------------
-
-    #[tokio::test]
-    #[serial]
-    async fn test_check_enabled_default_true() {
-        let (_dir, original_cwd) = enter_temp_repo().await;
-
-        // No config set -- should default to enabled
-        assert!(check_enabled().await);
-
-        std::env::set_current_dir(original_cwd).unwrap();
-    }
-
-    #[tokio::test]
-    #[serial]
-    async fn test_check_enabled_explicitly_true() {
-        let (dir, original_cwd) = enter_temp_repo().await;
-
-        run_git(dir.path(), &["config", "ai.cadence.enabled", "true"]).await;
-        assert!(check_enabled().await);
-
-        std::env::set_current_dir(original_cwd).unwrap();
-    }
-
-    #[tokio::test]
-    #[serial]
-    async fn test_check_enabled_explicitly_false() {
-        let (dir, original_cwd) = enter_temp_repo().await;
-
-        run_git(dir.path(), &["config", "ai.cadence.enabled", "false"]).await;
-        assert!(!check_enabled().await);
-
-        std::env::set_current_dir(original_cwd).unwrap();
-    }
-
-    #[tokio::test]
-    #[serial]
-    async fn test_check_enabled_other_value_treated_as_true() {
-        let (dir, original_cwd) = enter_temp_repo().await;
-
-        run_git(dir.path(), &["config", "ai.cadence.enabled", "yes"]).await;
-        assert!(check_enabled().await);
-
-        std::env::set_current_dir(original_cwd).unwrap();
-    }
-
     // -----------------------------------------------------------------------
     // Phase 12 hardening: detached HEAD
     // -----------------------------------------------------------------------
@@ -2081,6 +2355,28 @@ mod tests {
         assert!(orgs.contains(&"org-two".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_ensure_remote_exists_at_names_configured_remotes() {
+        let dir = init_temp_repo().await;
+        let path = dir.path();
+
+        let err = ensure_remote_exists_at(path, "origin").await.unwrap_err();
+        assert!(
+            err.to_string().contains("(configured remotes: none)"),
+            "{err}"
+        );
+
+        run_git(path, &["remote", "add", "origin", "/tmp/origin.git"]).await;
+        run_git(path, &["remote", "add", "backup", "/tmp/backup.git"]).await;
+        ensure_remote_exists_at(path, "backup").await.unwrap();
+        let err = ensure_remote_exists_at(path, "upstream")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no remote named 'upstream'"), "{err}");
+        assert!(err.contains("backup, origin"), "{err}");
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_repo_matches_org_filter() {
@@ -2103,7 +2399,7 @@ mod tests {
             std::env::set_var("GIT_CONFIG_GLOBAL", &global_config);
         }
 
-        let matches = repo_matches_org_filter(path)
+        let matches = repo_matches_org_filter(path, &Settings::load(Some(path)).await)
             .await
             .expect("repo_matches_org_filter failed");
         assert!(matches);
@@ -2111,7 +2407,7 @@ mod tests {
         tokio::fs::write(&global_config, "[ai \"cadence\"]\n    org = other-org\n")
             .await
             .unwrap();
-        let matches = repo_matches_org_filter(path)
+        let matches = repo_matches_org_filter(path, &Settings::load(Some(path)).await)
             .await
             .expect("repo_matches_org_filter failed");
         assert!(!matches);
@@ -2195,14 +2491,14 @@ mod tests {
 
     // -----------------------------------------------------------------------
     // Tree/ref plumbing: ls_tree_at, mktree_at, commit_tree_at, update_ref_at,
-    // rev_parse_at
+    // rev_parse_at / resolve_commit_at
     // -----------------------------------------------------------------------
 
     #[tokio::test]
     async fn test_ls_tree_at() {
         let dir = init_temp_repo().await;
         let tree_rev = "HEAD^{tree}";
-        let entries = ls_tree_at(Some(dir.path()), &tree_rev)
+        let entries = ls_tree_at(Some(dir.path()), tree_rev)
             .await
             .expect("ls_tree_at failed");
         assert!(!entries.is_empty());
@@ -2231,6 +2527,31 @@ mod tests {
         assert_eq!(entries[0], entry);
     }
 
+    #[tokio::test]
+    async fn test_tree_blob_stats_at_counts_nested_blobs() {
+        let dir = init_temp_repo().await;
+        let blob = store_blob_at(Some(dir.path()), b"0123456789")
+            .await
+            .expect("store_blob failed");
+        let inner = mktree_at(
+            Some(dir.path()),
+            &[
+                format!("100644 blob {blob}\ta"),
+                format!("100644 blob {blob}\tb"),
+            ],
+        )
+        .await
+        .expect("mktree failed");
+        let outer = mktree_at(Some(dir.path()), &[format!("040000 tree {inner}\tab")])
+            .await
+            .expect("mktree failed");
+
+        let stats = tree_blob_stats_at(Some(dir.path()), &outer).await.unwrap();
+        assert_eq!(stats, Some((2, 20)));
+        let missing = tree_blob_stats_at(Some(dir.path()), "refs/cadence/missing").await;
+        assert_eq!(missing.unwrap(), None);
+    }
+
     #[tokio::test]
     async fn test_commit_tree_at_creates_orphan() {
         let dir = init_temp_repo().await;
@@ -2299,6 +2620,35 @@ mod tests {
         assert_eq!(resolved, head);
     }
 
+    #[tokio::test]
+    async fn test_resolve_commit_at_accepts_short_hashes_and_refs() {
+        let dir = init_temp_repo().await;
+        let path = dir.path();
+        let first = run_git(path, &["rev-parse", "HEAD"]).await;
+        run_git(path, &["commit", "--allow-empty", "-q", "-m", "second"]).await;
+        let second = run_git(path, &["rev-parse", "HEAD"]).await;
+
+        for (spec, expected) in [
+            ("HEAD", &second),
+            ("HEAD~1", &first),
+            (&first[..7], &first),
+            (first.as_str(), &first),
+        ] {
+            assert_eq!(
+                &resolve_commit_at(Some(path), spec).await.unwrap(),
+                expected
+            );
+        }
+
+        for bad in ["no-such-ref", "HEAD~5", "--all", ""] {
+            let err = resolve_commit_at(Some(path), bad).await.unwrap_err();
+            assert_eq!(err.to_string(), format!("unknown commit '{bad}'"));
+        }
+        // A tree is an object, but not a commit.
+        let tree = run_git(path, &["rev-parse", "HEAD^{tree}"]).await;
+        assert!(resolve_commit_at(Some(path), &tree).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_migrate_legacy_session_ref_copies_when_new_missing() {
         let dir = init_temp_repo().await;
@@ -2356,4 +2706,113 @@ mod tests {
             .expect("canonical ref");
         assert_eq!(canonical, head, "canonical ref should not be overwritten");
     }
+
+    #[test]
+    fn push_ref_args_pin_fully_qualified_refspec() {
+        let args =
+            push_ref_args("upstream", SESSION_DATA_REF, &Some("abc123".to_string())).expect("args");
+        assert_eq!(
+            args,
+            vec![
+                "push",
+                "--no-verify",
+                "--force-with-lease=refs/cadence/sessions/data:abc123",
+                "upstream",
+                "refs/cadence/sessions/data:refs/cadence/sessions/data",
+            ]
+        );
+    }
+
+    #[test]
+    fn push_ref_args_reject_branches_and_bad_remotes() {
+        assert!(push_ref_args("origin", "refs/heads/main", &None).is_err());
+        assert!(push_ref_args("origin", "main", &None).is_err());
+        assert!(push_ref_args(".", SESSION_DATA_REF, &None).is_err());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn session_remote_prefers_configured_remote() {
+        let dir = init_temp_repo().await;
+        run_git(dir.path(), &["remote", "add", "origin", "/tmp/origin.git"]).await;
+        run_git(
+            dir.path(),
+            &["remote", "add", "upstream", "/tmp/upstream.git"],
+        )
+        .await;
+        assert_eq!(
+            session_remote_at(dir.path(), &Settings::load(Some(dir.path())).await).await,
+            "origin"
+        );
+
+        run_git(dir.path(), &["config", SESSION_REMOTE_KEY, "upstream"]).await;
+        assert_eq!(
+            session_remote_at(dir.path(), &Settings::load(Some(dir.path())).await).await,
+            "upstream"
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn session_remote_selects_remote_matching_org_filter() {
+        let dir = init_temp_repo().await;
+        let path = dir.path();
+        run_git(
+            path,
+            &[
+                "remote",
+                "add",
+                "origin",
+                "git@github.com:other-org/repo.git",
+            ],
+        )
+        .await;
+        run_git(
+            path,
+            &[
+                "remote",
+                "add",
+                "upstream",
+                "https://github.com/My-Org/repo.git",
+            ],
+        )
+        .await;
+
+        let global_config = path.join("fake-global-gitconfig");
+        tokio::fs::write(&global_config, "[ai \"cadence\"]\n    org = my-org\n")
+            .await
+            .unwrap();
+        let original_global = std::env::var("GIT_CONFIG_GLOBAL").ok();
+        unsafe {
+            std::env::set_var("GIT_CONFIG_GLOBAL", &global_config);
+        }
+
+        let remote = session_remote_at(path, &Settings::load(Some(path)).await).await;
+
+        unsafe {
+            match original_global {
+                Some(g) => std::env::set_var("GIT_CONFIG_GLOBAL", g),
+                None => std::env::remove_var("GIT_CONFIG_GLOBAL"),
+            }
+        }
+        assert_eq!(remote, "upstream");
+    }
+
+    #[tokio::test]
+    async fn ref_exists_reports_missing_ref_as_false() {
+        let dir = init_temp_repo().await;
+        let exists = ref_exists_at(Some(dir.path()), SESSION_DATA_REF)
+            .await
+            .expect("missing ref is not an error");
+        assert!(!exists);
+    }
+
+    #[tokio::test]
+    async fn ref_exists_errors_outside_a_repository() {
+        let dir = TempDir::new().unwrap();
+        let err = ref_exists_at(Some(dir.path()), SESSION_DATA_REF)
+            .await
+            .expect_err("git failure should surface as an error");
+        assert!(format!("{err:#}").contains("show-ref"));
+    }
 }
@@ -1,7 +1,12 @@
 //! Library entrypoint for cadence-cli.
 //!
 //! The primary interface is the `cadence` binary. This lib target exists to
-//! expose internal modules to integration tests and future spec consumers.
+//! expose internal modules to integration tests and embedders: agent session
+//! discovery ([`agents`]), session log parsing ([`scanner`]) and matching
+//! sessions to a repository ([`matcher`]).
 
+pub mod agents;
 pub mod config;
+pub mod matcher;
+pub mod scanner;
 pub mod update;
@@ -0,0 +1,149 @@
+//! Prefixed logging for git hook entry points.
+//!
+//! Git interleaves hook output with its own, so every hook line is tagged with
+//! `[Cadence]`. Levels honor the global verbosity set by `-q`/`-v`: `debug`
+//! prints only when verbose and `warn` always prints.
+//!
+//! With `ai.cadence.hookLog = file`, hooks call [`set_file_sink`] and every
+//! line (including debug) is appended to a log file instead, keeping commit
+//! output clean.
+//!
+//! Background workers (deferred sync, push) log through `tracing` to their
+//! own log files instead.
+
+use console::{Color, style};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::output::{self, Verbosity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Debug,
+    Warn,
+}
+
+/// Whether a line at `level` should be printed under `verbosity`.
+pub fn level_enabled(level: Level, verbosity: Verbosity) -> bool {
+    match level {
+        Level::Debug => verbosity == Verbosity::Verbose,
+        Level::Warn => true,
+    }
+}
+
+/// The `[Cadence]` prefix, styled on a TTY.
+pub fn label(is_tty: bool) -> String {
+    if is_tty {
+        style("[Cadence]").bold().fg(Color::Cyan).to_string()
+    } else {
+        "[Cadence]".to_string()
+    }
+}
+
+fn write_line(w: &mut dyn Write, level: Level, msg: &str, is_tty: bool) {
+    let label = label(is_tty);
+    let line = match level {
+        Level::Warn if is_tty => format!("{label} {} {msg}", style("warning:").fg(Color::Yellow)),
+        Level::Warn => format!("{label} warning: {msg}"),
+        Level::Debug if is_tty => format!("{label} {}", style(format!("debug: {msg}")).dim()),
+        Level::Debug => format!("{label} debug: {msg}"),
+    };
+    let _ = writeln!(w, "{line}");
+}
+
+/// Hook log files are rotated to `<name>.old` once they grow past this size.
+const FILE_SINK_MAX_BYTES: u64 = 1024 * 1024;
+
+static FILE_SINK: OnceLock<PathBuf> = OnceLock::new();
+
+/// Send all further log lines to `path` instead of stderr.
+pub fn set_file_sink(path: PathBuf) {
+    let _ = FILE_SINK.set(path);
+}
+
+/// Whether log lines are going to a file instead of stderr.
+pub fn writes_to_file() -> bool {
+    FILE_SINK.get().is_some()
+}
+
+fn append_to_file(path: &Path, level: Level, msg: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::metadata(path).is_ok_and(|m| m.len() > FILE_SINK_MAX_BYTES) {
+        let _ = std::fs::rename(path, path.with_extension("log.old"));
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    write!(file, "{} ", crate::note::now_rfc3339())?;
+    write_line(&mut file, level, msg, false);
+    Ok(())
+}
+
+fn emit(level: Level, msg: &str) {
+    if let Some(path) = FILE_SINK.get() {
+        let _ = append_to_file(path, level, msg);
+        return;
+    }
+    if !level_enabled(level, output::verbosity()) {
+        return;
+    }
+    write_line(&mut io::stderr(), level, msg, output::is_stderr_tty());
+}
+
+pub fn warn(msg: &str) {
+    emit(Level::Warn, msg);
+}
+
+pub fn debug(msg: &str) {
+    emit(Level::Debug, msg);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_enabled_follows_verbosity() {
+        assert!(!level_enabled(Level::Debug, Verbosity::Normal));
+        assert!(level_enabled(Level::Debug, Verbosity::Verbose));
+        assert!(level_enabled(Level::Warn, Verbosity::Quiet));
+    }
+
+    #[test]
+    fn lines_are_prefixed_without_tty() {
+        let mut buf = Vec::new();
+        write_line(&mut buf, Level::Warn, "hook issue", false);
+        write_line(&mut buf, Level::Debug, "scanning 4 candidates", false);
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "[Cadence] warning: hook issue\n\
+             [Cadence] debug: scanning 4 candidates\n"
+        );
+    }
+
+    #[test]
+    fn file_sink_appends_timestamped_lines_and_rotates() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let path = tmp.path().join("logs").join("hooks.log");
+        append_to_file(&path, Level::Warn, "hook issue").unwrap();
+        append_to_file(&path, Level::Debug, "scanned 3").unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with(" [Cadence] warning: hook issue"));
+        assert!(lines[1].ends_with(" [Cadence] debug: scanned 3"));
+
+        std::fs::write(&path, vec![b'x'; FILE_SINK_MAX_BYTES as usize + 1]).unwrap();
+        append_to_file(&path, Level::Warn, "fresh").unwrap();
+        assert!(path.with_extension("log.old").exists());
+        assert!(
+            std::fs::read_to_string(&path)
+                .unwrap()
+                .ends_with("[Cadence] warning: fresh\n")
+        );
+    }
+}
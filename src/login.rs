@@ -8,40 +8,66 @@ use crate::api_client::{ApiClient, CliTokenExchangeResult};
 
 const CADENCE_LOCKUP_INLINE_SVG: &str = include_str!("../assets/cadence-lockup-inline.svg");
 
-/// Complete browser-based CLI OAuth login flow.
-pub async fn login_via_browser(
-    api_base_url: &str,
-    timeout: Duration,
-) -> Result<CliTokenExchangeResult> {
-    let nonce = generate_nonce();
-
-    let listener = TcpListener::bind("127.0.0.1:0")
-        .await
-        .context("failed to bind local callback port")?;
-    let local_port = listener
-        .local_addr()
-        .context("failed to read local callback address")?
-        .port();
-
-    let auth_url = format!(
-        "{}/auth/token?port={}&state={}",
-        api_base_url.trim_end_matches('/'),
-        local_port,
-        nonce
-    );
+/// A browser login in progress: the callback listener is bound and the URL
+/// the user must visit is known. Whoever starts it decides how that URL
+/// reaches the browser.
+///
+/// The browser redirects to a loopback listener (see [`CallbackListener`]),
+/// so the callback never goes through a proxy; only the code exchange uses
+/// the client's proxy.
+pub struct PendingLogin {
+    listener: CallbackListener,
+    nonce: String,
+    auth_url: String,
+}
 
-    open::that(&auth_url).with_context(|| {
-        format!("failed to open browser. Open this URL manually to continue login: {auth_url}")
-    })?;
+impl PendingLogin {
+    /// Bind the local callback listener for a login against `client`'s API.
+    pub async fn start(client: &ApiClient) -> Result<Self> {
+        let nonce = generate_nonce();
+        let listener = CallbackListener::bind().await?;
+        let local_port = listener.port();
+        let auth_url = format!(
+            "{}/auth/token?port={}&state={}",
+            client.base_url(),
+            local_port,
+            nonce
+        );
+        Ok(Self {
+            listener,
+            nonce,
+            auth_url,
+        })
+    }
 
-    let deadline = Instant::now() + timeout;
-    let exchange_code = wait_for_exchange_code(&listener, &nonce, deadline).await?;
+    /// The local port the browser callback is sent to.
+    pub fn port(&self) -> u16 {
+        self.listener.port()
+    }
 
-    let client = ApiClient::new(api_base_url);
-    client
-        .exchange_cli_code(&exchange_code, Duration::from_secs(10))
-        .await
-        .context("failed to exchange login code for CLI token")
+    /// The URL that starts the login in a browser.
+    pub fn auth_url(&self) -> &str {
+        &self.auth_url
+    }
+
+    /// Wait up to `timeout` for the browser callback, then exchange its code
+    /// for a CLI token.
+    pub async fn finish(
+        self,
+        client: &ApiClient,
+        timeout: Duration,
+    ) -> Result<CliTokenExchangeResult> {
+        let deadline = Instant::now() + timeout;
+        let Some(exchange_code) =
+            wait_for_exchange_code(&self.listener, &self.nonce, deadline).await?
+        else {
+            return Err(timeout_error(&self.listener, timeout));
+        };
+        client
+            .exchange_cli_code(&exchange_code, Duration::from_secs(10))
+            .await
+            .context("failed to exchange login code for CLI token")
+    }
 }
 
 fn generate_nonce() -> String {
@@ -60,24 +86,98 @@ fn bytes_to_hex(bytes: &[u8]) -> String {
     out
 }
 
+/// The timeout error with what to try next.
+fn timeout_error(listener: &CallbackListener, timeout: Duration) -> anyhow::Error {
+    let callback = format!("localhost:{}", listener.port());
+    anyhow::anyhow!(
+        "login timed out after {}s waiting for the browser callback.\n\
+         - Run `cadence login` again, with `--timeout <secs>` for more time.\n\
+         - Finish sign-in in a browser on this machine; the callback goes to {callback}.\n\
+         - Check that no firewall or security software blocks connections to {callback}.",
+        timeout.as_secs()
+    )
+}
+
+/// Loopback listeners for the login callback on one port: `127.0.0.1`, and
+/// `::1` when IPv6 is available, since browsers may resolve `localhost` to
+/// either.
+struct CallbackListener {
+    v4: TcpListener,
+    v6: Option<TcpListener>,
+}
+
+impl CallbackListener {
+    /// Bind `127.0.0.1` on a free port, then `::1` on the same port. When
+    /// IPv6 is unavailable or the port is taken there, only IPv4 is used.
+    async fn bind() -> Result<Self> {
+        let v4 = TcpListener::bind("127.0.0.1:0")
+            .await
+            .context("failed to bind local callback port")?;
+        let port = v4
+            .local_addr()
+            .context("failed to read local callback address")?
+            .port();
+        let v6 = TcpListener::bind(("::1", port)).await.ok();
+        Ok(Self { v4, v6 })
+    }
+
+    fn port(&self) -> u16 {
+        self.v4.local_addr().map(|addr| addr.port()).unwrap_or(0)
+    }
+
+    async fn accept(&self) -> std::io::Result<TcpStream> {
+        let (stream, _addr) = match &self.v6 {
+            Some(v6) => tokio::select! {
+                accepted = self.v4.accept() => accepted?,
+                accepted = v6.accept() => accepted?,
+            },
+            None => self.v4.accept().await?,
+        };
+        Ok(stream)
+    }
+}
+
+/// Most connections the callback listener serves in one login. Browsers
+/// add a few (favicon, preconnects); far more means something else is
+/// talking to the port.
+const MAX_CALLBACK_REQUESTS: usize = 64;
+
+/// The exchange code from the first valid callback, or `None` if none
+/// arrives before `deadline`.
+///
+/// Connections are served concurrently, so a client that connects and
+/// never sends a request cannot hold up the real callback. Requests that
+/// are not a valid callback are answered and otherwise ignored.
 async fn wait_for_exchange_code(
-    listener: &TcpListener,
+    listener: &CallbackListener,
     expected_state: &str,
     deadline: Instant,
-) -> Result<String> {
+) -> Result<Option<String>> {
+    let deadline = tokio::time::Instant::from_std(deadline);
+    let mut handlers = tokio::task::JoinSet::new();
+    let mut accepted = 0;
     loop {
-        if Instant::now() >= deadline {
-            bail!("login timed out waiting for browser callback");
+        if accepted >= MAX_CALLBACK_REQUESTS && handlers.is_empty() {
+            bail!(
+                "login callback received {MAX_CALLBACK_REQUESTS} requests without a valid \
+                 callback; something other than the browser is using the callback port"
+            );
         }
-
-        match tokio::time::timeout(Duration::from_millis(250), listener.accept()).await {
-            Ok(Ok((mut stream, _addr))) => {
-                if let Some(code) = handle_callback_request(&mut stream, expected_state).await? {
-                    return Ok(code);
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline) => return Ok(None),
+            accept = listener.accept(), if accepted < MAX_CALLBACK_REQUESTS => {
+                let mut stream = accept.context("failed while waiting for browser callback")?;
+                accepted += 1;
+                let expected_state = expected_state.to_string();
+                handlers.spawn(async move {
+                    handle_callback_request(&mut stream, &expected_state).await
+                });
+            }
+            Some(handled) = handlers.join_next() => {
+                if let Ok(Ok(Some(code))) = handled {
+                    return Ok(Some(code));
                 }
             }
-            Ok(Err(e)) => return Err(e).context("failed while waiting for browser callback"),
-            Err(_) => continue,
         }
     }
 }
@@ -349,6 +449,126 @@ mod tests {
         assert!(nonce.chars().all(|c| c.is_ascii_hexdigit()));
     }
 
+    #[tokio::test]
+    async fn pending_login_url_names_the_callback_port_and_state() {
+        let client = ApiClient::configured(
+            "https://dash.example.com/",
+            &crate::config::CliConfig::default(),
+        )
+        .unwrap();
+        let pending = PendingLogin::start(&client).await.unwrap();
+        let port = pending.listener.port();
+        assert_eq!(
+            pending.auth_url(),
+            format!(
+                "https://dash.example.com/auth/token?port={port}&state={}",
+                pending.nonce
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn timeout_names_the_callback_and_next_steps() {
+        let client = ApiClient::configured(
+            "https://dash.example.com",
+            &crate::config::CliConfig::default(),
+        )
+        .unwrap();
+        let pending = PendingLogin::start(&client).await.unwrap();
+        let port = pending.listener.port();
+        let err = pending
+            .finish(&client, Duration::ZERO)
+            .await
+            .expect_err("times out");
+        let message = err.to_string();
+        assert!(message.starts_with("login timed out after 0s"), "{message}");
+        assert!(message.contains("--timeout"), "{message}");
+        assert!(message.contains(&format!("localhost:{port}")), "{message}");
+    }
+
+    async fn send(addr: std::net::SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response).await;
+        response
+    }
+
+    #[tokio::test]
+    async fn junk_requests_do_not_delay_the_real_callback() {
+        let listener = CallbackListener::bind().await.unwrap();
+        let addr = listener.v4.local_addr().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let started = Instant::now();
+        let waiting =
+            tokio::spawn(
+                async move { wait_for_exchange_code(&listener, "good-state", deadline).await },
+            );
+
+        // A connection that never sends anything stays open throughout.
+        let _idle = TcpStream::connect(addr).await.unwrap();
+        let favicon = send(addr, "GET /favicon.ico HTTP/1.1\r\n\r\n").await;
+        assert!(favicon.starts_with("HTTP/1.1 404"), "{favicon}");
+        let preflight = send(addr, "OPTIONS /callback HTTP/1.1\r\n\r\n").await;
+        assert!(preflight.starts_with("HTTP/1.1 405"), "{preflight}");
+        let bad_state = send(addr, "GET /callback?code=c&state=bad HTTP/1.1\r\n\r\n").await;
+        assert!(bad_state.starts_with("HTTP/1.1 400"), "{bad_state}");
+        let ok = send(
+            addr,
+            "GET /callback?code=the-code&state=good-state HTTP/1.1\r\n\r\n",
+        )
+        .await;
+        assert!(ok.starts_with("HTTP/1.1 200"), "{ok}");
+
+        let code = waiting.await.unwrap().unwrap();
+        assert_eq!(code.as_deref(), Some("the-code"));
+        // The idle connection's 3s read timeout did not hold anything up.
+        assert!(started.elapsed() < Duration::from_secs(3));
+    }
+
+    #[tokio::test]
+    async fn callback_arrives_over_ipv6_loopback_when_available() {
+        let listener = CallbackListener::bind().await.unwrap();
+        let Some(v6) = &listener.v6 else {
+            // No IPv6 here: the listener fell back to IPv4 only.
+            return;
+        };
+        let addr = v6.local_addr().unwrap();
+        assert_eq!(addr.port(), listener.port());
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let waiting =
+            tokio::spawn(
+                async move { wait_for_exchange_code(&listener, "good-state", deadline).await },
+            );
+        let ok = send(
+            addr,
+            "GET /callback?code=v6-code&state=good-state HTTP/1.1\r\n\r\n",
+        )
+        .await;
+        assert!(ok.starts_with("HTTP/1.1 200"), "{ok}");
+        let code = waiting.await.unwrap().unwrap();
+        assert_eq!(code.as_deref(), Some("v6-code"));
+    }
+
+    #[tokio::test]
+    async fn callback_listener_gives_up_after_too_many_requests() {
+        let listener = CallbackListener::bind().await.unwrap();
+        let addr = listener.v4.local_addr().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(30);
+        let waiting =
+            tokio::spawn(
+                async move { wait_for_exchange_code(&listener, "good-state", deadline).await },
+            );
+        for _ in 0..MAX_CALLBACK_REQUESTS {
+            send(addr, "GET /favicon.ico HTTP/1.1\r\n\r\n").await;
+        }
+        let err = waiting.await.unwrap().expect_err("gives up");
+        assert!(
+            err.to_string()
+                .contains("requests without a valid callback")
+        );
+    }
+
     #[test]
     fn hex_encoder_round_trip_length() {
         let bytes = [0xde, 0xad, 0xbe, 0xef];
@@ -1,17 +1,22 @@
-mod agents;
 mod api_client;
+mod attribution;
 mod backfill_log;
 mod config;
 mod deferred_sync;
 mod git;
 mod keychain;
+mod log;
 mod login;
 mod note;
 mod output;
 mod pgp_keys;
 mod push;
-mod scanner;
+mod repo_config;
+mod selftest;
+mod settings;
+mod state;
 mod sync_pending;
+mod trailer;
 mod update;
 
 use anyhow::{Context, Result};
@@ -28,6 +33,8 @@ use std::time::Duration;
 use tokio::sync::{OnceCell, Semaphore};
 use tokio::task::JoinSet;
 
+use cadence_cli::{agents, matcher, scanner};
+
 use crate::keychain::KeychainStore;
 
 const KEYCHAIN_SERVICE: &str = "cadence-cli";
@@ -43,14 +50,22 @@ static API_URL_OVERRIDE: OnceCell<String> = OnceCell::const_new();
 #[derive(Parser, Debug)]
 #[command(name = "cadence", version, about)]
 struct Cli {
-    /// Enable verbose logging (e.g., git commands and output).
-    #[arg(long, global = true)]
+    /// Enable verbose logging (e.g., git commands and candidate scanning).
+    #[arg(short, long, global = true, conflicts_with = "quiet")]
     verbose: bool,
 
+    /// Only print notes and errors; hide progress and informational lines.
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
     /// API base URL override for this command invocation.
     #[arg(long, global = true)]
     api_url: Option<String>,
 
+    /// Run as if started in this repository instead of the current directory.
+    #[arg(long, global = true)]
+    repo: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Command,
 }
@@ -62,6 +77,9 @@ enum Command {
         /// Optional GitHub org filter for push scoping.
         #[arg(long)]
         org: Option<String>,
+        /// Write hooks that invoke this binary by absolute path.
+        #[arg(long)]
+        force: bool,
     },
 
     /// Git hook entry points.
@@ -75,10 +93,29 @@ enum Command {
         /// How far back to scan, e.g. "30d" for 30 days.
         #[arg(long, default_value = "30d")]
         since: String,
+
+        /// Scan only what changed since the last successful backfill. Falls
+        /// back to the `--since` window when there is no previous run.
+        #[arg(long)]
+        since_last: bool,
+
+        /// Only backfill sessions from this agent (e.g. "claude", "codex").
+        #[arg(long, value_parser = parse_agent_filter)]
+        agent: Option<scanner::AgentType>,
     },
 
     /// Sign in via browser OAuth and store a CLI token locally.
-    Login,
+    Login {
+        /// Seconds to wait for the browser to complete sign-in.
+        #[arg(long, value_name = "SECS", default_value_t = LOGIN_TIMEOUT_SECS,
+              value_parser = clap::value_parser!(u64).range(1..))]
+        timeout: u64,
+
+        /// Print the sign-in URL to stdout instead of opening a browser,
+        /// then wait for the callback as usual.
+        #[arg(long)]
+        print_url: bool,
+    },
 
     /// Revoke and clear local CLI authentication token.
     Logout,
@@ -101,6 +138,11 @@ enum Command {
         /// Attempt to repair auto-update scheduler artifacts based on config intent.
         #[arg(long)]
         repair: bool,
+
+        /// Print the checks as one JSON object on stdout instead of the
+        /// checklist. Exits non-zero when any check fails.
+        #[arg(long)]
+        json: bool,
     },
 
     /// Check for and install updates.
@@ -132,6 +174,47 @@ enum Command {
         keys_command: Option<KeysCommands>,
     },
 
+    /// Remove local Cadence state: pending sync jobs, logs, and the auth token.
+    Clean {
+        /// Remove queued session-ref sync jobs, pending session ingests and
+        /// unsent attribution events.
+        #[arg(long)]
+        pending: bool,
+
+        /// Remove sync, hook, and backfill logs.
+        #[arg(long)]
+        logs: bool,
+
+        /// Remove the stored auth token (without revoking it on the server).
+        #[arg(long)]
+        token: bool,
+
+        /// Remove everything above.
+        #[arg(long)]
+        all: bool,
+
+        /// Skip the confirmation prompt.
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+
+    /// Check that the post-commit hook stores sessions on this machine.
+    ///
+    /// Runs the hook against a fake session in a scratch repository and
+    /// exits non-zero if anything fails. Nothing outside it is touched.
+    #[command(hide = true)]
+    Selftest,
+
+    /// Measure how long the post-commit hook takes to find sessions here.
+    ///
+    /// Runs session matching against this repository's candidate logs
+    /// several times without storing anything, and reports the latency.
+    Bench {
+        /// How many times to run the matching.
+        #[arg(long, default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+        iterations: u32,
+    },
+
     /// Clear session refs and re-backfill.
     ///
     /// Deletes local and remote session refs, then re-runs backfill.
@@ -179,7 +262,15 @@ enum AutoUpdateCommand {
 #[derive(Subcommand, Debug)]
 enum HookCommand {
     /// Post-commit hook: ingest recent AI sessions for the current repository.
-    PostCommit,
+    PostCommit {
+        /// Print a single JSON result line to stdout (for editor integrations).
+        #[arg(long)]
+        report_json: bool,
+        /// Also print every session log considered and why each was or was
+        /// not stored. The hook still runs normally.
+        #[arg(long)]
+        explain: bool,
+    },
     /// Pre-push hook: sync session refs with the push remote.
     PrePush {
         /// Remote name provided by git.
@@ -187,12 +278,19 @@ enum HookCommand {
         /// Remote URL provided by git.
         url: String,
     },
+    /// Prepare-commit-msg hook: add a `Session-Id:` trailer when
+    /// `ai.cadence.injectTrailer` is enabled.
+    PrepareCommitMsg {
+        /// Path to the commit message file provided by git.
+        msg_file: PathBuf,
+        /// Message source provided by git (message, template, merge, squash, commit).
+        source: Option<String>,
+        /// Commit object name provided by git for amends.
+        sha: Option<String>,
+    },
     /// Deferred sync worker: process queued session-ref sync jobs.
     DeferredSync {
-        /// Repository path to sync (defaults to current repository).
-        #[arg(long)]
-        repo: Option<PathBuf>,
-        /// Remote name to sync (defaults to push remote or origin).
+        /// Remote name to sync (defaults to ai.cadence.remote, push remote, or origin).
         #[arg(long)]
         remote: Option<String>,
         /// Process queued pending sync jobs.
@@ -238,9 +336,17 @@ enum SessionsCommand {
         #[arg(long)]
         all: bool,
         /// Print the full stored record + session_content for each match.
+        /// Sessions stored by reference are re-read from their log file.
         #[arg(long)]
         raw: bool,
     },
+    /// Show the agent session logs the post-commit hook would consider for
+    /// the current repo, and whether each one matches.
+    Detect {
+        /// How far back to look for session logs, e.g. "7d".
+        #[arg(long, default_value = "7d")]
+        since: String,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -409,7 +515,7 @@ async fn resolve_api_public_key_cache(force_refresh: bool) -> Result<Option<Stri
 
     let cfg = config::CliConfig::load().await?;
     let resolved = cfg.resolve_api_url(api_url_override());
-    let client = api_client::ApiClient::new(&resolved.url);
+    let client = api_client::ApiClient::configured(&resolved.url, &cfg)?;
     let keys_url = format!("{}/api/keys/public", resolved.url.trim_end_matches('/'));
     let api_key = client
         .get_api_public_key()
@@ -446,14 +552,17 @@ async fn resolve_api_public_key_cache(force_refresh: bool) -> Result<Option<Stri
 /// 1. Set `git config --global core.hooksPath ~/.git-hooks`
 /// 2. Create `~/.git-hooks/` directory if missing
 /// 3. Write `~/.git-hooks/post-commit` shim script
-/// 4. Write `~/.git-hooks/pre-push` shim script
+/// 4. Write `~/.git-hooks/pre-push` and `~/.git-hooks/prepare-commit-msg` shim scripts
 /// 5. Make shims executable (chmod +x)
 /// 6. If `--org` provided, persist org filter to global git config
 ///
+/// With `force`, shims invoke the running binary by absolute path instead of
+/// `cadence` from `PATH`, repairing hooks left behind by a moved binary.
+///
 /// Errors at each step are reported but do not prevent subsequent steps
 /// from being attempted.
-async fn run_install(org: Option<String>) -> Result<()> {
-    run_install_inner(org, None).await
+async fn run_install(org: Option<String>, force: bool) -> Result<()> {
+    run_install_inner(org, force, None).await
 }
 
 fn is_cadence_hook(content: &str) -> bool {
@@ -488,17 +597,127 @@ fn debug_hook_exe_path() -> Option<String> {
     None
 }
 
-fn post_commit_hook_content() -> String {
-    format!("#!/bin/sh\nexec {} hook post-commit\n", hook_command_exe())
+/// The running binary's absolute path, quoted for the shim if needed.
+fn current_exe_for_hooks() -> Result<String> {
+    let exe = std::env::current_exe().context("could not resolve the current executable")?;
+    Ok(shell_quote(&exe.display().to_string()))
+}
+
+/// `word` as a single `sh` word: unchanged when it has no characters the
+/// shell treats specially, otherwise in single quotes, with each `'`
+/// written as `'\''`.
+fn shell_quote(word: &str) -> String {
+    let plain = |c: char| c.is_ascii_alphanumeric() || "/._-+:,@%=".contains(c);
+    if !word.is_empty() && word.chars().all(plain) {
+        return word.to_string();
+    }
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+/// The line of a shim that first runs the hook it replaced, saved by
+/// [`install_hook_shim`] as `<hook>.pre-cadence`, so installing Cadence
+/// does not silently drop a user's hook. Where git lets the hook stop the
+/// operation, the saved hook's failure stops it too.
+fn chain_saved_hook(can_stop: bool) -> &'static str {
+    if can_stop {
+        "if [ -x \"$0.pre-cadence\" ]; then \"$0.pre-cadence\" \"$@\" || exit $?; fi\n"
+    } else {
+        "if [ -x \"$0.pre-cadence\" ]; then \"$0.pre-cadence\" \"$@\"; fi\n"
+    }
+}
+
+fn post_commit_hook_content(exe: &str) -> String {
+    format!(
+        "#!/bin/sh\n{}exec {exe} hook post-commit\n",
+        chain_saved_hook(false)
+    )
 }
 
-fn pre_push_hook_content() -> String {
+fn pre_push_hook_content(exe: &str) -> String {
     format!(
-        "#!/bin/sh\nexec {} hook pre-push \"$1\" \"$2\"\n",
-        hook_command_exe()
+        "#!/bin/sh\n{}exec {exe} hook pre-push \"$1\" \"$2\"\n",
+        chain_saved_hook(true)
+    )
+}
+
+/// `injectTrailer` is off by default, so the prepare-commit-msg shim reads
+/// it itself and only starts Cadence when it is on. A `.cadence.toml` can
+/// only turn it off, which the hook still checks.
+fn prepare_commit_msg_hook_content(exe: &str) -> String {
+    format!(
+        "#!/bin/sh\n\
+         {}\
+         case \"${{{}:-$(git config --get {})}}\" in\n\
+         [Tt][Rr][Uu][Ee] | [Yy][Ee][Ss] | 1) ;;\n\
+         *) exit 0 ;;\n\
+         esac\n\
+         exec {exe} hook prepare-commit-msg \"$@\"\n",
+        chain_saved_hook(true),
+        settings::env_var_name(trailer::INJECT_TRAILER_KEY),
+        trailer::INJECT_TRAILER_KEY,
     )
 }
 
+/// The executable a Cadence shim runs, as written in its `exec` line.
+fn hook_shim_exe(content: &str) -> Option<String> {
+    let rest = content
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("exec "))?
+        .trim_start();
+    let exe = first_shell_word(rest);
+    (!exe.is_empty()).then_some(exe)
+}
+
+/// The first word of a `sh` command line, with single quotes, double quotes
+/// and backslash escapes removed.
+fn first_shell_word(line: &str) -> String {
+    let mut word = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => word.extend(chars.by_ref().take_while(|&c| c != '\'')),
+            '"' => word.extend(chars.by_ref().take_while(|&c| c != '"')),
+            '\\' => word.extend(chars.next()),
+            c if c.is_whitespace() => break,
+            c => word.push(c),
+        }
+    }
+    word
+}
+
+/// Whether `exe` names an existing file, either as a path or via `PATH`.
+fn hook_exe_resolves(exe: &str) -> bool {
+    let path = Path::new(exe);
+    if path.components().count() > 1 {
+        return path.is_file();
+    }
+    let Some(search) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&search).any(|dir| {
+        dir.join(exe).is_file() || (cfg!(windows) && dir.join(format!("{exe}.exe")).is_file())
+    })
+}
+
+/// If a Cadence shim in `hooks_dir` runs an executable that no longer
+/// exists, return the hook's name and that executable.
+async fn stale_hook_exe(hooks_dir: &Path) -> Option<(&'static str, String)> {
+    for hook_name in ["post-commit", "pre-push", "prepare-commit-msg"] {
+        let Ok(content) = tokio::fs::read_to_string(hooks_dir.join(hook_name)).await else {
+            continue;
+        };
+        if !is_cadence_hook(&content) {
+            continue;
+        }
+        if let Some(exe) = hook_shim_exe(&content)
+            && !hook_exe_resolves(&exe)
+        {
+            return Some((hook_name, exe));
+        }
+    }
+    None
+}
+
 fn resolve_hooks_path(repo_root: Option<&Path>, configured_path: &str) -> PathBuf {
     let path = Path::new(configured_path);
     if path.is_absolute() {
@@ -516,26 +735,126 @@ fn paths_equivalent(left: &Path, right: &Path) -> bool {
     left_norm == right_norm
 }
 
-async fn cadence_hooks_installed(hooks_dir: &Path) -> (bool, bool) {
-    let post_path = hooks_dir.join("post-commit");
-    let post_installed = match tokio::fs::read_to_string(&post_path).await {
-        Ok(content) => is_cadence_hook(&content),
-        Err(_) => false,
-    };
+/// Which Cadence shims a hooks directory holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct InstalledHooks {
+    post_commit: bool,
+    pre_push: bool,
+    prepare_commit_msg: bool,
+}
 
-    let pre_path = hooks_dir.join("pre-push");
-    let pre_installed = match tokio::fs::read_to_string(&pre_path).await {
-        Ok(content) => is_cadence_hook(&content),
-        Err(_) => false,
+impl InstalledHooks {
+    fn all(self) -> bool {
+        self.post_commit && self.pre_push && self.prepare_commit_msg
+    }
+
+    /// E.g. `post-commit: yes, pre-push: yes, prepare-commit-msg: no`.
+    fn summary(self) -> String {
+        let yes_no = |installed: bool| if installed { "yes" } else { "no" };
+        format!(
+            "post-commit: {}, pre-push: {}, prepare-commit-msg: {}",
+            yes_no(self.post_commit),
+            yes_no(self.pre_push),
+            yes_no(self.prepare_commit_msg)
+        )
+    }
+}
+
+async fn cadence_hooks_installed(hooks_dir: &Path) -> InstalledHooks {
+    let installed = |hook_name: &'static str| async move {
+        match tokio::fs::read_to_string(hooks_dir.join(hook_name)).await {
+            Ok(content) => is_cadence_hook(&content),
+            Err(_) => false,
+        }
     };
+    InstalledHooks {
+        post_commit: installed("post-commit").await,
+        pre_push: installed("pre-push").await,
+        prepare_commit_msg: installed("prepare-commit-msg").await,
+    }
+}
+
+/// Write the `hook_name` shim into `hooks_dir`, backing up a foreign hook
+/// first. Returns false if any step failed.
+async fn install_hook_shim(hooks_dir: &Path, hook_name: &str, content: String) -> bool {
+    let shim_path = hooks_dir.join(hook_name);
+    let mut label = hook_name.to_string();
+    label[..1].make_ascii_uppercase();
+
+    // Check if hook already exists
+    if tokio::fs::try_exists(&shim_path).await.unwrap_or(false) {
+        match tokio::fs::read_to_string(&shim_path).await {
+            Ok(existing) => {
+                if is_cadence_hook(&existing) {
+                    output::detail(&format!("{label} hook already installed; updating"));
+                } else {
+                    // Back up the existing hook before overwriting
+                    let backup_path = hooks_dir.join(format!("{hook_name}.pre-cadence"));
+                    match tokio::fs::copy(&shim_path, &backup_path).await {
+                        Ok(_) => {
+                            output::note(&format!(
+                                "Existing {} hook saved to {}",
+                                hook_name,
+                                backup_path.display()
+                            ));
+                        }
+                        Err(e) => {
+                            output::note(&format!(
+                                "Could not back up existing {} hook ({})",
+                                hook_name, e
+                            ));
+                        }
+                    }
+                }
+            }
+            Err(_) => {
+                output::note(&format!(
+                    "Could not read existing {}; overwriting",
+                    shim_path.display()
+                ));
+            }
+        }
+    }
+
+    if let Err(e) = tokio::fs::write(&shim_path, content).await {
+        output::fail(
+            "Failed",
+            &format!("to write {} ({})", shim_path.display(), e),
+        );
+        return false;
+    }
+    output::success(
+        "Wrote",
+        &format!("{} hook ({})", hook_name, shim_path.display()),
+    );
+
+    // Make executable (Unix only)
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let perms = std::fs::Permissions::from_mode(0o755);
+        match tokio::fs::set_permissions(&shim_path, perms).await {
+            Ok(()) => {
+                output::detail(&format!("Made {} executable", shim_path.display()));
+            }
+            Err(e) => {
+                output::fail(
+                    "Failed",
+                    &format!("to make {} executable ({})", shim_path.display(), e),
+                );
+                return false;
+            }
+        }
+    }
 
-    (post_installed, pre_installed)
+    true
 }
 
 /// Inner implementation of install, accepting an optional home directory override
 /// for testability. If `home_override` is `None`, uses the real home directory.
 async fn run_install_inner(
     org: Option<String>,
+    force: bool,
     home_override: Option<&std::path::Path>,
 ) -> Result<()> {
     println!();
@@ -583,160 +902,27 @@ async fn run_install_inner(
         ));
     }
 
-    // Step 3 & 4: Write post-commit shim and make it executable
-    let shim_path = hooks_dir.join("post-commit");
-    let shim_content = post_commit_hook_content();
-
-    // Check if hook already exists
-    let should_write = if tokio::fs::try_exists(&shim_path).await.unwrap_or(false) {
-        match tokio::fs::read_to_string(&shim_path).await {
-            Ok(existing) => {
-                if is_cadence_hook(&existing) {
-                    output::detail("Post-commit hook already installed; updating");
-                    true
-                } else {
-                    // Back up the existing hook before overwriting
-                    let backup_path = hooks_dir.join("post-commit.pre-cadence");
-                    match tokio::fs::copy(&shim_path, &backup_path).await {
-                        Ok(_) => {
-                            output::note(&format!(
-                                "Existing post-commit hook saved to {}",
-                                backup_path.display()
-                            ));
-                        }
-                        Err(e) => {
-                            output::note(&format!(
-                                "Could not back up existing post-commit hook ({})",
-                                e
-                            ));
-                        }
-                    }
-                    true
-                }
-            }
-            Err(_) => {
-                output::note(&format!(
-                    "Could not read existing {}; overwriting",
-                    shim_path.display()
-                ));
-                true
-            }
-        }
-    } else {
-        true
-    };
-
-    if should_write {
-        match tokio::fs::write(&shim_path, shim_content).await {
-            Ok(()) => {
-                output::success(
-                    "Wrote",
-                    &format!("post-commit hook ({})", shim_path.display()),
-                );
-
-                // Make executable (Unix only)
-                #[cfg(unix)]
-                {
-                    use std::os::unix::fs::PermissionsExt;
-                    let perms = std::fs::Permissions::from_mode(0o755);
-                    match tokio::fs::set_permissions(&shim_path, perms).await {
-                        Ok(()) => {
-                            output::detail(&format!("Made {} executable", shim_path.display()));
-                        }
-                        Err(e) => {
-                            output::fail(
-                                "Failed",
-                                &format!("to make {} executable ({})", shim_path.display(), e),
-                            );
-                            had_errors = true;
-                        }
-                    }
-                }
-            }
+    // Step 3 & 4: Write hook shims and make them executable
+    let exe = if force {
+        match current_exe_for_hooks() {
+            Ok(exe) => exe,
             Err(e) => {
-                output::fail(
-                    "Failed",
-                    &format!("to write {} ({})", shim_path.display(), e),
-                );
+                output::fail("Failed", &format!("{e}; using `cadence` from PATH"));
                 had_errors = true;
-            }
-        }
-    }
-
-    // Step 4b: Write pre-push shim and make it executable
-    let pre_push_path = hooks_dir.join("pre-push");
-    let pre_push_content = pre_push_hook_content();
-
-    let should_write_pre_push = if tokio::fs::try_exists(&pre_push_path).await.unwrap_or(false) {
-        match tokio::fs::read_to_string(&pre_push_path).await {
-            Ok(existing) => {
-                if is_cadence_hook(&existing) {
-                    output::detail("Pre-push hook already installed; updating");
-                    true
-                } else {
-                    let backup_path = hooks_dir.join("pre-push.pre-cadence");
-                    match tokio::fs::copy(&pre_push_path, &backup_path).await {
-                        Ok(_) => {
-                            output::note(&format!(
-                                "Existing pre-push hook saved to {}",
-                                backup_path.display()
-                            ));
-                        }
-                        Err(e) => {
-                            output::note(&format!(
-                                "Could not back up existing pre-push hook ({})",
-                                e
-                            ));
-                        }
-                    }
-                    true
-                }
-            }
-            Err(_) => {
-                output::note(&format!(
-                    "Could not read existing {}; overwriting",
-                    pre_push_path.display()
-                ));
-                true
+                hook_command_exe()
             }
         }
     } else {
-        true
+        hook_command_exe()
     };
-
-    if should_write_pre_push {
-        match tokio::fs::write(&pre_push_path, pre_push_content).await {
-            Ok(()) => {
-                output::success(
-                    "Wrote",
-                    &format!("pre-push hook ({})", pre_push_path.display()),
-                );
-
-                #[cfg(unix)]
-                {
-                    use std::os::unix::fs::PermissionsExt;
-                    let perms = std::fs::Permissions::from_mode(0o755);
-                    match tokio::fs::set_permissions(&pre_push_path, perms).await {
-                        Ok(()) => {
-                            output::detail(&format!("Made {} executable", pre_push_path.display()));
-                        }
-                        Err(e) => {
-                            output::fail(
-                                "Failed",
-                                &format!("to make {} executable ({})", pre_push_path.display(), e),
-                            );
-                            had_errors = true;
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                output::fail(
-                    "Failed",
-                    &format!("to write {} ({})", pre_push_path.display(), e),
-                );
-                had_errors = true;
-            }
+    let shims = [
+        ("post-commit", post_commit_hook_content(&exe)),
+        ("pre-push", pre_push_hook_content(&exe)),
+        ("prepare-commit-msg", prepare_commit_msg_hook_content(&exe)),
+    ];
+    for (hook_name, content) in shims {
+        if !install_hook_shim(&hooks_dir, hook_name, content).await {
+            had_errors = true;
         }
     }
 
@@ -799,7 +985,7 @@ async fn run_install_inner(
     Ok(())
 }
 
-async fn run_login() -> Result<()> {
+async fn run_login(timeout: Duration, print_url: bool) -> Result<()> {
     let mut cfg = config::CliConfig::load().await?;
     let resolved = cfg.resolve_api_url(api_url_override());
     output::detail(&format!("Using API URL: {}", resolved.url));
@@ -810,9 +996,40 @@ async fn run_login() -> Result<()> {
         ));
     }
 
-    output::action("Login", "opening browser for authentication");
-    let exchanged =
-        login::login_via_browser(&resolved.url, Duration::from_secs(LOGIN_TIMEOUT_SECS)).await?;
+    let client = api_client::ApiClient::configured(&resolved.url, &cfg)?;
+    let pending = login::PendingLogin::start(&client).await?;
+    if print_url {
+        output::action(
+            "Login",
+            &format!(
+                "open the URL below; waiting for the callback on localhost:{}",
+                pending.port()
+            ),
+        );
+        println!("{}", pending.auth_url());
+    } else {
+        output::action("Login", "opening browser for authentication");
+        if let Err(e) = open::that(pending.auth_url()) {
+            // Headless machines have no browser to open; the listener still
+            // works from a browser on this machine or through a forwarded port.
+            output::note(&format!(
+                "Could not open a browser ({e}). Open this URL in a browser on this machine \
+                 to continue:\n\n  {}\n",
+                pending.auth_url()
+            ));
+        }
+    }
+    let waiting = output::Spinner::start("Waiting for browser authentication");
+    let exchanged = match pending.finish(&client, timeout).await {
+        Ok(exchanged) => {
+            waiting.finish_and_clear();
+            exchanged
+        }
+        Err(e) => {
+            waiting.finish_err("Browser authentication did not complete");
+            return Err(e);
+        }
+    };
 
     cfg.api_url = Some(resolved.url.clone());
     cfg.token = Some(exchanged.token.clone());
@@ -840,13 +1057,13 @@ async fn run_logout() -> Result<()> {
     let resolved = cfg.resolve_api_url(api_url_override());
 
     if let Some(token) = resolve_cli_auth_token(&cfg).await {
-        let client = api_client::ApiClient::new(&resolved.url);
+        let client = api_client::ApiClient::configured(&resolved.url, &cfg)?;
         match client
             .revoke_token(&token, Duration::from_secs(API_TIMEOUT_SECS))
             .await
         {
             Ok(()) => output::detail("Revoked token on server."),
-            Err(api_client::AuthenticatedRequestError::Unauthorized) => {
+            Err(api_client::ApiError::Unauthorized) => {
                 output::note("Token was already invalid or expired.");
             }
             Err(err) => {
@@ -898,7 +1115,13 @@ async fn report_backfill_completion(window_days: i32, stats: BackfillSyncStats)
     };
 
     let resolved = cfg.resolve_api_url(api_url_override());
-    let client = api_client::ApiClient::new(&resolved.url);
+    let client = match api_client::ApiClient::configured(&resolved.url, &cfg) {
+        Ok(client) => client,
+        Err(e) => {
+            output::detail(&format!("Backfill sync skipped: {e:#}"));
+            return;
+        }
+    };
     let finished_at = time::OffsetDateTime::now_utc()
         .format(&time::format_description::well_known::Rfc3339)
         .unwrap_or_else(|_| "1970-01-01T00:00:00Z".to_string());
@@ -926,16 +1149,16 @@ async fn report_backfill_completion(window_days: i32, stats: BackfillSyncStats)
                 output::detail("Backfill sync already recorded.");
             }
         }
-        Err(api_client::AuthenticatedRequestError::Unauthorized) => {
+        Err(api_client::ApiError::Unauthorized) => {
             output::note("Run `cadence login` to re-authenticate");
         }
-        Err(api_client::AuthenticatedRequestError::Network(_)) => {
+        Err(api_client::ApiError::Network(_)) => {
             output::note("Notes are safely stored locally");
         }
-        Err(api_client::AuthenticatedRequestError::NotFound) => {
+        Err(api_client::ApiError::NotFound(_)) => {
             output::note("API does not support this yet");
         }
-        Err(api_client::AuthenticatedRequestError::Server(_)) => {
+        Err(api_client::ApiError::Server(_)) => {
             output::note("API returned an error");
         }
         Err(other) => {
@@ -954,79 +1177,337 @@ async fn report_backfill_completion(window_days: i32, stats: BackfillSyncStats)
 /// The outer wrapper uses `std::panic::catch_unwind` to catch panics, and
 /// pattern-matches on `HookError` to distinguish commit-blocking
 /// failures from soft failures that should be swallowed.
-async fn run_hook_post_commit() -> Result<()> {
+async fn run_hook_post_commit(report_json: bool, explain: bool) -> Result<()> {
+    let (repo_root, settings) = hook_context().await;
+    configure_hook_logging(&settings);
+    let started = std::time::Instant::now();
+
     // Catch-all: catch panics
-    let result = tokio::spawn(async { hook_post_commit_inner().await }).await;
+    let task_repo_root = repo_root.clone();
+    let task_settings = settings.clone();
+    let result = tokio::spawn(async move {
+        let mut metrics = HookMetrics::default();
+        let outcome =
+            hook_post_commit_inner(task_repo_root.as_deref(), &task_settings, &mut metrics).await;
+        (outcome, metrics)
+    })
+    .await;
+    let (result, metrics) = match result {
+        Ok((outcome, metrics)) => (Ok(outcome), metrics),
+        Err(e) => (Err(e), HookMetrics::default()),
+    };
 
+    let mut report = PostCommitReport::default();
+    let mut stored = Vec::new();
     let final_result = match result {
-        Ok(Ok(())) => Ok(()),
+        Ok(Ok(outcome)) => {
+            report_post_commit_outcome(&outcome);
+            if let PostCommitOutcome::Stored(sessions) = &outcome {
+                if let Some(repo_root) = &repo_root {
+                    report_commit_attribution(repo_root, &settings, sessions).await;
+                }
+                stored = sessions.clone();
+            }
+            report = PostCommitReport::from(outcome);
+            Ok(())
+        }
         Ok(Err(HookError::EncryptionFailed(msg))) => {
             output::fail("Encryption", &format!("failed ({})", msg));
-            anyhow::bail!("Encryption configured but failed: {}", msg);
+            report.error = Some(format!("encryption failed: {msg}"));
+            Err(anyhow::anyhow!("Encryption configured but failed: {}", msg))
         }
         Ok(Err(HookError::Soft(e))) => {
-            output::note(&format!("Hook issue: {}", e));
+            log::warn(&format!("Hook issue: {}", e));
+            report.error = Some(e.to_string());
             Ok(())
         }
         Err(e) => {
-            if e.is_panic() {
-                output::note("Hook panicked (please report this issue)");
-            } else {
-                output::note(&format!("Hook task failed: {}", e));
-            }
+            let msg = hook_join_error_message(e);
+            log::warn(&msg);
+            report.error = Some(msg);
             Ok(())
         }
     };
 
-    eprintln!();
-    final_result
-}
-
-/// The pre-push hook handler. Must never block the push.
-async fn run_hook_pre_push(remote: &str, url: &str) -> Result<()> {
-    let remote = remote.to_string();
-    let url = url.to_string();
-    let result = tokio::spawn(async move { hook_pre_push_inner(&remote, &url).await }).await;
+    record_hook_metrics(&report, &metrics, started.elapsed()).await;
+
+    if explain
+        && let Err(e) = explain_post_commit(&report, &stored, repo_root.as_deref(), &settings).await
+    {
+        log::warn(&format!("Could not explain the match: {e}"));
+    }
+
+    if report_json {
+        report.commit = git::resolve_commit_at(None, "HEAD").await.ok();
+        if let Ok(line) = serde_json::to_string(&report) {
+            println!("{line}");
+        }
+    }
+
+    if !log::writes_to_file() {
+        eprintln!();
+    }
+    final_result
+}
+
+/// What one post-commit run looked at, for the hook metrics log.
+#[derive(Debug, Default)]
+struct HookMetrics {
+    /// Session logs considered, in the window and by the subject fallback.
+    candidates: usize,
+    /// The strongest confidence among the sessions stored.
+    confidence: Option<matcher::Confidence>,
+    /// Nothing was in the window and the commit-subject fallback ran.
+    subject_fallback: bool,
+}
+
+/// The metrics row for one post-commit run.
+fn hook_metrics_row(
+    report: &PostCommitReport,
+    metrics: &HookMetrics,
+    elapsed: Duration,
+) -> serde_json::Value {
+    serde_json::json!({
+        "duration_ms": elapsed.as_millis() as u64,
+        "candidates": metrics.candidates,
+        "matched": report.stored,
+        "sessions": report.sessions,
+        "confidence": metrics.confidence.map(matcher::Confidence::as_str),
+        "pending": report.pending,
+        "skipped": report.skipped.is_some(),
+        "error": report.error.is_some(),
+    })
+}
+
+/// Append a `post_commit` row to `hook-metrics.log` in the config
+/// directory, for looking at match rates over time. Never fails.
+async fn record_hook_metrics(report: &PostCommitReport, metrics: &HookMetrics, elapsed: Duration) {
+    let logger = match backfill_log::BackfillLogger::hook_metrics().await {
+        Ok(logger) => logger,
+        Err(e) => {
+            log::debug(&format!("not recording hook metrics: {e:#}"));
+            return;
+        }
+    };
+    logger.event("post_commit", hook_metrics_row(report, metrics, elapsed));
+    logger.flush().await;
+}
+
+/// Queue opt-in attribution events for the commit just made; see
+/// [`attribution`]. Never fails.
+async fn report_commit_attribution(
+    repo_root: &Path,
+    settings: &settings::Settings,
+    sessions: &[StoredSession],
+) {
+    if sessions.is_empty() {
+        return;
+    }
+    let Ok(commit) = git::resolve_commit_at(Some(repo_root), "HEAD").await else {
+        return;
+    };
+    let sessions: Vec<(String, String)> = sessions
+        .iter()
+        .map(|s| (s.agent.clone(), s.session_id.clone()))
+        .collect();
+    attribution::record_commit(repo_root, settings, &commit, &sessions).await;
+}
+
+/// The repository a hook runs in and its settings, resolved once when the
+/// hook starts and passed down from there. Outside a repository only the
+/// global settings apply.
+async fn hook_context() -> (Option<PathBuf>, settings::Settings) {
+    let repo_root = git::repo_root().await.ok();
+    let settings = settings::Settings::load(repo_root.as_deref()).await;
+    (repo_root, settings)
+}
+
+/// Honor `ai.cadence.hookLog = file`: hook warnings and diagnostics go to
+/// `<state dir>/logs/hooks.log`, and spinners and success lines are hidden.
+/// Encryption failures that block a commit are still printed.
+fn configure_hook_logging(settings: &settings::Settings) {
+    if !settings.hook_log_to_file {
+        return;
+    }
+    let Some(dir) = state::state_dir_path() else {
+        return;
+    };
+    log::set_file_sink(dir.join("logs").join("hooks.log"));
+    output::set_verbosity(output::Verbosity::Quiet);
+}
+
+/// Describe a failed hook task, including the panic message when it panicked.
+///
+/// The default panic hook has already printed the location (and a backtrace
+/// when `RUST_BACKTRACE` is set) by the time this runs.
+fn hook_join_error_message(err: tokio::task::JoinError) -> String {
+    if err.is_panic() {
+        format!(
+            "Hook panicked: {} (please report this issue)",
+            panic_message(err.into_panic().as_ref())
+        )
+    } else {
+        format!("Hook task failed: {}", err)
+    }
+}
+
+/// Extract the message from a panic payload (`&str` or `String`).
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(msg) = payload.downcast_ref::<&str>() {
+        (*msg).to_string()
+    } else if let Some(msg) = payload.downcast_ref::<String>() {
+        msg.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
+/// The pre-push hook handler. Must never block the push.
+async fn run_hook_pre_push(remote: &str, url: &str) -> Result<()> {
+    let (repo_root, settings) = hook_context().await;
+    configure_hook_logging(&settings);
+    let remote = remote.to_string();
+    let url = url.to_string();
+    let result = tokio::spawn(async move {
+        hook_pre_push_inner(&remote, &url, repo_root.as_deref(), &settings).await
+    })
+    .await;
 
     match result {
         Ok(Ok(())) => {}
         Ok(Err(e)) => {
-            output::note(&format!("Hook issue: {}", e));
+            log::warn(&format!("Hook issue: {}", e));
         }
         Err(e) => {
-            if !e.is_panic() {
-                output::note(&format!("Hook task failed: {}", e));
-            }
-            output::note("Hook panicked (please report this issue)");
+            log::warn(&hook_join_error_message(e));
         }
     }
 
-    eprintln!();
+    if !log::writes_to_file() {
+        eprintln!();
+    }
+    Ok(())
+}
+
+/// The prepare-commit-msg hook handler. Must never block the commit.
+async fn run_hook_prepare_commit_msg(msg_file: &Path, source: Option<&str>) -> Result<()> {
+    let (repo_root, settings) = hook_context().await;
+    configure_hook_logging(&settings);
+    let msg_file = msg_file.to_path_buf();
+    let source = source.map(str::to_string);
+    let result = tokio::spawn(async move {
+        hook_prepare_commit_msg_inner(
+            &msg_file,
+            source.as_deref(),
+            repo_root.as_deref(),
+            &settings,
+        )
+        .await
+    })
+    .await;
+
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            log::warn(&format!("Hook issue: {}", e));
+        }
+        Err(e) => {
+            log::warn(&hook_join_error_message(e));
+        }
+    }
     Ok(())
 }
 
+/// Inner implementation of the prepare-commit-msg hook.
+async fn hook_prepare_commit_msg_inner(
+    msg_file: &Path,
+    source: Option<&str>,
+    repo_root: Option<&Path>,
+    settings: &settings::Settings,
+) -> Result<()> {
+    if !hook_home_available() {
+        return Ok(());
+    }
+    if !settings.enabled || !settings.inject_trailer {
+        return Ok(());
+    }
+    // Merge and squash messages are generated by git and edited rarely;
+    // leave them as they are.
+    if matches!(source, Some("merge" | "squash")) {
+        return Ok(());
+    }
+
+    let repo_root = repo_root.context("not in a git repository")?;
+    let Some(session) = current_session_for_repo(repo_root, settings).await else {
+        log::debug("no recent agent session for this repository; no trailer added");
+        return Ok(());
+    };
+    let trailers = settings
+        .trailer_format
+        .trailers(&session.agent.to_string(), &session.session_id);
+    if trailer::append_session_trailers(msg_file, &trailers).await? {
+        log::debug(&format!(
+            "added {}: {} to the commit message",
+            trailer::SESSION_ID_TRAILER,
+            session.session_id
+        ));
+    }
+    Ok(())
+}
+
+/// The agent session most likely behind a commit made now in `repo_root`.
+async fn current_session_for_repo(
+    repo_root: &Path,
+    settings: &settings::Settings,
+) -> Option<matcher::MatchedSession> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    matcher::Matcher::new(settings.window_before_secs)
+        .match_parent_cwd(settings.match_parent_cwd)
+        .min_confidence(settings.min_confidence)
+        .best_session(repo_root, now)
+        .await
+}
+
 /// Inner implementation of the post-commit hook.
 ///
 /// Returns `HookError::EncryptionFailed` if encryption is configured but
 /// fails — this is the only case where the hook blocks the commit. All other
 /// errors are wrapped in `HookError::Soft` and swallowed by the caller.
-async fn hook_post_commit_inner() -> std::result::Result<(), HookError> {
-    // Step 0: Per-repo enabled check — if disabled, skip EVERYTHING
-    if !git::check_enabled().await {
-        return Ok(());
+async fn hook_post_commit_inner(
+    repo_root: Option<&Path>,
+    settings: &settings::Settings,
+    metrics: &mut HookMetrics,
+) -> std::result::Result<PostCommitOutcome, HookError> {
+    // Step 0: Without a home directory there is nowhere to keep state.
+    if !hook_home_available() {
+        return Ok(PostCommitOutcome::Skipped(SkipReason::NoHome));
+    }
+
+    // Step 0.5: If Cadence is disabled for the repository, skip EVERYTHING
+    let repo_root = repo_root.context("not in a git repository")?.to_path_buf();
+    let repo_root_str = repo_root.to_string_lossy().to_string();
+    if !settings.enabled {
+        return Ok(PostCommitOutcome::Skipped(SkipReason::Disabled));
     }
     let _activity_lock = update::acquire_activity_lock_blocking("hook-post-commit")
         .await
         .map_err(HookError::Soft)?;
 
-    // Step 1: Get repo root
-    let repo_root = git::repo_root().await?;
-    let repo_root_str = repo_root.to_string_lossy().to_string();
+    // Step 1.1: Bot and pipeline commits are never linked to sessions.
+    if head_author_skipped(&repo_root, settings).await {
+        return Ok(PostCommitOutcome::Skipped(SkipReason::Author));
+    }
+    if settings.only_my_commits && !head_authored_by_user(&repo_root).await {
+        return Ok(PostCommitOutcome::Skipped(SkipReason::NotMine));
+    }
 
     // Step 1.25: Org filter gating — skip session storage if mismatched
-    match git::repo_matches_org_filter(&repo_root).await {
+    match git::repo_matches_org_filter(&repo_root, settings).await {
         Ok(true) => {}
-        Ok(false) => return Ok(()),
+        Ok(false) => return Ok(PostCommitOutcome::Skipped(SkipReason::OrgFilter)),
         Err(e) => return Err(HookError::Soft(e)),
     }
 
@@ -1036,116 +1517,698 @@ async fn hook_post_commit_inner() -> std::result::Result<(), HookError> {
         HookError::Soft(e)
     })?;
 
-    let storing_progress = hook_status_spinner_start("Storing AI sessions");
-    let scanned = match ingest_recent_sessions_for_repo(
-        &repo_root,
-        &repo_root_str,
-        POST_COMMIT_MATCH_WINDOW_SECS,
-        &encryption_method,
-    )
-    .await
-    {
-        Ok(scanned) => {
-            hook_status_spinner_finish_ok(storing_progress, "Storing AI sessions");
-            scanned
+    let hook_timeout = settings.hook_timeout;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let anchor = commit_anchor(&repo_root, settings, now).await;
+    let window = MatchWindow::around(anchor, settings, now);
+    let storing_task = hook_status_task("Storing AI sessions");
+    let storing_progress = output::Spinner::start(&storing_task);
+    // Only the scan runs under the time budget: dropping it part way leaves
+    // nothing behind, while a ref update cut short would.
+    let scan = match_commit(&repo_root, window, settings, metrics);
+    let scan_result = match hook_timeout {
+        Some(budget) => match tokio::time::timeout(budget, scan).await {
+            Ok(result) => result,
+            Err(_) if metrics.subject_fallback => {
+                // Nothing was in the window, so there is nothing for the
+                // push to pick up either.
+                storing_progress.finish_and_clear();
+                log::debug("commit-subject fallback ran out of time; nothing stored");
+                return Ok(PostCommitOutcome::Stored(Vec::new()));
+            }
+            Err(_) => {
+                // The pre-push hook scans the window again from the
+                // pending record.
+                storing_progress.finish_and_clear();
+                if let Err(e) = defer_commit(&repo_root, &repo_root_str, anchor).await {
+                    log::debug(&format!("could not record pending commit: {e:#}"));
+                }
+                return Ok(PostCommitOutcome::Deferred { budget });
+            }
+        },
+        None => scan.await,
+    };
+    let ingest_result = match scan_result {
+        Ok(matches) => {
+            store_commit_matches(
+                matches,
+                &repo_root,
+                &repo_root_str,
+                settings,
+                &encryption_method,
+                metrics,
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+    match ingest_result {
+        Ok(stored) => {
+            storing_progress.finish_ok(&storing_task);
+            Ok(PostCommitOutcome::Stored(stored))
         }
         Err(e) => {
-            hook_status_spinner_finish_err(storing_progress, "Storing AI sessions");
-            return Err(if encryption_method.is_configured() {
+            storing_progress.finish_err(&storing_task);
+            Err(if encryption_method.is_configured() {
                 HookError::EncryptionFailed(format!("{:#}", e))
             } else {
                 HookError::Soft(e)
-            });
+            })
+        }
+    }
+}
+
+/// Store the sessions matching the commit just made: [`match_commit`]
+/// followed by [`store_commit_matches`].
+#[cfg(test)]
+async fn ingest_for_commit(
+    repo_root: &Path,
+    repo_root_str: &str,
+    window: MatchWindow,
+    settings: &settings::Settings,
+    method: &EncryptionMethod,
+    metrics: &mut HookMetrics,
+) -> Result<Vec<StoredSession>> {
+    let matches = match_commit(repo_root, window, settings, metrics).await?;
+    store_commit_matches(matches, repo_root, repo_root_str, settings, method, metrics).await
+}
+
+/// The reading half of the post-commit hook: find the sessions matching the
+/// commit just made, those in `window`, otherwise, with `subjectFallback`
+/// set, the newest one naming the commit subject. Only session logs are
+/// read, so the hook can abandon this at its time budget without leaving
+/// refs half written.
+///
+/// An agent may still be writing the log that records the commit when the
+/// hook runs. With `recheckDelayMs` set, a first look that finds nothing in
+/// the window waits that long and looks once more. The wait counts against
+/// the caller's time budget.
+async fn match_commit(
+    repo_root: &Path,
+    window: MatchWindow,
+    settings: &settings::Settings,
+    metrics: &mut HookMetrics,
+) -> Result<CommitMatches> {
+    let mut matches = CommitMatches::new(
+        consider_recent_sessions(
+            repo_root,
+            window.lookback_secs,
+            window.not_after,
+            settings,
+            metrics,
+        )
+        .await,
+        settings,
+    );
+    if matches.selected.is_empty()
+        && let Some(delay) = settings.recheck_delay
+    {
+        log::debug(&format!(
+            "no session in the window; looking again in {}ms",
+            delay.as_millis()
+        ));
+        tokio::time::sleep(delay).await;
+        matches = CommitMatches::new(
+            consider_recent_sessions(
+                repo_root,
+                window.lookback_secs,
+                window.not_after,
+                settings,
+                metrics,
+            )
+            .await,
+            settings,
+        );
+    }
+    if !matches.selected.is_empty() || !settings.subject_fallback {
+        return Ok(matches);
+    }
+    metrics.subject_fallback = true;
+    matches.by_subject = find_session_by_subject(repo_root, window, settings, metrics).await?;
+    Ok(matches)
+}
+
+/// The writing half of the post-commit hook: store what [`match_commit`]
+/// found, and leave the logs below `minConfidence` as pending records.
+async fn store_commit_matches(
+    matches: CommitMatches,
+    repo_root: &Path,
+    repo_root_str: &str,
+    settings: &settings::Settings,
+    method: &EncryptionMethod,
+    metrics: &mut HookMetrics,
+) -> Result<Vec<StoredSession>> {
+    for log in &matches.below_min_confidence {
+        if let Err(e) = defer_below_min_confidence(repo_root_str, log).await {
+            log::debug(&format!("could not record pending session log: {e:#}"));
+        }
+    }
+    let mut stored = store_selected_sessions(
+        matches.selected,
+        repo_root,
+        repo_root_str,
+        settings,
+        method,
+        metrics,
+    )
+    .await?;
+    if let Some(parsed) = matches.by_subject {
+        stored.push(
+            store_subject_match(parsed, repo_root, repo_root_str, settings, method, metrics)
+                .await?,
+        );
+    }
+    Ok(stored)
+}
+
+/// What [`match_commit`] found for a commit; nothing is stored yet.
+#[derive(Default)]
+struct CommitMatches {
+    /// Logs in the window that clear `minConfidence`.
+    selected: Vec<ConsideredLog>,
+    /// Logs in the window that match below `minConfidence`.
+    below_min_confidence: Vec<agents::SessionLog>,
+    /// The commit-subject fallback's pick, when nothing was selected.
+    by_subject: Option<ParsedSessionLog>,
+}
+
+impl CommitMatches {
+    fn new(considered: Vec<ConsideredLog>, settings: &settings::Settings) -> Self {
+        let mut matches = Self::default();
+        for considered_log in considered {
+            if considered_log.selected(settings) {
+                matches.selected.push(considered_log);
+            } else if considered_log.candidate.is_match() {
+                matches.below_min_confidence.push(considered_log.parsed.log);
+            }
+        }
+        matches
+    }
+}
+
+/// A session stored by the post-commit hook.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StoredSession {
+    agent: String,
+    session_id: String,
+}
+
+/// What the post-commit hook did. The hook itself prints nothing about the
+/// outcome; [`report_post_commit_outcome`] and `--report-json` do.
+#[derive(Debug, PartialEq, Eq)]
+enum PostCommitOutcome {
+    /// Recent sessions for this repository were stored (possibly none).
+    Stored(Vec<StoredSession>),
+    /// Finding sessions exceeded the time budget; a pending record leaves
+    /// them to the next push.
+    Deferred { budget: Duration },
+    /// The hook returned before looking for sessions.
+    Skipped(SkipReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SkipReason {
+    NoHome,
+    Disabled,
+    Author,
+    NotMine,
+    OrgFilter,
+}
+
+impl SkipReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            SkipReason::NoHome => "home directory is not set",
+            SkipReason::Disabled => "disabled for this repository",
+            SkipReason::Author => "commit author is listed in ai.cadence.skipAuthors",
+            SkipReason::NotMine => "commit author is not user.email (ai.cadence.onlyMyCommits)",
+            SkipReason::OrgFilter => "repository does not match the org filter",
+        }
+    }
+}
+
+fn report_post_commit_outcome(outcome: &PostCommitOutcome) {
+    match outcome {
+        PostCommitOutcome::Stored(sessions) => {
+            log::debug(&format!("ingested {} recent sessions", sessions.len()));
+        }
+        PostCommitOutcome::Deferred { budget } => {
+            log::warn(&format!(
+                "Storing AI sessions exceeded {}ms; deferring to the next push (see {})",
+                budget.as_millis(),
+                settings::HOOK_TIMEOUT_KEY
+            ));
+        }
+        PostCommitOutcome::Skipped(reason) => {
+            log::debug(&format!("skipping post-commit: {}", reason.as_str()));
+        }
+    }
+}
+
+/// Result of the post-commit hook, printed as JSON with `--report-json`.
+#[derive(Debug, Default, serde::Serialize)]
+struct PostCommitReport {
+    /// HEAD after the commit.
+    commit: Option<String>,
+    /// Whether at least one session was stored.
+    stored: bool,
+    /// Number of sessions stored by this run.
+    sessions: usize,
+    /// Agents of the stored sessions, sorted and deduplicated.
+    agents: Vec<String>,
+    /// Storing ran out of time; the next push picks the sessions up.
+    pending: bool,
+    /// Why the hook returned early, if it did.
+    skipped: Option<String>,
+    /// The issue that stopped the hook, if any.
+    error: Option<String>,
+}
+
+impl From<PostCommitOutcome> for PostCommitReport {
+    fn from(outcome: PostCommitOutcome) -> Self {
+        let mut report = PostCommitReport::default();
+        match outcome {
+            PostCommitOutcome::Stored(sessions) => {
+                report.sessions = sessions.len();
+                report.stored = !sessions.is_empty();
+                report.agents = sessions.into_iter().map(|s| s.agent).collect();
+                report.agents.sort();
+                report.agents.dedup();
+            }
+            PostCommitOutcome::Deferred { .. } => report.pending = true,
+            PostCommitOutcome::Skipped(reason) => {
+                report.skipped = Some(reason.as_str().to_string());
+            }
         }
+        report
+    }
+}
+
+/// `hook post-commit --explain`: after the hook has run, list every session
+/// log in the window and why each was or was not stored. Printed to stderr,
+/// so `--report-json` output stays parseable.
+async fn explain_post_commit(
+    report: &PostCommitReport,
+    stored: &[StoredSession],
+    repo_root: Option<&Path>,
+    settings: &settings::Settings,
+) -> Result<()> {
+    let repo_root = repo_root.context("not in a git repository")?;
+    output::action("Explain", &repo_root.to_string_lossy());
+    if let Some(reason) = &report.skipped {
+        output::note(&format!("Skipped: {reason}"));
+        return Ok(());
+    }
+    if report.pending {
+        output::note("Storing ran out of time; the next push stores these sessions.");
+    }
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let window = MatchWindow::around(commit_anchor(repo_root, settings, now).await, settings, now);
+    // The hook's own matching, without `not_after`, so logs updated after
+    // the window are listed with that reason.
+    let mut candidates: Vec<matcher::Candidate> = consider_recent_sessions(
+        repo_root,
+        window.lookback_secs,
+        None,
+        settings,
+        &mut HookMetrics::default(),
+    )
+    .await
+    .into_iter()
+    .map(|considered| considered.candidate)
+    .collect();
+    candidates.sort_by(|a, b| matcher::closeness(a).cmp(&matcher::closeness(b)));
+    output::detail(&format!(
+        "{} session logs in the {}",
+        candidates.len(),
+        describe_window(window.lookback_secs)
+    ));
+    if !candidates.is_empty() {
+        let rows: Vec<Vec<String>> = candidates
+            .iter()
+            .map(|c| {
+                vec![
+                    c.evidence
+                        .mtime_delta_secs
+                        .map_or_else(|| "-".to_string(), format_mtime_delta),
+                    c.agent.to_string(),
+                    c.session_id.clone().unwrap_or_else(|| "-".to_string()),
+                    c.evidence.cwd_match.as_str().to_string(),
+                    explain_decision(c, window, stored, settings.min_confidence),
+                ]
+            })
+            .collect();
+        output::table(&["updated", "agent", "session", "cwd", "decision"], &rows);
+    }
+
+    let Some((session, confidence)) = explain_winner(&candidates, stored, now) else {
+        output::note("No session was stored for this commit.");
+        return Ok(());
     };
-    if output::is_verbose() {
-        output::detail(&format!("ingested {} recent sessions", scanned));
+    output::success(
+        "Best match",
+        &format!(
+            "{} {} (confidence: {confidence})",
+            session.agent, session.session_id
+        ),
+    );
+    Ok(())
+}
+
+/// `cadence bench`: time the post-commit hook's matching against the
+/// current repository's session logs: the same reads and checks the hook
+/// makes, including the commit-subject fallback when it is enabled and
+/// nothing matched, without storing anything.
+async fn run_bench(iterations: u32, repo: Option<&Path>) -> Result<()> {
+    let repo_root = command_repo_root(repo).await?;
+    let settings = settings::Settings::load(Some(&repo_root)).await;
+    output::action(
+        "Bench",
+        &format!(
+            "{iterations} matching run(s) in {}",
+            repo_root.to_string_lossy()
+        ),
+    );
+
+    let mut samples = Vec::with_capacity(iterations as usize);
+    let mut candidates = 0;
+    let mut matched = 0;
+    let mut lookback_secs = settings.window_before_secs;
+    for _ in 0..iterations {
+        let started = std::time::Instant::now();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let window = MatchWindow::around(
+            commit_anchor(&repo_root, &settings, now).await,
+            &settings,
+            now,
+        );
+        lookback_secs = window.lookback_secs;
+        let mut metrics = HookMetrics::default();
+        let found = consider_recent_sessions(
+            &repo_root,
+            window.lookback_secs,
+            window.not_after,
+            &settings,
+            &mut metrics,
+        )
+        .await;
+        candidates = found.len();
+        matched = found.iter().filter(|c| c.selected(&settings)).count();
+        if matched == 0 && settings.subject_fallback {
+            match find_session_by_subject(&repo_root, window, &settings, &mut metrics).await {
+                Ok(found) => matched = usize::from(found.is_some()),
+                Err(e) => log::debug(&format!("commit-subject fallback failed: {e:#}")),
+            }
+        }
+        samples.push(started.elapsed());
     }
 
+    let latency = LatencySummary::of(&mut samples);
+    output::detail(&format!(
+        "{candidates} session logs in the {}, {matched} matching this repository",
+        describe_window(lookback_secs)
+    ));
+    output::table(
+        &["min", "median", "p95"],
+        &[vec![
+            format_latency(latency.min),
+            format_latency(latency.median),
+            format_latency(latency.p95),
+        ]],
+    );
+    if let Some(budget) = settings.hook_timeout
+        && latency.p95 > budget
+    {
+        output::note(&format!(
+            "p95 exceeds {} ({}ms); commits here will defer storing to the next push. \
+             A smaller {} means fewer logs to read.",
+            settings::HOOK_TIMEOUT_KEY,
+            budget.as_millis(),
+            settings::WINDOW_BEFORE_KEY
+        ));
+    }
     Ok(())
 }
 
+/// Latency percentiles over a set of timed runs.
+#[derive(Debug, PartialEq, Eq)]
+struct LatencySummary {
+    min: Duration,
+    median: Duration,
+    p95: Duration,
+}
+
+impl LatencySummary {
+    /// Summarize `samples` (sorted in place) using the nearest-rank method.
+    /// `samples` must not be empty.
+    fn of(samples: &mut [Duration]) -> Self {
+        samples.sort();
+        let rank = |percent: usize| {
+            let index = (samples.len() * percent).div_ceil(100).max(1) - 1;
+            samples[index]
+        };
+        Self {
+            min: samples[0],
+            median: rank(50),
+            p95: rank(95),
+        }
+    }
+}
+
+fn format_latency(d: Duration) -> String {
+    format!("{:.1}ms", d.as_secs_f64() * 1_000.0)
+}
+
+/// Why the post-commit hook did or did not store `candidate`.
+fn explain_decision(
+    candidate: &matcher::Candidate,
+    window: MatchWindow,
+    stored: &[StoredSession],
+    min_confidence: matcher::Confidence,
+) -> String {
+    let id = candidate.session_id.as_deref().unwrap_or("unknown");
+    let is_stored = stored
+        .iter()
+        .any(|s| s.session_id == id && s.agent == candidate.agent.to_string());
+    if is_stored {
+        return "stored".to_string();
+    }
+    if !candidate.evidence.clears(min_confidence) {
+        return match candidate.evidence.rejection() {
+            Some(rejection) => rejection.to_string(),
+            None => format!("below {}", settings::MIN_CONFIDENCE_KEY),
+        };
+    }
+    match (window.not_after, candidate.updated_at) {
+        (Some(not_after), Some(updated)) if updated > not_after => {
+            "updated after the window".to_string()
+        }
+        _ => "matched, not stored".to_string(),
+    }
+}
+
+/// The stored session the commit most likely came from, ranked like
+/// [`matcher::Matcher::best_session`], with its match confidence. A stored
+/// session outside the window was linked by commit subject alone.
+fn explain_winner(
+    candidates: &[matcher::Candidate],
+    stored: &[StoredSession],
+    reference: i64,
+) -> Option<(StoredSession, &'static str)> {
+    let mut matched: Vec<(matcher::MatchedSession, &'static str)> = candidates
+        .iter()
+        .filter_map(|c| {
+            let confidence = c.evidence.confidence()?.as_str();
+            // Logs without a session id are stored under "unknown".
+            let session = matcher::MatchedSession {
+                agent: c.agent.clone(),
+                session_id: c
+                    .session_id
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string()),
+                cwd: c.cwd.clone()?,
+                cwd_is_root: c.evidence.cwd_match == matcher::CwdMatch::Root,
+                updated_at: c.updated_at,
+                source_label: c.source_label.clone(),
+            };
+            Some((session, confidence))
+        })
+        .filter(|(m, _)| {
+            stored
+                .iter()
+                .any(|s| s.session_id == m.session_id && s.agent == m.agent.to_string())
+        })
+        .collect();
+    matched.sort_by(|(a, _), (b, _)| {
+        matcher::rank_key(a, reference).cmp(&matcher::rank_key(b, reference))
+    });
+    match matched.into_iter().next() {
+        Some((session, confidence)) => Some((
+            StoredSession {
+                agent: session.agent.to_string(),
+                session_id: session.session_id,
+            },
+            confidence,
+        )),
+        None => stored
+            .first()
+            .map(|s| (s.clone(), "low (commit subject only)")),
+    }
+}
+
+/// `delta_secs` from a log's last update to the match time, as "5m before"
+/// or "30s after".
+fn format_mtime_delta(delta_secs: i64) -> String {
+    let abs = delta_secs.unsigned_abs();
+    let amount = if abs >= 3_600 {
+        format!("{}h", abs / 3_600)
+    } else if abs >= 60 {
+        format!("{}m", abs / 60)
+    } else {
+        format!("{abs}s")
+    };
+    if delta_secs < 0 {
+        format!("{amount} after")
+    } else {
+        format!("{amount} before")
+    }
+}
+
+/// Hooks keep locks, cursors and pending jobs under the home directory. In
+/// containers and CI `HOME` is sometimes unset; hooks then do nothing rather
+/// than fail the git operation.
+fn hook_home_available() -> bool {
+    if agents::home_dir().is_some() {
+        return true;
+    }
+    log::debug("home directory is not set; skipping hook");
+    false
+}
+
 /// Inner implementation of the pre-push hook.
-async fn hook_pre_push_inner(remote: &str, _url: &str) -> Result<()> {
-    if !git::check_enabled().await {
+async fn hook_pre_push_inner(
+    remote: &str,
+    _url: &str,
+    repo_root: Option<&Path>,
+    settings: &settings::Settings,
+) -> Result<()> {
+    if !hook_home_available() {
+        return Ok(());
+    }
+    if !settings.enabled {
         return Ok(());
     }
     let _activity_lock = update::acquire_activity_lock_blocking("hook-pre-push").await?;
 
-    if push::should_push_remote(remote).await {
-        let repo_root = git::repo_root().await?;
+    if push::should_push_remote(remote, settings).await {
+        let repo_root = repo_root.context("not in a git repository")?;
         let repo_root_str = repo_root.to_string_lossy().to_string();
         let encryption_method = resolve_encryption_method()
             .await
             .unwrap_or_else(|e| EncryptionMethod::Unavailable(format!("{e}")));
-        if let Err(e) =
-            ingest_incremental_sessions_for_repo(&repo_root, &repo_root_str, &encryption_method)
-                .await
+        if let Err(e) = ingest_incremental_sessions_for_repo(
+            repo_root,
+            &repo_root_str,
+            settings,
+            &encryption_method,
+        )
+        .await
         {
-            output::note(&format!("Pre-push ingest issue: {}", e));
+            log::warn(&format!("Pre-push ingest issue: {}", e));
         }
-        let queue_progress = hook_status_spinner_start("Queueing AI session sync");
-        deferred_sync::enqueue_pending_sync(&repo_root, remote).await?;
-        let _ = deferred_sync::spawn_background_sync(&repo_root, remote).await;
-        hook_status_spinner_finish_ok(queue_progress, "Queueing AI session sync");
+        let queue_task = hook_status_task("Queueing AI session sync");
+        let queue_progress = output::Spinner::start(&queue_task);
+        deferred_sync::enqueue_pending_sync(repo_root, remote, "queued by the pre-push hook")
+            .await?;
+        let _ = deferred_sync::spawn_background_sync(repo_root, remote).await;
+        queue_progress.finish_ok(&queue_task);
     }
 
     Ok(())
 }
 
-const POST_COMMIT_MATCH_WINDOW_SECS: i64 = 1_800;
+/// The session log update times the post-commit hook considers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MatchWindow {
+    /// How far back from now to discover logs.
+    lookback_secs: i64,
+    /// Logs updated after this time are ignored.
+    not_after: Option<i64>,
+}
 
-/// Stored canonical session object info.
-struct SessionIngestInfo {
-    session_uid: String,
-    blob_sha: String,
-    encoding: note::ContentEncoding,
+impl MatchWindow {
+    /// The window around a commit made at `anchor`. Discovery looks back
+    /// from `now`, so the lookback covers however long ago the anchor was
+    /// plus the window before it.
+    fn around(anchor: i64, settings: &settings::Settings, now: i64) -> Self {
+        MatchWindow {
+            lookback_secs: (now - anchor).max(0) + settings.window_before_secs,
+            not_after: settings.window_after_secs.map(|after| anchor + after),
+        }
+    }
 }
 
-const INDEX_TARGET_SIZE_BYTES: usize = 128 * 1024;
-const INDEX_HARD_SIZE_BYTES: usize = 256 * 1024;
+/// HEAD's timestamp per `timeSource`, or `now` when it cannot be read.
+async fn commit_anchor(repo_root: &Path, settings: &settings::Settings, now: i64) -> i64 {
+    git::head_timestamp_at(repo_root, settings.time_source)
+        .await
+        .unwrap_or(now)
+}
 
-fn cadence_hook_label(is_tty: bool) -> String {
-    if is_tty {
-        console::style("[Cadence]")
-            .bold()
-            .fg(console::Color::Cyan)
-            .to_string()
-    } else {
-        "[Cadence]".to_string()
-    }
+/// Whether an author matches any skip entry, case-insensitively.
+fn author_is_skipped(entries: &[String], email: &str, name: &str) -> bool {
+    entries
+        .iter()
+        .flat_map(|entry| entry.split(','))
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| entry.eq_ignore_ascii_case(email) || entry.eq_ignore_ascii_case(name))
 }
 
-fn hook_status_spinner_start(task: &str) -> Option<ProgressBar> {
-    if !output::is_stderr_tty() {
-        eprintln!("{} {}", cadence_hook_label(false), task);
-        eprintln!();
-        return None;
+async fn head_author_skipped(repo: &Path, settings: &settings::Settings) -> bool {
+    if settings.skip_authors.is_empty() {
+        return false;
+    }
+    match git::head_author_at(repo).await {
+        Ok((email, name)) => author_is_skipped(&settings.skip_authors, &email, &name),
+        Err(_) => false,
     }
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::with_template("{spinner} {msg}")
-            .unwrap_or_else(|_| ProgressStyle::default_spinner()),
-    );
-    pb.enable_steady_tick(std::time::Duration::from_millis(100));
-    pb.set_message(format!("{} {}", cadence_hook_label(true), task));
-    Some(pb)
 }
 
-fn hook_status_spinner_finish_ok(pb: Option<ProgressBar>, task: &str) {
-    if let Some(pb) = pb {
-        let check = console::style("✓").fg(console::Color::Green).to_string();
-        pb.finish_with_message(format!("{} {} {}", check, cadence_hook_label(true), task));
-    } else {
-        eprintln!("✓ {} {}", cadence_hook_label(false), task);
+/// Whether HEAD's author email is the user's own `user.email`. Without a
+/// configured email nothing counts as the user's, so nothing is linked.
+async fn head_authored_by_user(repo: &Path) -> bool {
+    let Ok(Some(user_email)) = git::config_get_at(repo, "user.email").await else {
+        return false;
+    };
+    match git::head_author_at(repo).await {
+        Ok((email, _)) => email.trim().eq_ignore_ascii_case(user_email.trim()),
+        Err(_) => false,
     }
 }
 
-fn hook_status_spinner_finish_err(pb: Option<ProgressBar>, task: &str) {
-    if let Some(pb) = pb {
-        let cross = console::style("✗").fg(console::Color::Red).to_string();
-        pb.finish_with_message(format!("{} {} {}", cross, cadence_hook_label(true), task));
-    }
+/// Stored canonical session object info.
+struct SessionIngestInfo {
+    session_uid: String,
+    blob_sha: String,
+    encoding: note::ContentEncoding,
+}
+
+const INDEX_TARGET_SIZE_BYTES: usize = 128 * 1024;
+const INDEX_HARD_SIZE_BYTES: usize = 256 * 1024;
+
+fn hook_status_task(task: &str) -> String {
+    format!("{} {}", log::label(output::is_stderr_tty()), task)
 }
 
 fn format_unix_rfc3339(epoch: i64) -> Option<String> {
@@ -1188,10 +2251,11 @@ struct ParsedSessionLog {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-#[allow(dead_code)]
 enum IncrementalLogDisposition {
     Indexed,
     SkippedPermanent,
+    /// Left as a pending record, which is retried instead of the log.
+    Pending,
     ErrorRetriable,
 }
 
@@ -1201,7 +2265,9 @@ fn advance_cursor_for_disposition(
     disposition: IncrementalLogDisposition,
 ) -> i64 {
     match disposition {
-        IncrementalLogDisposition::Indexed | IncrementalLogDisposition::SkippedPermanent => {
+        IncrementalLogDisposition::Indexed
+        | IncrementalLogDisposition::SkippedPermanent
+        | IncrementalLogDisposition::Pending => {
             mtime.map_or(current_cursor, |mtime| current_cursor.max(mtime))
         }
         IncrementalLogDisposition::ErrorRetriable => current_cursor,
@@ -1210,11 +2276,11 @@ fn advance_cursor_for_disposition(
 
 async fn parse_session_log_once(log: agents::SessionLog) -> Option<ParsedSessionLog> {
     let session_log = match &log.source {
-        agents::SessionSource::File(path) => tokio::fs::read_to_string(path).await.ok()?,
+        agents::SessionSource::File(path) => agents::read_session_file(path).await.ok()?,
         agents::SessionSource::Inline { content, .. } => content.clone(),
     };
-    let mut metadata = scanner::parse_session_metadata_str(&session_log);
-    metadata.agent_type = Some(log.agent_type.clone());
+    let metadata = scanner::parse_agent_session_metadata_str(&log.agent_type, &session_log);
+    note_skipped_lines(&log, &metadata);
     let session_start = scanner::session_time_range_str(&session_log).map(|(start, _)| start);
     Some(ParsedSessionLog {
         log,
@@ -1256,15 +2322,27 @@ async fn committer_key_hash_for_repo(repo: &std::path::Path) -> String {
     note::hash_key(email.trim().to_ascii_lowercase().as_str())
 }
 
+/// The redactor configured by `settings`, if redaction is enabled.
+fn session_redactor(settings: &settings::Settings) -> Result<Option<note::Redactor>> {
+    if settings.redact_patterns.is_empty() && !settings.redact_secrets {
+        return Ok(None);
+    }
+    note::Redactor::new(&settings.redact_patterns).map(Some)
+}
+
 #[allow(clippy::too_many_arguments)]
 async fn ingest_session_from_log(
     agent_type: &scanner::AgentType,
     session_id: &str,
+    session_id_inferred: bool,
+    matched_by_subject: bool,
     repo_str: &str,
     session_log: &str,
+    source_path: Option<&std::path::Path>,
     method: &EncryptionMethod,
     session_start: Option<i64>,
     repo: Option<&std::path::Path>,
+    settings: &settings::Settings,
     explicit_branch_keys: Option<&[String]>,
 ) -> Result<SessionIngestInfo> {
     let repo_path = match repo {
@@ -1308,10 +2386,12 @@ async fn ingest_session_from_log(
         _ => None,
     };
 
-    let record = note::SessionRecord {
+    let mut record = note::SessionRecord {
         session_uid: session_uid.clone(),
         agent: agent_type.to_string(),
         session_id: session_id.to_string(),
+        session_id_inferred,
+        matched_by_subject,
         repo_root: repo_str.to_string(),
         repo_remote_url,
         branch_key: branch_key.clone(),
@@ -1323,21 +2403,55 @@ async fn ingest_session_from_log(
         cwd: Some(repo_str.to_string()),
         ingested_at: ingested_at.clone(),
         cli_version: env!("CARGO_PKG_VERSION").to_string(),
+        session_path: None,
     };
 
-    let session_bytes = note::serialize_session_object(record, session_log.to_string())?;
+    // The uid and content hash above come from the original log, so a
+    // session keeps its identity when redaction or body settings change.
+    // Inline sources have no file to refer to and are always stored in full.
+    let reference = match source_path {
+        Some(path) if settings.session_body == note::SessionBody::Reference => {
+            Some(std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()))
+        }
+        _ => None,
+    };
+    let session_content = if let Some(path) = reference {
+        record.session_path = Some(path.to_string_lossy().to_string());
+        String::new()
+    } else {
+        match session_redactor(settings)? {
+            Some(redactor) => redactor.redact(session_log).into_owned(),
+            None => session_log.to_string(),
+        }
+    };
     let _ = git::migrate_legacy_session_ref_at(Some(&repo_path)).await?;
-    let (blob_sha, encoding) =
-        encode_and_store_session_object_at(Some(&repo_path), &session_bytes, method).await?;
     let fanout_path = git::fanout_path_for_key_hash(&session_uid)?;
-    git::ensure_blob_referenced_in_ref_at(
-        &repo_path,
-        git::SESSION_DATA_REF,
-        &fanout_path,
-        &blob_sha,
-        "cadence session data",
-    )
-    .await?;
+    // The uid covers the content hash, so an existing object for it already
+    // holds this exact log (e.g. an earlier commit in the same session with
+    // no new agent output). Point at it instead of storing another copy;
+    // encrypted objects would otherwise get a fresh blob every time.
+    let existing = git::blob_in_ref_at(&repo_path, git::SESSION_DATA_REF, &fanout_path).await?;
+    let (blob_sha, encoding) = match existing {
+        Some(blob_sha) => {
+            let blob = git::read_blob_at(Some(&repo_path), &blob_sha).await?;
+            (blob_sha, note::ContentEncoding::sniff(&blob))
+        }
+        None => {
+            let session_bytes = note::serialize_session_object(record, session_content)?;
+            let (blob_sha, encoding) =
+                encode_and_store_session_object_at(Some(&repo_path), &session_bytes, method)
+                    .await?;
+            git::ensure_blob_referenced_in_ref_at(
+                &repo_path,
+                git::SESSION_DATA_REF,
+                &fanout_path,
+                &blob_sha,
+                "cadence session data",
+            )
+            .await?;
+            (blob_sha, encoding)
+        }
+    };
 
     let index_entry = note::IndexEntry {
         session_uid: session_uid.clone(),
@@ -1377,40 +2491,161 @@ async fn ingest_session_from_log(
     })
 }
 
-async fn ingest_recent_sessions_for_repo(
+/// One session log the post-commit hook read, with the evidence for
+/// whether it belongs to the repository.
+struct ConsideredLog {
+    parsed: ParsedSessionLog,
+    candidate: matcher::Candidate,
+}
+
+impl ConsideredLog {
+    /// Whether the hook stores this log: it matches the repository and
+    /// clears `minConfidence`. `--explain` decides with the same check.
+    fn selected(&self, settings: &settings::Settings) -> bool {
+        self.candidate.evidence.clears(settings.min_confidence)
+    }
+}
+
+/// The matching half of the post-commit hook: read the session logs updated
+/// in the last `since_secs` (and, with `not_after`, not after it) and weigh
+/// each against `repo_root`. Nothing is stored, so `cadence bench` and
+/// `hook post-commit --explain` run exactly what the hook runs.
+async fn consider_recent_sessions(
     repo_root: &std::path::Path,
-    repo_root_str: &str,
     since_secs: i64,
-    method: &EncryptionMethod,
-) -> Result<usize> {
+    not_after: Option<i64>,
+    settings: &settings::Settings,
+    metrics: &mut HookMetrics,
+) -> Vec<ConsideredLog> {
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
         .as_secs() as i64;
-    let files = agents::discover_recent_sessions(now, since_secs).await;
+    let mut files =
+        readable_session_logs(agents::discover_recent_sessions(now, since_secs).await).await;
+    if let Some(not_after) = not_after {
+        files.retain(|log| log.updated_at.is_none_or(|updated| updated <= not_after));
+    }
+    metrics.candidates += files.len();
+    log::debug(&format!(
+        "scanning {} candidate session logs modified in the last {}s",
+        files.len(),
+        since_secs
+    ));
     let parsed_logs = parse_session_logs_bounded(files).await;
+    let parent_scope = parent_cwd_scope(repo_root, settings).await;
     let mut repo_root_cache: std::collections::HashMap<String, Option<std::path::PathBuf>> =
         std::collections::HashMap::new();
-    let mut ingested = 0usize;
+    let mut considered = Vec::with_capacity(parsed_logs.len());
 
     for parsed in parsed_logs {
-        let Some(cwd) = parsed.metadata.cwd.clone() else {
-            continue;
-        };
-        let resolved_repo = if let Some(cached) = repo_root_cache.get(&cwd) {
-            cached.clone()
-        } else {
-            let resolved = git::repo_root_at(std::path::Path::new(&cwd)).await.ok();
-            repo_root_cache.insert(cwd.clone(), resolved.clone());
-            resolved
+        let cwd_match = match parsed.metadata.cwd.as_deref() {
+            None => {
+                log::debug(&format!(
+                    "skipping {}: no working directory in session log",
+                    parsed.log.source_label()
+                ));
+                matcher::CwdMatch::Unknown
+            }
+            Some(cwd) => {
+                let (cwd_match, resolved_repo) = classify_session_cwd(
+                    cwd,
+                    repo_root,
+                    parent_scope.as_deref(),
+                    &mut repo_root_cache,
+                )
+                .await;
+                if !cwd_match.matches()
+                    && let Some(resolved_repo) = resolved_repo
+                {
+                    log::debug(&format!(
+                        "skipping {}: belongs to {}",
+                        parsed.log.source_label(),
+                        resolved_repo.display()
+                    ));
+                }
+                cwd_match
+            }
         };
-        let Some(resolved_repo) = resolved_repo else {
-            continue;
+        let candidate = matcher::Candidate {
+            agent: parsed
+                .metadata
+                .agent_type
+                .clone()
+                .unwrap_or_else(|| parsed.log.agent_type.clone()),
+            session_id: parsed.metadata.session_id.clone(),
+            cwd: parsed.metadata.cwd.clone(),
+            updated_at: parsed.log.updated_at,
+            source_label: parsed.log.source_label(),
+            evidence: matcher::MatchEvidence {
+                cwd_match,
+                has_session_id: parsed.metadata.session_id.is_some(),
+                session_id_inferred: parsed.metadata.session_id_inferred,
+                mtime_delta_secs: parsed.log.updated_at.map(|updated| now - updated),
+            },
         };
-        if resolved_repo != repo_root {
-            continue;
+        let considered_log = ConsideredLog { parsed, candidate };
+        if cwd_match.matches() {
+            let confidence = considered_log.candidate.evidence.confidence();
+            if considered_log.selected(settings) {
+                log::debug(&format!(
+                    "matched {} (cwd {}, updated {})",
+                    considered_log.candidate.source_label,
+                    cwd_match.as_str(),
+                    considered_log
+                        .candidate
+                        .evidence
+                        .mtime_delta_secs
+                        .map_or_else(|| "at an unknown time".to_string(), format_mtime_delta)
+                ));
+            } else {
+                log::debug(&format!(
+                    "skipping {}: {} confidence is below {}",
+                    considered_log.candidate.source_label,
+                    confidence.map_or("no", matcher::Confidence::as_str),
+                    settings::MIN_CONFIDENCE_KEY
+                ));
+            }
         }
+        considered.push(considered_log);
+    }
+    considered
+}
+
+#[cfg(test)]
+async fn ingest_recent_sessions_for_repo(
+    repo_root: &std::path::Path,
+    repo_root_str: &str,
+    since_secs: i64,
+    not_after: Option<i64>,
+    settings: &settings::Settings,
+    method: &EncryptionMethod,
+    metrics: &mut HookMetrics,
+) -> Result<Vec<StoredSession>> {
+    let considered =
+        consider_recent_sessions(repo_root, since_secs, not_after, settings, metrics).await;
+    store_commit_matches(
+        CommitMatches::new(considered, settings),
+        repo_root,
+        repo_root_str,
+        settings,
+        method,
+        metrics,
+    )
+    .await
+}
 
+/// Store each selected log as a session of `repo_root`.
+async fn store_selected_sessions(
+    selected: Vec<ConsideredLog>,
+    repo_root: &std::path::Path,
+    repo_root_str: &str,
+    settings: &settings::Settings,
+    method: &EncryptionMethod,
+    metrics: &mut HookMetrics,
+) -> Result<Vec<StoredSession>> {
+    let mut stored = Vec::new();
+    for ConsideredLog { parsed, candidate } in selected {
         let session_id = parsed
             .metadata
             .session_id
@@ -1426,37 +2661,263 @@ async fn ingest_recent_sessions_for_repo(
         let info = ingest_session_from_log(
             &agent,
             &session_id,
+            parsed.metadata.session_id_inferred,
+            false,
             repo_root_str,
             &parsed.session_log,
+            parsed.log.file_path(),
             method,
             parsed.session_start,
             Some(repo_root),
+            settings,
             None,
         )
         .await?;
-        ingested += 1;
-        if output::is_verbose() {
-            output::detail(&format!(
-                "session uid {} stored as {} ({})",
-                info.session_uid, info.blob_sha, info.encoding
-            ));
+        stored.push(StoredSession {
+            agent: agent.to_string(),
+            session_id,
+        });
+        metrics.confidence = metrics.confidence.max(candidate.evidence.confidence());
+        log::debug(&format!(
+            "session uid {} stored as {} ({})",
+            info.session_uid, info.blob_sha, info.encoding
+        ));
+    }
+
+    Ok(stored)
+}
+
+/// Where sessions may match `repo_root` as a parent; `None` unless
+/// `matchParentCwd` is on.
+async fn parent_cwd_scope(
+    repo_root: &std::path::Path,
+    settings: &settings::Settings,
+) -> Option<std::path::PathBuf> {
+    if !settings.match_parent_cwd {
+        return None;
+    }
+    matcher::parent_cwd_scope(repo_root).await
+}
+
+/// How `cwd` relates to `repo_root`, and the repository git resolves it to.
+/// `parent_scope` comes from [`matcher::parent_cwd_scope`] when parent
+/// matching is on. Resolutions are cached per working directory.
+async fn classify_session_cwd(
+    cwd: &str,
+    repo_root: &std::path::Path,
+    parent_scope: Option<&std::path::Path>,
+    cache: &mut std::collections::HashMap<String, Option<std::path::PathBuf>>,
+) -> (matcher::CwdMatch, Option<std::path::PathBuf>) {
+    let resolved_repo = if let Some(cached) = cache.get(cwd) {
+        cached.clone()
+    } else {
+        let resolved = git::repo_root_at(std::path::Path::new(cwd)).await.ok();
+        cache.insert(cwd.to_string(), resolved.clone());
+        resolved
+    };
+    let cwd_match = matcher::CwdMatch::classify(
+        std::path::Path::new(cwd),
+        resolved_repo.as_deref(),
+        repo_root,
+        parent_scope,
+    );
+    (cwd_match, resolved_repo)
+}
+
+/// How far back the commit-subject fallback looks for session logs.
+const SUBJECT_MATCH_LOOKBACK_SECS: i64 = 7 * 86_400;
+
+/// Fallback for commits whose work was stashed and popped long after the
+/// session went quiet, so no log was updated inside the window: store the
+/// newest older session in `repo_root` whose log mentions HEAD's subject.
+/// The record is marked `matched_by_subject`, a weaker match than one made
+/// by time.
+#[cfg(test)]
+async fn ingest_session_by_subject(
+    repo_root: &std::path::Path,
+    repo_root_str: &str,
+    window: MatchWindow,
+    settings: &settings::Settings,
+    method: &EncryptionMethod,
+    metrics: &mut HookMetrics,
+) -> Result<Option<StoredSession>> {
+    let Some(parsed) = find_session_by_subject(repo_root, window, settings, metrics).await? else {
+        return Ok(None);
+    };
+    store_subject_match(parsed, repo_root, repo_root_str, settings, method, metrics)
+        .await
+        .map(Some)
+}
+
+/// Store `parsed`, found by [`find_session_by_subject`], marked
+/// `matched_by_subject`.
+async fn store_subject_match(
+    parsed: ParsedSessionLog,
+    repo_root: &std::path::Path,
+    repo_root_str: &str,
+    settings: &settings::Settings,
+    method: &EncryptionMethod,
+    metrics: &mut HookMetrics,
+) -> Result<StoredSession> {
+    let session_id = parsed
+        .metadata
+        .session_id
+        .clone()
+        .unwrap_or_else(|| "unknown".to_string());
+    let agent = parsed
+        .metadata
+        .agent_type
+        .clone()
+        .unwrap_or(scanner::AgentType::Claude);
+    let info = ingest_session_from_log(
+        &agent,
+        &session_id,
+        parsed.metadata.session_id_inferred,
+        true,
+        repo_root_str,
+        &parsed.session_log,
+        parsed.log.file_path(),
+        method,
+        parsed.session_start,
+        Some(repo_root),
+        settings,
+        None,
+    )
+    .await?;
+    log::debug(&format!(
+        "session uid {} matched by commit subject, stored as {}",
+        info.session_uid, info.blob_sha
+    ));
+    metrics.confidence = metrics.confidence.max(Some(matcher::Confidence::Low));
+    Ok(StoredSession {
+        agent: agent.to_string(),
+        session_id,
+    })
+}
+
+/// The matching half of [`ingest_session_by_subject`]: the newest session
+/// log from before the window that ran in `repo_root` and mentions HEAD's
+/// subject.
+async fn find_session_by_subject(
+    repo_root: &std::path::Path,
+    window: MatchWindow,
+    settings: &settings::Settings,
+    metrics: &mut HookMetrics,
+) -> Result<Option<ParsedSessionLog>> {
+    // Subject matches are the weakest kind.
+    if !settings
+        .min_confidence
+        .admits(Some(matcher::Confidence::Low))
+    {
+        return Ok(None);
+    }
+    let subject = git::head_subject_at(repo_root).await?;
+    if subject.chars().count() < matcher::MIN_SUBJECT_CHARS {
+        return Ok(None);
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let window_start = now - window.lookback_secs;
+    let mut files = readable_session_logs(
+        agents::discover_recent_sessions(now, window.lookback_secs + SUBJECT_MATCH_LOOKBACK_SECS)
+            .await,
+    )
+    .await;
+    // Logs updated inside the window were already considered, and Claude
+    // logs name their working directory in the path, so logs of other
+    // projects are dropped before anything is read.
+    files.retain(|log| {
+        log.updated_at.is_some_and(|updated| updated < window_start)
+            && (log.agent_type != scanner::AgentType::Claude
+                || log
+                    .file_path()
+                    .and_then(Path::parent)
+                    .and_then(Path::file_name)
+                    .is_none_or(|dir| {
+                        agents::claude::project_dir_may_match(
+                            &dir.to_string_lossy(),
+                            repo_root,
+                            settings.match_parent_cwd,
+                        )
+                    }))
+    });
+    metrics.candidates += files.len();
+    log::debug(&format!(
+        "no session in the window; checking {} older session logs for {subject:?}",
+        files.len()
+    ));
+    let parent_scope = parent_cwd_scope(repo_root, settings).await;
+    let mut repo_root_cache = std::collections::HashMap::new();
+    let mut best: Option<ParsedSessionLog> = None;
+    for parsed in parse_session_logs_bounded(files).await {
+        let Some(cwd) = parsed.metadata.cwd.as_deref() else {
+            continue;
+        };
+        if !matcher::log_mentions_subject(&parsed.session_log, &subject) {
+            continue;
+        }
+        let (cwd_match, _) = classify_session_cwd(
+            cwd,
+            repo_root,
+            parent_scope.as_deref(),
+            &mut repo_root_cache,
+        )
+        .await;
+        if cwd_match.matches()
+            && best
+                .as_ref()
+                .is_none_or(|b| parsed.log.updated_at > b.log.updated_at)
+        {
+            best = Some(parsed);
         }
     }
+    Ok(best)
+}
 
-    Ok(ingested)
+/// Drop session log files over the size limit, warning about each, so a
+/// stray multi-gigabyte file cannot stall a commit.
+async fn readable_session_logs(logs: Vec<agents::SessionLog>) -> Vec<agents::SessionLog> {
+    let max_bytes = agents::max_session_log_bytes();
+    let (logs, oversized) = agents::partition_oversized(logs, max_bytes).await;
+    for skipped in oversized {
+        log::warn(&format!(
+            "skipping {}: {} bytes exceeds the {max_bytes}-byte session log limit \
+             (CADENCE_MAX_SESSION_LOG_BYTES)",
+            skipped.path.display(),
+            skipped.bytes
+        ));
+    }
+    logs
 }
 
 async fn session_log_metadata(log: &agents::SessionLog) -> scanner::SessionMetadata {
-    let mut metadata = match &log.source {
-        agents::SessionSource::File(path) => scanner::parse_session_metadata(path).await,
+    let metadata = match &log.source {
+        agents::SessionSource::File(path) => {
+            scanner::parse_agent_session_metadata(&log.agent_type, path).await
+        }
         agents::SessionSource::Inline { content, .. } => {
-            scanner::parse_session_metadata_str(content)
+            scanner::parse_agent_session_metadata_str(&log.agent_type, content)
         }
     };
-    metadata.agent_type = Some(log.agent_type.clone());
+    note_skipped_lines(log, &metadata);
     metadata
 }
 
+/// Corrupt lines are skipped, not fatal; say so when debugging a log that
+/// did not match.
+fn note_skipped_lines(log: &agents::SessionLog, metadata: &scanner::SessionMetadata) {
+    if metadata.skipped_lines > 0 {
+        log::debug(&format!(
+            "skipped {} malformed line(s) in {} session log {}",
+            metadata.skipped_lines,
+            log.agent_type,
+            log.source_label()
+        ));
+    }
+}
+
 async fn session_log_time_range(log: &agents::SessionLog) -> Option<(i64, i64)> {
     match &log.source {
         agents::SessionSource::File(path) => scanner::session_time_range(path).await,
@@ -1466,14 +2927,22 @@ async fn session_log_time_range(log: &agents::SessionLog) -> Option<(i64, i64)>
 
 async fn session_log_content_async(log: &agents::SessionLog) -> Option<String> {
     match &log.source {
-        agents::SessionSource::File(path) => tokio::fs::read_to_string(path).await.ok(),
+        agents::SessionSource::File(path) => agents::read_session_file(path).await.ok(),
         agents::SessionSource::Inline { content, .. } => Some(content.clone()),
     }
 }
 
+/// Store sessions for `repo_root` updated since the last scan, for the
+/// pre-push hook and deferred sync, then retry its pending records (see
+/// [`retry_pending_ingests`]).
+///
+/// Matches below `minConfidence` are left as pending records, as in the
+/// post-commit hook. Author filters are not applied: the sessions picked up
+/// here may belong to any of the commits being pushed, not just HEAD.
 async fn ingest_incremental_sessions_for_repo(
     repo_root: &std::path::Path,
     repo_root_str: &str,
+    settings: &settings::Settings,
     method: &EncryptionMethod,
 ) -> Result<usize> {
     let remote = git::resolve_push_remote_at(repo_root)
@@ -1523,7 +2992,8 @@ async fn ingest_incremental_sessions_for_repo(
     let fallback_since = now - 30 * 86_400;
     let min_cursor = cursor_values.into_iter().min().unwrap_or(fallback_since);
     let since_secs = (now - min_cursor).max(0);
-    let files = agents::discover_recent_sessions(now, since_secs).await;
+    let files =
+        readable_session_logs(agents::discover_recent_sessions(now, since_secs).await).await;
 
     let mut ingested = 0usize;
     let mut cursor_advance_mtime = min_cursor;
@@ -1538,8 +3008,10 @@ async fn ingest_incremental_sessions_for_repo(
         candidates.push(log);
     }
     let parsed_logs = parse_session_logs_bounded(candidates).await;
+    let parent_scope = parent_cwd_scope(repo_root, settings).await;
     let mut repo_root_cache: std::collections::HashMap<String, Option<std::path::PathBuf>> =
         std::collections::HashMap::new();
+    let mut stored_paths = std::collections::HashSet::new();
 
     for parsed in parsed_logs {
         let log_mtime = parsed.log.updated_at;
@@ -1552,32 +3024,292 @@ async fn ingest_incremental_sessions_for_repo(
             );
             continue;
         };
-        let resolved_repo = if let Some(cached) = repo_root_cache.get(&cwd) {
-            cached.clone()
-        } else {
-            let resolved = git::repo_root_at(std::path::Path::new(&cwd)).await.ok();
-            repo_root_cache.insert(cwd.clone(), resolved.clone());
-            resolved
-        };
-        let Some(resolved_repo) = resolved_repo else {
-            // Unresolvable CWD cannot be matched to this repo in this path; skip permanently.
+        let (cwd_match, _) = classify_session_cwd(
+            &cwd,
+            repo_root,
+            parent_scope.as_deref(),
+            &mut repo_root_cache,
+        )
+        .await;
+        if !cwd_match.matches() {
+            // Logs outside this repo (or outside any repo) are permanently
+            // irrelevant for it.
             cursor_advance_mtime = advance_cursor_for_disposition(
                 cursor_advance_mtime,
                 log_mtime,
                 IncrementalLogDisposition::SkippedPermanent,
             );
             continue;
+        }
+        let confidence = log_confidence(cwd_match, &parsed.metadata);
+        if !settings.min_confidence.admits(confidence) {
+            log::debug(&format!(
+                "pre-push incremental: deferring {}: {} confidence is below {}",
+                parsed.log.source_label(),
+                confidence.map_or("no", matcher::Confidence::as_str),
+                settings::MIN_CONFIDENCE_KEY
+            ));
+            let disposition = match defer_below_min_confidence(repo_root_str, &parsed.log).await {
+                Ok(()) => IncrementalLogDisposition::Pending,
+                Err(e) => {
+                    log::debug(&format!("could not record pending session log: {e:#}"));
+                    IncrementalLogDisposition::ErrorRetriable
+                }
+            };
+            cursor_advance_mtime =
+                advance_cursor_for_disposition(cursor_advance_mtime, log_mtime, disposition);
+            continue;
+        }
+
+        let session_id = parsed
+            .metadata
+            .session_id
+            .as_deref()
+            .unwrap_or("unknown")
+            .to_string();
+        let agent = parsed
+            .metadata
+            .agent_type
+            .clone()
+            .unwrap_or(scanner::AgentType::Claude);
+
+        let info = match ingest_session_from_log(
+            &agent,
+            &session_id,
+            parsed.metadata.session_id_inferred,
+            false,
+            repo_root_str,
+            &parsed.session_log,
+            parsed.log.file_path(),
+            method,
+            parsed.session_start,
+            Some(repo_root),
+            settings,
+            None,
+        )
+        .await
+        {
+            Ok(info) => info,
+            Err(err) => {
+                // Ingest failure is retriable; keep cursor unchanged.
+                return Err(err);
+            }
         };
-        if resolved_repo != repo_root {
-            // Logs for a different repo are permanently irrelevant for this repo.
-            cursor_advance_mtime = advance_cursor_for_disposition(
+        ingested += 1;
+        if let Some(path) = parsed.log.file_path() {
+            stored_paths.insert(path.to_path_buf());
+        }
+        cursor_advance_mtime = advance_cursor_for_disposition(
+            cursor_advance_mtime,
+            log_mtime,
+            IncrementalLogDisposition::Indexed,
+        );
+        log::debug(&format!(
+            "pre-push incremental: session uid {} stored as {} ({})",
+            info.session_uid, info.blob_sha, info.encoding
+        ));
+    }
+
+    if cursor_advance_mtime > min_cursor {
+        sync_pending::upsert_cursor(
+            repo_root_str,
+            sync_pending::ScopeType::Committer,
+            &committer_hash,
+            cursor_advance_mtime,
+        )
+        .await?;
+        for key_hash in &branch_key_hashes {
+            sync_pending::upsert_cursor(
+                repo_root_str,
+                sync_pending::ScopeType::Branch,
+                key_hash,
                 cursor_advance_mtime,
-                log_mtime,
-                IncrementalLogDisposition::SkippedPermanent,
-            );
+            )
+            .await?;
+        }
+    }
+
+    ingested +=
+        retry_pending_ingests(repo_root, repo_root_str, settings, method, &stored_paths).await?;
+    Ok(ingested)
+}
+
+/// How long a pending record is retried before it is dropped.
+const PENDING_INGEST_MAX_AGE_SECS: i64 = 7 * 86_400;
+
+/// How strongly a session log with `metadata`, whose working directory
+/// relates to the repository as `cwd_match`, ties to it; `None` without a
+/// session id.
+fn log_confidence(
+    cwd_match: matcher::CwdMatch,
+    metadata: &scanner::SessionMetadata,
+) -> Option<matcher::Confidence> {
+    matcher::MatchEvidence {
+        cwd_match,
+        has_session_id: metadata.session_id.is_some(),
+        session_id_inferred: metadata.session_id_inferred,
+        mtime_delta_secs: None,
+    }
+    .confidence()
+}
+
+/// Leave `log`, which matched the repository below `minConfidence`, as a
+/// pending record so a later run stores it once it clears the minimum.
+/// Inline logs cannot be read back later and are not recorded.
+async fn defer_below_min_confidence(repo_root_str: &str, log: &agents::SessionLog) -> Result<()> {
+    let Some(path) = log.file_path() else {
+        return Ok(());
+    };
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    sync_pending::add_pending_ingest(
+        repo_root_str,
+        sync_pending::PendingIngest::SessionLog {
+            path: path.to_string_lossy().to_string(),
+            agent: log.agent_type.to_string(),
+        },
+        now,
+    )
+    .await
+}
+
+/// Leave the commit at HEAD, whose sessions the post-commit hook ran out of
+/// time to find, as a pending record so a later run scans its window.
+async fn defer_commit(repo_root: &Path, repo_root_str: &str, anchor: i64) -> Result<()> {
+    let sha = git::rev_parse_at(Some(repo_root), "HEAD").await?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    sync_pending::add_pending_ingest(
+        repo_root_str,
+        sync_pending::PendingIngest::Commit {
+            sha,
+            anchor_epoch: anchor,
+        },
+        now,
+    )
+    .await
+}
+
+/// Work through the pending records for `repo_root`: session logs are
+/// stored once they clear `minConfidence`, and deferred commits have their
+/// window scanned and stored. Records are dropped when done, when their log
+/// is gone or no longer matches, or after [`PENDING_INGEST_MAX_AGE_SECS`].
+/// Logs in `already_stored` were stored by the caller and only lose their
+/// record. Returns how many sessions were stored.
+async fn retry_pending_ingests(
+    repo_root: &Path,
+    repo_root_str: &str,
+    settings: &settings::Settings,
+    method: &EncryptionMethod,
+    already_stored: &std::collections::HashSet<PathBuf>,
+) -> Result<usize> {
+    let records = sync_pending::pending_ingests(repo_root_str).await?;
+    if records.is_empty() {
+        return Ok(0);
+    }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let parent_scope = parent_cwd_scope(repo_root, settings).await;
+    let mut repo_root_cache = std::collections::HashMap::new();
+    let mut stored = 0;
+    for (record_path, record) in records {
+        let (path, agent) = match &record.target {
+            sync_pending::PendingIngest::SessionLog { path, agent } => (path, agent),
+            sync_pending::PendingIngest::Commit { sha, anchor_epoch } => {
+                let window = MatchWindow::around(*anchor_epoch, settings, now);
+                let mut metrics = HookMetrics::default();
+                let mut matches = CommitMatches::new(
+                    consider_recent_sessions(
+                        repo_root,
+                        window.lookback_secs,
+                        window.not_after,
+                        settings,
+                        &mut metrics,
+                    )
+                    .await,
+                    settings,
+                );
+                matches.selected.retain(|considered| {
+                    considered
+                        .parsed
+                        .log
+                        .file_path()
+                        .is_none_or(|path| !already_stored.contains(path))
+                });
+                let sessions = store_commit_matches(
+                    matches,
+                    repo_root,
+                    repo_root_str,
+                    settings,
+                    method,
+                    &mut metrics,
+                )
+                .await?;
+                sync_pending::remove_pending_ingest(&record_path).await?;
+                stored += sessions.len();
+                log::debug(&format!(
+                    "pending commit {sha}: stored {} sessions",
+                    sessions.len()
+                ));
+                continue;
+            }
+        };
+        let path = PathBuf::from(path);
+        let log = agents::SessionLog {
+            agent_type: scanner::AgentType::from_name(agent).unwrap_or(scanner::AgentType::Claude),
+            source: agents::SessionSource::File(path.clone()),
+            updated_at: None,
+        };
+        let parsed = if already_stored.contains(&path) {
+            None
+        } else {
+            match readable_session_logs(vec![log]).await.pop() {
+                Some(log) => parse_session_log_once(log).await,
+                None => None,
+            }
+        };
+        let Some(parsed) = parsed else {
+            sync_pending::remove_pending_ingest(&record_path).await?;
+            continue;
+        };
+        let cwd_match = match parsed.metadata.cwd.as_deref() {
+            Some(cwd) => {
+                classify_session_cwd(
+                    cwd,
+                    repo_root,
+                    parent_scope.as_deref(),
+                    &mut repo_root_cache,
+                )
+                .await
+                .0
+            }
+            None => matcher::CwdMatch::Unknown,
+        };
+        if !cwd_match.matches() {
+            sync_pending::remove_pending_ingest(&record_path).await?;
+            continue;
+        }
+        if !settings
+            .min_confidence
+            .admits(log_confidence(cwd_match, &parsed.metadata))
+        {
+            if now - record.created_at_epoch > PENDING_INGEST_MAX_AGE_SECS {
+                log::debug(&format!(
+                    "dropping pending {}: still below {} after {}s",
+                    path.display(),
+                    settings::MIN_CONFIDENCE_KEY,
+                    PENDING_INGEST_MAX_AGE_SECS
+                ));
+                sync_pending::remove_pending_ingest(&record_path).await?;
+            }
             continue;
         }
-
         let session_id = parsed
             .metadata
             .session_id
@@ -1588,60 +3320,32 @@ async fn ingest_incremental_sessions_for_repo(
             .metadata
             .agent_type
             .clone()
-            .unwrap_or(scanner::AgentType::Claude);
-
-        let info = match ingest_session_from_log(
+            .unwrap_or_else(|| parsed.log.agent_type.clone());
+        let info = ingest_session_from_log(
             &agent,
             &session_id,
+            parsed.metadata.session_id_inferred,
+            false,
             repo_root_str,
             &parsed.session_log,
+            parsed.log.file_path(),
             method,
             parsed.session_start,
             Some(repo_root),
+            settings,
             None,
         )
-        .await
-        {
-            Ok(info) => info,
-            Err(err) => {
-                // Ingest failure is retriable; keep cursor unchanged.
-                return Err(err);
-            }
-        };
-        ingested += 1;
-        cursor_advance_mtime = advance_cursor_for_disposition(
-            cursor_advance_mtime,
-            log_mtime,
-            IncrementalLogDisposition::Indexed,
-        );
-        if output::is_verbose() {
-            output::detail(&format!(
-                "pre-push incremental: session uid {} stored as {} ({})",
-                info.session_uid, info.blob_sha, info.encoding
-            ));
-        }
-    }
-
-    if cursor_advance_mtime > min_cursor {
-        sync_pending::upsert_cursor(
-            repo_root_str,
-            sync_pending::ScopeType::Committer,
-            &committer_hash,
-            cursor_advance_mtime,
-        )
         .await?;
-        for key_hash in &branch_key_hashes {
-            sync_pending::upsert_cursor(
-                repo_root_str,
-                sync_pending::ScopeType::Branch,
-                key_hash,
-                cursor_advance_mtime,
-            )
-            .await?;
-        }
+        sync_pending::remove_pending_ingest(&record_path).await?;
+        stored += 1;
+        log::debug(&format!(
+            "pending session log {} stored as {} ({})",
+            path.display(),
+            info.blob_sha,
+            info.encoding
+        ));
     }
-
-    Ok(ingested)
+    Ok(stored)
 }
 
 /// Parse a duration string like "7d", "30d", "1d" into seconds.
@@ -1677,8 +3381,114 @@ fn parse_since_duration(since: &str) -> Result<i64> {
 /// - Prints verbose progress throughout
 /// - All errors are non-fatal (logged and continued)
 /// - Always syncs and pushes canonical session refs per repository
-async fn run_backfill(since: &str) -> Result<()> {
-    run_backfill_inner(since, None).await
+///
+/// With `since_last`, the window starts at the last successful unfiltered
+/// run (see [`record_backfill_success`]) instead of `since`.
+async fn run_backfill(
+    since: &str,
+    since_last: bool,
+    repo_filter: Option<&std::path::Path>,
+    agent: Option<&scanner::AgentType>,
+) -> Result<()> {
+    let default_secs = parse_since_duration(since)?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+
+    let since_secs = if since_last {
+        let last = load_last_backfill_epoch().await;
+        if last.is_none() {
+            output::detail(&format!(
+                "No previous backfill recorded; scanning the {}",
+                describe_window(default_secs)
+            ));
+        }
+        since_last_window_secs(last, now, default_secs)
+    } else {
+        default_secs
+    };
+
+    let errors = run_backfill_inner(since_secs, repo_filter, agent).await?;
+
+    // A filtered or partially failed run does not cover everything since the
+    // previous mark, so only clean full runs move it forward.
+    if repo_filter.is_none()
+        && agent.is_none()
+        && errors == 0
+        && let Err(e) = record_backfill_success(now).await
+    {
+        output::detail(&format!("Could not record backfill time: {e}"));
+    }
+    Ok(())
+}
+
+/// File in the state directory holding the start time of the last successful
+/// unfiltered backfill.
+const LAST_BACKFILL_FILE: &str = "last-backfill.json";
+
+/// Extra seconds scanned before the recorded mark by `--since-last`, so
+/// sessions written while the previous run was starting are not missed.
+const SINCE_LAST_OVERLAP_SECS: i64 = 300;
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+struct LastBackfill {
+    last_success_epoch: i64,
+}
+
+async fn load_last_backfill_epoch() -> Option<i64> {
+    let path = state::state_dir_path()?.join(LAST_BACKFILL_FILE);
+    let content = tokio::fs::read_to_string(path).await.ok()?;
+    serde_json::from_str::<LastBackfill>(&content)
+        .ok()
+        .map(|record| record.last_success_epoch)
+}
+
+/// Remember `started_at` as the start of the last successful backfill.
+async fn record_backfill_success(started_at: i64) -> Result<()> {
+    let dir = state::state_dir().await?;
+    state::write_json_atomic(
+        &dir.join(LAST_BACKFILL_FILE),
+        &LastBackfill {
+            last_success_epoch: started_at,
+        },
+    )
+    .await
+}
+
+/// Scan window for `--since-last`: everything since `last` plus a small
+/// overlap, or `default_secs` when no run was recorded. A mark in the future
+/// (clock changes) is treated as just the overlap.
+fn since_last_window_secs(last: Option<i64>, now: i64, default_secs: i64) -> i64 {
+    match last {
+        Some(last) => (now - last).max(0) + SINCE_LAST_OVERLAP_SECS,
+        None => default_secs,
+    }
+}
+
+/// Human-readable scan window, e.g. "last 30 days" or "last 3 hours".
+fn describe_window(secs: i64) -> String {
+    let (n, unit) = if secs >= 86_400 {
+        (secs / 86_400, "day")
+    } else if secs >= 3_600 {
+        (secs / 3_600, "hour")
+    } else {
+        ((secs / 60).max(1), "minute")
+    };
+    let plural = if n == 1 { "" } else { "s" };
+    format!("last {n} {unit}{plural}")
+}
+
+/// Clap value parser for `--agent`: validates against the agent registry.
+fn parse_agent_filter(value: &str) -> std::result::Result<scanner::AgentType, String> {
+    scanner::AgentType::from_name(value).ok_or_else(|| {
+        let known = scanner::AgentType::ALL
+            .iter()
+            .map(|agent| agent.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("unknown agent '{value}' (expected one of: {known})")
+    })
 }
 
 /// Inner implementation of backfill that accepts an optional repo filter.
@@ -1796,7 +3606,8 @@ async fn process_repo_backfill(
         }),
     );
 
-    match git::repo_matches_org_filter(&repo_root).await {
+    let settings = settings::Settings::load(Some(&repo_root)).await;
+    match git::repo_matches_org_filter(&repo_root, &settings).await {
         Ok(true) => {}
         Ok(false) => {
             backfill_logger.event(
@@ -1831,8 +3642,7 @@ async fn process_repo_backfill(
         }
     }
 
-    let repo_enabled = git::check_enabled_at(&repo_root).await;
-    if !repo_enabled {
+    if !settings.enabled {
         backfill_logger.event(
             "repo_skipped",
             serde_json::json!({
@@ -1848,10 +3658,7 @@ async fn process_repo_backfill(
     }
 
     let repo_remote = {
-        let remote = match git::resolve_push_remote_at(&repo_root).await {
-            Ok(Some(remote)) => remote,
-            _ => "origin".to_string(),
-        };
+        let remote = git::session_remote_at(&repo_root, &settings).await;
         backfill_logger.event(
             "repo_remote_sync_started",
             serde_json::json!({
@@ -1905,6 +3712,37 @@ async fn process_repo_backfill(
             }),
         );
 
+        let cwd_match = matcher::CwdMatch::classify(
+            Path::new(session.metadata.cwd.as_deref().unwrap_or_default()),
+            Some(&session.repo_root),
+            &session.repo_root,
+            None,
+        );
+        let confidence = log_confidence(cwd_match, &session.metadata);
+        if !settings.min_confidence.admits(confidence) {
+            // Like the hooks, leave it for a retry rather than store it.
+            stats.skipped += 1;
+            let pending = defer_below_min_confidence(&repo_root_str, &session.log)
+                .await
+                .is_ok();
+            backfill_logger.event(
+                "session_skipped",
+                serde_json::json!({
+                    "repo_display": repo_display.as_str(),
+                    "repo_root": repo_root_str.as_str(),
+                    "session_id": session.session_id.as_str(),
+                    "file": session.log.source_label(),
+                    "reason": "below_min_confidence",
+                    "confidence": confidence.map(matcher::Confidence::as_str),
+                    "pending": pending,
+                }),
+            );
+            if let Some(pb) = &repo_progress {
+                pb.inc(1);
+            }
+            continue;
+        }
+
         let session_log = match session_log_content_async(&session.log).await {
             Some(content) => content,
             None => {
@@ -1934,11 +3772,15 @@ async fn process_repo_backfill(
         match ingest_session_from_log(
             &agent_type,
             &session.session_id,
+            session.metadata.session_id_inferred,
+            false,
             &repo_str,
             &session_log,
+            session.log.file_path(),
             &encryption_method,
             session_start,
             Some(&session.repo_root),
+            &settings,
             None,
         )
         .await
@@ -1988,7 +3830,7 @@ async fn process_repo_backfill(
             "remote": repo_remote.as_str(),
         }),
     );
-    match push::try_push_remote_at_quiet(&repo_root, &repo_remote).await {
+    match push::try_push_remote_at_quiet(&repo_root, &repo_remote, &settings).await {
         Ok(()) => {
             backfill_logger.event(
                 "repo_push_completed",
@@ -2009,6 +3851,22 @@ async fn process_repo_backfill(
                     "error": format!("{e:#}"),
                 }),
             );
+            // Leave a pending sync job behind so the refs are pushed once the
+            // remote is reachable again (e.g. after working offline).
+            let reason = format!("push during backfill failed: {e:#}");
+            if deferred_sync::enqueue_pending_sync(&repo_root, &repo_remote, &reason)
+                .await
+                .is_ok()
+            {
+                backfill_logger.event(
+                    "repo_push_queued",
+                    serde_json::json!({
+                        "repo_display": repo_display.as_str(),
+                        "repo_root": repo_root_str.as_str(),
+                        "remote": repo_remote.as_str(),
+                    }),
+                );
+            }
         }
     }
 
@@ -2034,8 +3892,13 @@ async fn process_repo_backfill(
     stats
 }
 
-async fn run_backfill_inner(since: &str, repo_filter: Option<&std::path::Path>) -> Result<()> {
-    let since_secs = parse_since_duration(since)?;
+/// Returns the number of issues encountered; they are reported but never fail
+/// the run.
+async fn run_backfill_inner(
+    since_secs: i64,
+    repo_filter: Option<&std::path::Path>,
+    agent_filter: Option<&scanner::AgentType>,
+) -> Result<usize> {
     let since_days = since_secs / 86_400;
 
     let now = std::time::SystemTime::now()
@@ -2063,30 +3926,23 @@ async fn run_backfill_inner(since: &str, repo_filter: Option<&std::path::Path>)
     };
 
     let use_progress = output::is_stderr_tty();
-    let spinner = if use_progress {
-        let pb = ProgressBar::new_spinner();
-        pb.set_draw_target(ProgressDrawTarget::stderr());
-        pb.set_style(
-            ProgressStyle::with_template("{spinner} {msg}")
-                .unwrap_or_else(|_| ProgressStyle::default_spinner()),
-        );
-        pb.enable_steady_tick(std::time::Duration::from_millis(120));
-        pb.set_message(format!("Scanning agent logs (last {} days)", since_days));
-        Some(pb)
-    } else {
-        None
-    };
+    let spinner = use_progress.then(|| {
+        output::Spinner::start(&format!(
+            "Scanning agent logs ({})",
+            describe_window(since_secs)
+        ))
+    });
 
     backfill_logger.event(
         "backfill_started",
         serde_json::json!({
             "cli_version": env!("CARGO_PKG_VERSION"),
-            "since": since,
             "since_secs": since_secs,
             "since_days": since_days,
             "do_push": true,
             "sync_remote_before_attach": true,
             "repo_filter": repo_filter.map(|p| p.to_string_lossy().to_string()),
+            "agent_filter": agent_filter.map(|a| a.to_string()),
             "use_progress": use_progress,
         }),
     );
@@ -2099,12 +3955,36 @@ async fn run_backfill_inner(since: &str, repo_filter: Option<&std::path::Path>)
             "since_secs": since_secs,
         }),
     );
-    let files = agents::discover_recent_sessions(now, since_secs).await;
-    if let Some(pb) = spinner {
-        pb.finish_and_clear();
+    let files = match agent_filter {
+        Some(agent) => agents::discover_recent_sessions_for_agent(agent, now, since_secs).await,
+        None => agents::discover_recent_sessions(now, since_secs).await,
+    };
+    let max_log_bytes = agents::max_session_log_bytes();
+    let (files, oversized) = agents::partition_oversized(files, max_log_bytes).await;
+    if let Some(spinner) = spinner {
+        spinner.finish_and_clear();
     }
-    output::action("Scanned", &format!("agent logs (last {} days)", since_days));
+    output::action(
+        "Scanned",
+        &format!("agent logs ({})", describe_window(since_secs)),
+    );
     output::detail(&format!("Found {} session logs", files.len()));
+    for skipped in &oversized {
+        output::detail(&format!(
+            "Skipped {} ({} bytes, over the {max_log_bytes}-byte limit)",
+            skipped.path.display(),
+            skipped.bytes
+        ));
+        backfill_logger.event(
+            "session_discovery_skipped",
+            serde_json::json!({
+                "file": skipped.path.to_string_lossy(),
+                "reason": "oversized",
+                "bytes": skipped.bytes,
+                "max_bytes": max_log_bytes,
+            }),
+        );
+    }
     let mut agent_counts: std::collections::BTreeMap<String, usize> =
         std::collections::BTreeMap::new();
     for file in &files {
@@ -2150,8 +4030,13 @@ async fn run_backfill_inner(since: &str, repo_filter: Option<&std::path::Path>)
     } else {
         None
     };
+    let mut line_progress = (!use_progress)
+        .then(|| output::ProgressCounter::new(files.len() as u64, "processing sessions"));
 
     for log in &files {
+        if let Some(counter) = line_progress.as_mut() {
+            counter.inc();
+        }
         let file_path = log.source_label();
         let metadata = session_log_metadata(log).await;
 
@@ -2218,7 +4103,7 @@ async fn run_backfill_inner(since: &str, repo_filter: Option<&std::path::Path>)
 
         // If a repo filter is set, skip sessions that don't match.
         if let Some(filter) = repo_filter
-            && repo_root != filter
+            && !scanner::same_path(&repo_root, filter)
         {
             backfill_logger.event(
                 "session_discovery_skipped",
@@ -2308,6 +4193,9 @@ async fn run_backfill_inner(since: &str, repo_filter: Option<&std::path::Path>)
     if let Some(pb) = progress {
         pb.finish_and_clear();
     }
+    if let Some(counter) = line_progress {
+        counter.finish();
+    }
 
     // Step 4: Process sessions grouped by repo (bounded parallelism)
     let total_repos = sessions_by_repo.len();
@@ -2374,7 +4262,12 @@ async fn run_backfill_inner(since: &str, repo_filter: Option<&std::path::Path>)
         });
     }
 
+    let mut repo_line_progress =
+        (!use_progress).then(|| output::ProgressCounter::new(total_repos as u64, "repos synced"));
     while let Some(joined) = join_set.join_next().await {
+        if let Some(counter) = repo_line_progress.as_mut() {
+            counter.inc();
+        }
         match joined {
             Ok(Ok(repo_stats)) => {
                 attached += repo_stats.attached;
@@ -2413,6 +4306,10 @@ async fn run_backfill_inner(since: &str, repo_filter: Option<&std::path::Path>)
         }
     }
 
+    if let Some(counter) = repo_line_progress {
+        counter.finish();
+    }
+
     // Final summary
     output::success(
         "Backfill",
@@ -2444,7 +4341,7 @@ async fn run_backfill_inner(since: &str, repo_filter: Option<&std::path::Path>)
             "do_push": true,
         }),
     );
-    Ok(())
+    Ok(errors)
 }
 
 fn parse_ls_tree_line(line: &str) -> Option<(String, String, String)> {
@@ -2719,7 +4616,9 @@ async fn build_local_session_labels_for_repo(
         .unwrap_or_default()
         .as_secs() as i64;
     let mut labels = std::collections::HashMap::new();
-    for log in agents::discover_recent_sessions(now, 90 * 86_400).await {
+    let logs = agents::discover_recent_sessions(now, 90 * 86_400).await;
+    let (logs, _) = agents::partition_oversized(logs, agents::max_session_log_bytes()).await;
+    for log in logs {
         let metadata = session_log_metadata(&log).await;
         let Some(cwd) = metadata.cwd else {
             continue;
@@ -2776,10 +4675,12 @@ async fn session_display_label(
         Err(_) => return fallback,
     };
 
-    if let Some(excerpt) = one_line_excerpt(&envelope.session_content, 72) {
+    let content = envelope.resolve_content().await;
+    let text = content.text().unwrap_or_default();
+    if let Some(excerpt) = one_line_excerpt(text, 72) {
         return excerpt;
     }
-    if let Some(excerpt) = jsonl_prompt_excerpt(&envelope.session_content, 72) {
+    if let Some(excerpt) = jsonl_prompt_excerpt(text, 72) {
         return excerpt;
     }
     if !envelope.record.session_id.trim().is_empty() {
@@ -2797,7 +4698,9 @@ async fn discovered_repos_for_sessions() -> Vec<std::path::PathBuf> {
         .unwrap_or_default()
         .as_secs() as i64;
     let mut repos = std::collections::BTreeSet::new();
-    for log in agents::discover_recent_sessions(now, 90 * 86_400).await {
+    let logs = agents::discover_recent_sessions(now, 90 * 86_400).await;
+    let (logs, _) = agents::partition_oversized(logs, agents::max_session_log_bytes()).await;
+    for log in logs {
         let metadata = session_log_metadata(&log).await;
         let Some(cwd) = metadata.cwd else {
             continue;
@@ -2817,45 +4720,55 @@ async fn print_sessions_for_repo(repo: &std::path::Path) -> Result<()> {
 
     let mut branch_entries =
         list_index_entries_for_key(repo, git::SESSION_INDEX_BRANCH_REF, &branch_key_hash).await?;
-    branch_entries.sort_by(|a, b| b.session_start.cmp(&a.session_start));
+    branch_entries.sort_by_key(|e| std::cmp::Reverse(e.session_start));
     branch_entries.dedup_by(|a, b| a.session_uid == b.session_uid);
 
     let mut user_entries =
         list_index_entries_for_key(repo, git::SESSION_INDEX_COMMITTER_REF, &committer_hash).await?;
-    user_entries.sort_by(|a, b| b.session_start.cmp(&a.session_start));
+    user_entries.sort_by_key(|e| std::cmp::Reverse(e.session_start));
     user_entries.dedup_by(|a, b| a.session_uid == b.session_uid);
     let local_labels = build_local_session_labels_for_repo(repo).await;
 
     output::action("Repo", &repo_str);
     output::detail("Branch sessions:");
+    let mut rows = Vec::new();
     for entry in branch_entries.iter().take(10) {
-        let label = session_display_label(repo, entry, &local_labels).await;
-        output::detail(&format!(
-            "  {} {} {}",
-            entry.session_start.unwrap_or_default(),
-            entry.agent,
-            label
-        ));
+        rows.push(session_table_row(repo, entry, &local_labels).await);
     }
+    output::table(SESSION_TABLE_HEADERS, &rows);
     let shown = user_entries.len().min(10);
     output::detail(&format!(
         "User sessions (last {} of {}):",
         shown,
         user_entries.len()
     ));
+    let mut rows = Vec::new();
     for entry in user_entries.iter().take(shown) {
-        let label = session_display_label(repo, entry, &local_labels).await;
-        output::detail(&format!(
-            "  {} {} {}",
-            entry.session_start.unwrap_or_default(),
-            entry.agent,
-            label
-        ));
+        rows.push(session_table_row(repo, entry, &local_labels).await);
     }
+    output::table(SESSION_TABLE_HEADERS, &rows);
     Ok(())
 }
 
-async fn run_sessions_list(all: bool) -> Result<()> {
+const SESSION_TABLE_HEADERS: &[&str] = &["started", "agent", "session"];
+
+async fn session_table_row(
+    repo: &std::path::Path,
+    entry: &note::IndexEntry,
+    local_labels: &std::collections::HashMap<String, String>,
+) -> Vec<String> {
+    let started = entry
+        .session_start
+        .and_then(format_unix_rfc3339)
+        .unwrap_or_else(|| "-".to_string());
+    vec![
+        started,
+        entry.agent.clone(),
+        session_display_label(repo, entry, local_labels).await,
+    ]
+}
+
+async fn run_sessions_list(all: bool, repo: Option<&Path>) -> Result<()> {
     if all {
         let repos = discovered_repos_for_sessions().await;
         if repos.is_empty() {
@@ -2868,7 +4781,7 @@ async fn run_sessions_list(all: bool) -> Result<()> {
         return Ok(());
     }
 
-    let repo = git::repo_root()
+    let repo = command_repo_root(repo)
         .await
         .map_err(|_| anyhow::anyhow!("not in a git repository. Use `cadence sessions --all`."))?;
     print_sessions_for_repo(&repo).await
@@ -2913,13 +4826,14 @@ async fn sessions_audit_repo(repo: &std::path::Path, show_ok: bool) -> Result<()
     ));
 
     let mut total_sessions = 0usize;
+    let mut mismatched = 0usize;
 
     for branch in branches {
         let branch_key = format!("{remote}/{branch}");
         let key_hash = note::hash_key(&branch_key);
         let mut entries =
             list_index_entries_for_key(repo, git::SESSION_INDEX_BRANCH_REF, &key_hash).await?;
-        entries.sort_by(|a, b| b.session_start.cmp(&a.session_start));
+        entries.sort_by_key(|e| std::cmp::Reverse(e.session_start));
         entries.dedup_by(|a, b| a.session_uid == b.session_uid);
         if entries.is_empty() {
             continue;
@@ -2932,6 +4846,20 @@ async fn sessions_audit_repo(repo: &std::path::Path, show_ok: bool) -> Result<()
         ));
         for entry in entries {
             total_sessions += 1;
+            let record = load_session_envelope_for_entry(repo, &entry)
+                .await
+                .map(|envelope| envelope.record);
+            if let Some(problem) = record
+                .as_ref()
+                .and_then(|record| index_entry_mismatch(&entry, record))
+            {
+                mismatched += 1;
+                output::note(&format!(
+                    "MISMATCH uid={} on {branch}: {problem}",
+                    short_session_uid(&entry.session_uid)
+                ));
+                continue;
+            }
             if show_ok {
                 let label = session_display_label(repo, &entry, &local_labels).await;
                 output::detail(&format!(
@@ -2943,11 +4871,32 @@ async fn sessions_audit_repo(repo: &std::path::Path, show_ok: bool) -> Result<()
         }
     }
 
-    output::detail(&format!("summary: total={}", total_sessions));
+    output::detail(&format!(
+        "summary: total={total_sessions} mismatched={mismatched}"
+    ));
     Ok(())
 }
 
-async fn run_sessions_audit(all: bool, show_ok: bool) -> Result<()> {
+/// How the session object an index entry points at disagrees with the
+/// entry, e.g. an entry copied from another session's; `None` if it agrees.
+fn index_entry_mismatch(entry: &note::IndexEntry, record: &note::SessionRecord) -> Option<String> {
+    if record.session_uid != entry.session_uid {
+        return Some(format!(
+            "object {} belongs to session {}",
+            &entry.session_blob_sha[..entry.session_blob_sha.len().min(12)],
+            short_session_uid(&record.session_uid)
+        ));
+    }
+    if record.agent != entry.agent {
+        return Some(format!(
+            "indexed as {} but the object records {}",
+            entry.agent, record.agent
+        ));
+    }
+    None
+}
+
+async fn run_sessions_audit(all: bool, show_ok: bool, repo: Option<&Path>) -> Result<()> {
     if all {
         let repos = discovered_repos_for_sessions().await;
         if repos.is_empty() {
@@ -2959,17 +4908,22 @@ async fn run_sessions_audit(all: bool, show_ok: bool) -> Result<()> {
         }
         return Ok(());
     }
-    let repo = git::repo_root().await.map_err(|_| {
+    let repo = command_repo_root(repo).await.map_err(|_| {
         anyhow::anyhow!("not in a git repository. Use `cadence sessions audit --all`.")
     })?;
     sessions_audit_repo(&repo, show_ok).await
 }
 
-async fn run_sessions_inspect(query: &str, all: bool, raw: bool) -> Result<()> {
+async fn run_sessions_inspect(
+    query: &str,
+    all: bool,
+    raw: bool,
+    repo: Option<&Path>,
+) -> Result<()> {
     let repos = if all {
         discovered_repos_for_sessions().await
     } else {
-        vec![git::repo_root().await.map_err(|_| {
+        vec![command_repo_root(repo).await.map_err(|_| {
             anyhow::anyhow!(
                 "not in a git repository. Use `cadence sessions inspect --all <query>`."
             )
@@ -2988,7 +4942,7 @@ async fn run_sessions_inspect(query: &str, all: bool, raw: bool) -> Result<()> {
         let mut user_entries =
             list_index_entries_for_key(&repo, git::SESSION_INDEX_COMMITTER_REF, &committer_hash)
                 .await?;
-        user_entries.sort_by(|a, b| b.session_start.cmp(&a.session_start));
+        user_entries.sort_by_key(|e| std::cmp::Reverse(e.session_start));
         user_entries.dedup_by(|a, b| a.session_uid == b.session_uid);
 
         let remote = git::resolve_push_remote_at(&repo)
@@ -3025,7 +4979,7 @@ async fn run_sessions_inspect(query: &str, all: bool, raw: bool) -> Result<()> {
                 let mut branch_entries =
                     list_index_entries_for_key(&repo, git::SESSION_INDEX_BRANCH_REF, &key_hash)
                         .await?;
-                branch_entries.sort_by(|a, b| b.session_start.cmp(&a.session_start));
+                branch_entries.sort_by_key(|e| std::cmp::Reverse(e.session_start));
                 branch_entries.dedup_by(|a, b| a.session_uid == b.session_uid);
                 if branch_entries
                     .iter()
@@ -3037,15 +4991,38 @@ async fn run_sessions_inspect(query: &str, all: bool, raw: bool) -> Result<()> {
             output::detail(&format!("branches={:?}", branch_hits));
             if let Some(env) = envelope {
                 output::detail(&format!("session_id={}", env.record.session_id));
+                if env.record.matched_by_subject {
+                    output::detail("matched_by=commit subject (outside the time window)");
+                }
                 if raw {
                     output::detail("raw_record:");
                     let raw_record = serde_json::to_string_pretty(&env.record)?;
                     for line in raw_record.lines() {
                         output::detail(line);
                     }
-                    output::detail("raw_session_content:");
-                    for line in env.session_content.lines() {
-                        output::detail(line);
+                    match env.resolve_content().await {
+                        note::SessionContent::Stored(content) => {
+                            output::detail("raw_session_content:");
+                            for line in content.lines() {
+                                output::detail(line);
+                            }
+                        }
+                        note::SessionContent::Referenced { content, changed } => {
+                            output::detail("raw_session_content (re-read from session_path):");
+                            if changed {
+                                output::note(
+                                    "The session log changed after it was stored; showing its current content.",
+                                );
+                            }
+                            for line in content.lines() {
+                                output::detail(line);
+                            }
+                        }
+                        note::SessionContent::Missing { path } => {
+                            output::detail(&format!(
+                                "raw_session_content unavailable: {path} no longer exists"
+                            ));
+                        }
                     }
                 }
             } else if raw {
@@ -3060,15 +5037,76 @@ async fn run_sessions_inspect(query: &str, all: bool, raw: bool) -> Result<()> {
     Ok(())
 }
 
-async fn run_sessions(command: Option<SessionsCommand>, all: bool) -> Result<()> {
+async fn run_sessions(
+    command: Option<SessionsCommand>,
+    all: bool,
+    repo: Option<&Path>,
+) -> Result<()> {
     match command {
-        None => run_sessions_list(all).await,
-        Some(SessionsCommand::List { all }) => run_sessions_list(all).await,
-        Some(SessionsCommand::Audit { all, show_ok }) => run_sessions_audit(all, show_ok).await,
+        None => run_sessions_list(all, repo).await,
+        Some(SessionsCommand::List { all }) => run_sessions_list(all, repo).await,
+        Some(SessionsCommand::Audit { all, show_ok }) => {
+            run_sessions_audit(all, show_ok, repo).await
+        }
         Some(SessionsCommand::Inspect { query, all, raw }) => {
-            run_sessions_inspect(&query, all, raw).await
+            run_sessions_inspect(&query, all, raw, repo).await
         }
+        Some(SessionsCommand::Detect { since }) => run_sessions_detect(&since, repo).await,
+    }
+}
+
+/// List recent session logs from every agent with their detected session id
+/// and how their working directory relates to the current repo. Nothing is
+/// stored; this shows what the matcher sees.
+async fn run_sessions_detect(since: &str, repo: Option<&Path>) -> Result<()> {
+    let since_secs = parse_since_duration(since)?;
+    let repo = command_repo_root(repo)
+        .await
+        .map_err(|_| anyhow::anyhow!("not in a git repository"))?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+
+    let candidates = matcher::Matcher::new(since_secs)
+        .match_parent_cwd(settings::Settings::load(Some(&repo)).await.match_parent_cwd)
+        .candidates(&repo, now)
+        .await;
+    output::action("Detect", &repo.to_string_lossy());
+    let matching = candidates.iter().filter(|c| c.is_match()).count();
+    output::detail(&format!(
+        "{} session logs in the {}, {} matching this repo",
+        candidates.len(),
+        describe_window(since_secs),
+        matching
+    ));
+    if candidates.is_empty() {
+        return Ok(());
     }
+
+    let rows: Vec<Vec<String>> = candidates
+        .iter()
+        .map(|c| {
+            vec![
+                c.updated_at
+                    .and_then(format_unix_rfc3339)
+                    .unwrap_or_else(|| "-".to_string()),
+                c.evidence
+                    .mtime_delta_secs
+                    .map_or_else(|| "-".to_string(), format_mtime_delta),
+                c.agent.to_string(),
+                match &c.session_id {
+                    Some(id) if c.evidence.session_id_inferred => format!("{id} (from file name)"),
+                    Some(id) => id.clone(),
+                    None => "-".to_string(),
+                },
+                c.evidence.cwd_match.as_str().to_string(),
+                c.source_label.clone(),
+            ]
+        })
+        .collect();
+    output::table(&["updated", "age", "agent", "session", "cwd", "log"], &rows);
+    Ok(())
 }
 
 /// The status subcommand: show Cadence CLI configuration and state.
@@ -3082,15 +5120,41 @@ async fn run_sessions(command: Option<SessionsCommand>, all: bool) -> Result<()>
 ///
 /// All output is user-facing and written to stderr.
 /// Handles being called outside a git repo gracefully.
-async fn run_status() -> Result<()> {
-    run_status_inner(&mut std::io::stderr()).await
+/// One status line per sync job queued for `root`: where it pushes, why it
+/// was queued and how it last failed.
+async fn report_pending_sync(w: &mut dyn std::io::Write, root: &Path) {
+    let Ok(jobs) = deferred_sync::pending_sync_jobs_for(root).await else {
+        return;
+    };
+    for job in jobs {
+        let reason = if job.reason.is_empty() {
+            "reason not recorded"
+        } else {
+            job.reason.as_str()
+        };
+        output::detail_to_with_tty(
+            w,
+            &format!(
+                "Pending sync: {} since {} ({reason}), {} failed attempt(s)",
+                job.remote, job.enqueued_at, job.attempt_count
+            ),
+            false,
+        );
+        if let Some(error) = job.last_error {
+            output::detail_to_with_tty(w, &format!("Last sync error: {error}"), false);
+        }
+    }
+}
+
+async fn run_status(repo: Option<&Path>) -> Result<()> {
+    run_status_inner(&mut std::io::stderr(), repo).await
 }
 
-async fn run_status_inner(w: &mut dyn std::io::Write) -> Result<()> {
+async fn run_status_inner(w: &mut dyn std::io::Write, repo: Option<&Path>) -> Result<()> {
     output::action_to_with_tty(w, "Status", "", false);
 
     // --- Repo root ---
-    let repo_root = match git::repo_root().await {
+    let repo_root = match command_repo_root(repo).await {
         Ok(root) => {
             output::detail_to_with_tty(w, &format!("Repo: {}", root.to_string_lossy()), false);
             Some(root)
@@ -3114,19 +5178,14 @@ async fn run_status_inner(w: &mut dyn std::io::Write) -> Result<()> {
         {
             Some(path) => {
                 let hooks_dir = resolve_hooks_path(Some(root), &path);
-                let (post_installed, pre_installed) = cadence_hooks_installed(&hooks_dir).await;
-                let post_str = if post_installed { "yes" } else { "no" };
-                let pre_str = if pre_installed { "yes" } else { "no" };
+                let installed = cadence_hooks_installed(&hooks_dir).await;
                 output::detail_to_with_tty(
                     w,
-                    &format!(
-                        "Hooks path: {} (post-commit: {}, pre-push: {})",
-                        path, post_str, pre_str
-                    ),
+                    &format!("Hooks path: {} ({})", path, installed.summary()),
                     false,
                 );
 
-                if !post_installed || !pre_installed {
+                if !installed.all() {
                     output::note_to_with_tty(
                         w,
                         "Cadence hooks are not fully installed in the active hooksPath.",
@@ -3162,21 +5221,32 @@ async fn run_status_inner(w: &mut dyn std::io::Write) -> Result<()> {
         }
     } else if let Some(path) = global_hooks_path {
         let hooks_dir = resolve_hooks_path(None, &path);
-        let (post_installed, pre_installed) = cadence_hooks_installed(&hooks_dir).await;
-        let post_str = if post_installed { "yes" } else { "no" };
-        let pre_str = if pre_installed { "yes" } else { "no" };
+        let installed = cadence_hooks_installed(&hooks_dir).await;
         output::detail_to_with_tty(
             w,
-            &format!(
-                "Hooks path: {} (post-commit: {}, pre-push: {})",
-                path, post_str, pre_str
-            ),
+            &format!("Hooks path: {} ({})", path, installed.summary()),
             false,
         );
     } else {
         output::detail_to_with_tty(w, "Hooks path: (not configured)", false);
     }
 
+    let hooks_path = match &repo_root {
+        Some(root) => git::config_get_at(root, "core.hooksPath").await,
+        None => git::config_get("core.hooksPath").await,
+    };
+    if let Some(path) = hooks_path.ok().flatten() {
+        let hooks_dir = resolve_hooks_path(repo_root.as_deref(), &path);
+        if let Some((hook_name, exe)) = stale_hook_exe(&hooks_dir).await {
+            output::note_to_with_tty(
+                w,
+                &format!("{hook_name} hook runs {exe}, which no longer exists"),
+                false,
+            );
+            output::detail_to_with_tty(w, "Run `cadence install --force` to repair it.", false);
+        }
+    }
+
     if let Some(ref root) = repo_root {
         let has_data_ref = git::ref_exists_at(Some(root), git::SESSION_DATA_REF)
             .await
@@ -3197,22 +5267,24 @@ async fn run_status_inner(w: &mut dyn std::io::Write) -> Result<()> {
             ),
             false,
         );
+        report_session_data_size(w, root).await;
+        report_pending_sync(w, root).await;
     }
 
     // --- Org filter ---
-    match git::config_get_global("ai.cadence.org").await {
-        Ok(Some(org)) => {
+    let settings = settings::Settings::load(repo_root.as_deref()).await;
+    match &settings.org {
+        Some(org) => {
             output::detail_to_with_tty(w, &format!("Org filter: {}", org), false);
         }
-        _ => {
+        None => {
             output::detail_to_with_tty(w, "Org filter: (none)", false);
         }
     }
 
     // --- Per-repo enabled/disabled ---
     if repo_root.is_some() {
-        let enabled = git::check_enabled().await;
-        if enabled {
+        if settings.enabled {
             output::detail_to_with_tty(w, "Repo enabled: yes", false);
         } else {
             output::detail_to_with_tty(w, "Repo enabled: no", false);
@@ -3277,28 +5349,143 @@ async fn run_status_inner(w: &mut dyn std::io::Write) -> Result<()> {
     );
     output::detail_to_with_tty(
         w,
-        &format!("Auto-update remediation: {}", scheduler_health.remediation),
+        &format!("Auto-update remediation: {}", scheduler_health.remediation),
+        false,
+    );
+    output::detail_to_with_tty(
+        w,
+        "Controls: `cadence auto-update status|enable|disable|uninstall`",
+        false,
+    );
+
+    Ok(())
+}
+
+/// Stored session data beyond this many bytes makes `status` and `doctor`
+/// suggest shrinking it. Override with `CADENCE_SESSION_DATA_WARN_BYTES`.
+const DEFAULT_SESSION_DATA_WARN_BYTES: u64 = 256 * 1024 * 1024;
+
+fn session_data_warn_bytes() -> u64 {
+    std::env::var("CADENCE_SESSION_DATA_WARN_BYTES")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|v| *v > 0)
+        .unwrap_or(DEFAULT_SESSION_DATA_WARN_BYTES)
+}
+
+fn format_mib(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// Print the size of the session data ref and, past the warning threshold,
+/// how to shrink it.
+async fn report_session_data_size(w: &mut dyn std::io::Write, root: &Path) {
+    let Ok(Some((objects, bytes))) =
+        git::tree_blob_stats_at(Some(root), git::SESSION_DATA_REF).await
+    else {
+        return;
+    };
+    output::detail_to_with_tty(
+        w,
+        &format!("Session data: {objects} objects, {}", format_mib(bytes)),
         false,
     );
-    output::detail_to_with_tty(
+    let limit = session_data_warn_bytes();
+    if bytes <= limit {
+        return;
+    }
+    output::note_to_with_tty(
         w,
-        "Controls: `cadence auto-update status|enable|disable|uninstall`",
+        &format!(
+            "Session data exceeds {} and slows fetches of {}. To store new sessions as \
+             references to their local log files, set `git config {} reference`.",
+            format_mib(limit),
+            git::SESSION_DATA_REF,
+            note::SESSION_BODY_KEY
+        ),
         false,
     );
+}
 
-    Ok(())
+/// Outcome of one `cadence doctor` check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Ok,
+    Fail,
+    /// The check does not apply here, e.g. repository checks outside one.
+    Skipped,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct DoctorCheck {
+    name: &'static str,
+    status: CheckStatus,
+    detail: String,
+}
+
+/// What `cadence doctor` found, printed as JSON with `--json`.
+#[derive(Debug, serde::Serialize)]
+struct DoctorReport {
+    /// No check failed.
+    ok: bool,
+    checks: Vec<DoctorCheck>,
+}
+
+impl Default for DoctorReport {
+    fn default() -> Self {
+        Self {
+            ok: true,
+            checks: Vec::new(),
+        }
+    }
+}
+
+impl DoctorReport {
+    fn record(&mut self, name: &'static str, status: CheckStatus, detail: impl Into<String>) {
+        self.ok &= status != CheckStatus::Fail;
+        self.checks.push(DoctorCheck {
+            name,
+            status,
+            detail: detail.into(),
+        });
+    }
+
+    fn issues(&self) -> usize {
+        self.checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::Fail)
+            .count()
+    }
 }
 
-async fn run_doctor(repair: bool) -> Result<()> {
-    run_doctor_inner(&mut std::io::stderr(), repair).await
+async fn run_doctor(repair: bool, json: bool, repo: Option<&Path>) -> Result<()> {
+    let report = if json {
+        run_doctor_inner(&mut std::io::sink(), repair, repo).await?
+    } else {
+        run_doctor_inner(&mut std::io::stderr(), repair, repo).await?
+    };
+    if json {
+        println!("{}", serde_json::to_string(&report)?);
+    }
+    let issues = report.issues();
+    if issues > 0 {
+        anyhow::bail!("doctor found {} issue(s)", issues);
+    }
+    Ok(())
 }
 
-async fn run_doctor_inner(w: &mut dyn std::io::Write, repair: bool) -> Result<()> {
+/// Run every doctor check, printing the checklist to `w`.
+async fn run_doctor_inner(
+    w: &mut dyn std::io::Write,
+    repair: bool,
+    repo: Option<&Path>,
+) -> Result<DoctorReport> {
     output::action_to_with_tty(w, "Doctor", "", false);
 
-    let mut issues = 0usize;
+    let mut report = DoctorReport::default();
 
-    let repo_root = match git::repo_root().await {
+    let repo_root = match command_repo_root(repo).await {
         Ok(root) => {
             output::detail_to_with_tty(w, &format!("Repo: {}", root.to_string_lossy()), false);
             Some(root)
@@ -3312,13 +5499,9 @@ async fn run_doctor_inner(w: &mut dyn std::io::Write, repair: bool) -> Result<()
     let global_hooks_path = match git::config_get_global("core.hooksPath").await {
         Ok(path) => path,
         Err(e) => {
-            output::fail_to_with_tty(
-                w,
-                "Failed",
-                &format!("could not read global core.hooksPath ({e})"),
-                false,
-            );
-            issues += 1;
+            let detail = format!("could not read global core.hooksPath ({e})");
+            output::fail_to_with_tty(w, "Failed", &detail, false);
+            report.record("global_hooks", CheckStatus::Fail, detail);
             None
         }
     };
@@ -3326,18 +5509,13 @@ async fn run_doctor_inner(w: &mut dyn std::io::Write, repair: bool) -> Result<()
     match &global_hooks_path {
         Some(path) => {
             let hooks_dir = resolve_hooks_path(repo_root.as_deref(), path);
-            let (post_installed, pre_installed) = cadence_hooks_installed(&hooks_dir).await;
+            let installed = cadence_hooks_installed(&hooks_dir).await;
             output::detail_to_with_tty(
                 w,
-                &format!(
-                    "Global hooks: {} (post-commit: {}, pre-push: {})",
-                    path,
-                    if post_installed { "yes" } else { "no" },
-                    if pre_installed { "yes" } else { "no" }
-                ),
+                &format!("Global hooks: {} ({})", path, installed.summary()),
                 false,
             );
-            if !post_installed || !pre_installed {
+            if !installed.all() {
                 output::fail_to_with_tty(
                     w,
                     "Fail",
@@ -3345,13 +5523,36 @@ async fn run_doctor_inner(w: &mut dyn std::io::Write, repair: bool) -> Result<()
                     false,
                 );
                 output::detail_to_with_tty(w, "Run `cadence install` to repair hooks.", false);
-                issues += 1;
+                report.record(
+                    "global_hooks",
+                    CheckStatus::Fail,
+                    format!("Cadence global hooks are not fully installed in {path}"),
+                );
+            } else {
+                report.record("global_hooks", CheckStatus::Ok, path.clone());
+            }
+            match stale_hook_exe(&hooks_dir).await {
+                Some((hook_name, exe)) => {
+                    let detail = format!("{hook_name} hook runs {exe}, which no longer exists");
+                    output::fail_to_with_tty(w, "Fail", &detail, false);
+                    output::detail_to_with_tty(
+                        w,
+                        "Run `cadence install --force` to point hooks at this binary.",
+                        false,
+                    );
+                    report.record("hook_binary", CheckStatus::Fail, detail);
+                }
+                None => report.record("hook_binary", CheckStatus::Ok, ""),
             }
         }
         None => {
             output::fail_to_with_tty(w, "Fail", "Global core.hooksPath is not configured", false);
             output::detail_to_with_tty(w, "Run `cadence install` to configure hooks.", false);
-            issues += 1;
+            report.record(
+                "global_hooks",
+                CheckStatus::Fail,
+                "Global core.hooksPath is not configured",
+            );
         }
     }
 
@@ -3359,18 +5560,13 @@ async fn run_doctor_inner(w: &mut dyn std::io::Write, repair: bool) -> Result<()
         match git::config_get_at(root, "core.hooksPath").await {
             Ok(Some(active_path)) => {
                 let hooks_dir = resolve_hooks_path(Some(root), &active_path);
-                let (post_installed, pre_installed) = cadence_hooks_installed(&hooks_dir).await;
+                let installed = cadence_hooks_installed(&hooks_dir).await;
                 output::detail_to_with_tty(
                     w,
-                    &format!(
-                        "Active hooks: {} (post-commit: {}, pre-push: {})",
-                        active_path,
-                        if post_installed { "yes" } else { "no" },
-                        if pre_installed { "yes" } else { "no" }
-                    ),
+                    &format!("Active hooks: {} ({})", active_path, installed.summary()),
                     false,
                 );
-                if !post_installed || !pre_installed {
+                if !installed.all() {
                     output::fail_to_with_tty(
                         w,
                         "Fail",
@@ -3378,26 +5574,24 @@ async fn run_doctor_inner(w: &mut dyn std::io::Write, repair: bool) -> Result<()
                         false,
                     );
                     output::detail_to_with_tty(w, "Run `cadence install` or fix hooksPath.", false);
-                    issues += 1;
+                    report.record(
+                        "repo_hooks",
+                        CheckStatus::Fail,
+                        format!("Active hooksPath {active_path} does not contain Cadence hooks"),
+                    );
+                } else {
+                    report.record("repo_hooks", CheckStatus::Ok, active_path);
                 }
             }
             Ok(None) => {
-                output::fail_to_with_tty(
-                    w,
-                    "Fail",
-                    "No effective hooksPath found for this repository",
-                    false,
-                );
-                issues += 1;
+                let detail = "No effective hooksPath found for this repository";
+                output::fail_to_with_tty(w, "Fail", detail, false);
+                report.record("repo_hooks", CheckStatus::Fail, detail);
             }
             Err(e) => {
-                output::fail_to_with_tty(
-                    w,
-                    "Fail",
-                    &format!("could not read repo hooksPath ({e})"),
-                    false,
-                );
-                issues += 1;
+                let detail = format!("could not read repo hooksPath ({e})");
+                output::fail_to_with_tty(w, "Fail", &detail, false);
+                report.record("repo_hooks", CheckStatus::Fail, detail);
             }
         }
 
@@ -3408,21 +5602,17 @@ async fn run_doctor_inner(w: &mut dyn std::io::Write, repair: bool) -> Result<()
             let local_resolved = resolve_hooks_path(Some(root), &local_hooks_path);
             let global_resolved = resolve_hooks_path(Some(root), global_path);
             if !paths_equivalent(&local_resolved, &global_resolved) {
-                output::fail_to_with_tty(
-                    w,
-                    "Fail",
-                    &format!(
-                        "Repo-local core.hooksPath overrides global Cadence hooks: {}",
-                        local_hooks_path
-                    ),
-                    false,
+                let detail = format!(
+                    "Repo-local core.hooksPath overrides global Cadence hooks: {}",
+                    local_hooks_path
                 );
+                output::fail_to_with_tty(w, "Fail", &detail, false);
                 output::detail_to_with_tty(
                     w,
                     "Run `git config --unset core.hooksPath` in this repo to use global hooks.",
                     false,
                 );
-                issues += 1;
+                report.record("local_hooks_override", CheckStatus::Fail, detail);
             }
         }
     } else {
@@ -3431,6 +5621,11 @@ async fn run_doctor_inner(w: &mut dyn std::io::Write, repair: bool) -> Result<()
             "Skipped repo-local hook checks because current directory is not a git repository.",
             false,
         );
+        report.record(
+            "repo_hooks",
+            CheckStatus::Skipped,
+            "not in a git repository",
+        );
     }
 
     if let Some(root) = repo_root.as_ref() {
@@ -3443,36 +5638,40 @@ async fn run_doctor_inner(w: &mut dyn std::io::Write, repair: bool) -> Result<()
         let has_committer_ref = git::ref_exists_at(Some(root), git::SESSION_INDEX_COMMITTER_REF)
             .await
             .unwrap_or(false);
-        output::detail_to_with_tty(
-            w,
-            &format!(
-                "Session refs: data={} branch-index={} committer-index={}",
-                if has_data_ref { "yes" } else { "no" },
-                if has_branch_ref { "yes" } else { "no" },
-                if has_committer_ref { "yes" } else { "no" }
-            ),
-            false,
+        let detail = format!(
+            "data={} branch-index={} committer-index={}",
+            if has_data_ref { "yes" } else { "no" },
+            if has_branch_ref { "yes" } else { "no" },
+            if has_committer_ref { "yes" } else { "no" }
         );
+        output::detail_to_with_tty(w, &format!("Session refs: {detail}"), false);
+        report.record("session_refs", CheckStatus::Ok, detail);
+        report_session_data_size(w, root).await;
     }
 
     let updater_health = update::updater_health().await;
     match updater_health.state {
         update::UpdaterHealthState::Disabled => {
             output::detail_to_with_tty(w, "Auto-update: disabled", false);
+            report.record("auto_update", CheckStatus::Ok, "disabled");
         }
         update::UpdaterHealthState::NeverRun => {
             output::detail_to_with_tty(w, "Auto-update: enabled, never run yet", false);
+            report.record("auto_update", CheckStatus::Ok, "enabled, never run yet");
         }
         update::UpdaterHealthState::Healthy => {
             output::detail_to_with_tty(w, "Auto-update: healthy", false);
+            report.record("auto_update", CheckStatus::Ok, "healthy");
         }
         update::UpdaterHealthState::Retrying => {
-            output::fail_to_with_tty(w, "Fail", "Auto-update is retrying after failures", false);
-            issues += 1;
+            let detail = "Auto-update is retrying after failures";
+            output::fail_to_with_tty(w, "Fail", detail, false);
+            report.record("auto_update", CheckStatus::Fail, detail);
         }
         update::UpdaterHealthState::Failing => {
-            output::fail_to_with_tty(w, "Fail", "Auto-update is failing repeatedly", false);
-            issues += 1;
+            let detail = "Auto-update is failing repeatedly";
+            output::fail_to_with_tty(w, "Fail", detail, false);
+            report.record("auto_update", CheckStatus::Fail, detail);
         }
     }
     if let Some(next_retry) = updater_health.next_retry_after {
@@ -3516,6 +5715,11 @@ async fn run_doctor_inner(w: &mut dyn std::io::Write, repair: bool) -> Result<()
                 &format!("Auto-update scheduler: {}", scheduler_health.details),
                 false,
             );
+            report.record(
+                "auto_update_scheduler",
+                CheckStatus::Ok,
+                scheduler_health.details.clone(),
+            );
         }
         update::SchedulerHealthState::Missing | update::SchedulerHealthState::Broken => {
             output::fail_to_with_tty(
@@ -3524,7 +5728,11 @@ async fn run_doctor_inner(w: &mut dyn std::io::Write, repair: bool) -> Result<()
                 &format!("Auto-update scheduler: {}", scheduler_health.details),
                 false,
             );
-            issues += 1;
+            report.record(
+                "auto_update_scheduler",
+                CheckStatus::Fail,
+                scheduler_health.details.clone(),
+            );
         }
     }
     output::detail_to_with_tty(
@@ -3538,13 +5746,13 @@ async fn run_doctor_inner(w: &mut dyn std::io::Write, repair: bool) -> Result<()
         false,
     );
 
-    if issues == 0 {
-        output::success_to_with_tty(w, "Doctor", "all checks passed", false);
-        Ok(())
-    } else {
-        output::fail_to_with_tty(w, "Doctor", &format!("{} issue(s) found", issues), false);
-        anyhow::bail!("doctor found {} issue(s)", issues);
+    match report.issues() {
+        0 => output::success_to_with_tty(w, "Doctor", "all checks passed", false),
+        issues => {
+            output::fail_to_with_tty(w, "Doctor", &format!("{} issue(s) found", issues), false)
+        }
     }
+    Ok(report)
 }
 
 // ---------------------------------------------------------------------------
@@ -4259,7 +6467,126 @@ async fn run_update(check: bool, yes: bool) -> Result<()> {
 // GC: clear bloated notes and re-backfill
 // ---------------------------------------------------------------------------
 
-async fn run_gc(since: &str, confirm: bool) -> Result<()> {
+/// Remove the entries of `dir` for which `keep` returns false. A missing
+/// directory counts as empty. Returns how many entries were removed.
+async fn remove_dir_entries(dir: &Path, keep: impl Fn(&str) -> bool) -> Result<usize> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", dir.display())),
+    };
+    let mut removed = 0usize;
+    while let Some(entry) = entries.next_entry().await? {
+        if keep(&entry.file_name().to_string_lossy()) {
+            continue;
+        }
+        let path = entry.path();
+        let result = if entry.file_type().await?.is_dir() {
+            tokio::fs::remove_dir_all(&path).await
+        } else {
+            tokio::fs::remove_file(&path).await
+        };
+        result.with_context(|| format!("failed to remove {}", path.display()))?;
+        removed += 1;
+    }
+    Ok(removed)
+}
+
+fn is_backfill_log(name: &str) -> bool {
+    (name.starts_with("backfill.") && name.ends_with(".log"))
+        || name == backfill_log::HOOK_METRICS_FILE
+        || name == backfill_log::HOOK_METRICS_OLD_FILE
+}
+
+async fn run_clean(pending: bool, logs: bool, token: bool, yes: bool) -> Result<()> {
+    if !pending && !logs && !token {
+        anyhow::bail!("nothing to clean; pass --pending, --logs, --token, or --all");
+    }
+    let state_dir = state::state_dir_path()
+        .ok_or_else(|| anyhow::anyhow!("could not determine home directory"))?;
+    let config_dir = config::CliConfig::config_dir()
+        .ok_or_else(|| anyhow::anyhow!("could not determine home directory"))?;
+
+    let selected: Vec<&str> = [
+        (pending, "pending sync jobs"),
+        (logs, "logs"),
+        (token, "auth token"),
+    ]
+    .into_iter()
+    .filter_map(|(on, label)| on.then_some(label))
+    .collect();
+
+    if !yes && output::is_stderr_tty() && std::io::stdin().is_terminal() {
+        let prompt = format!("Remove local {}?", selected.join(", "));
+        let mut prompter = DialoguerPrompter::new();
+        if prompter.confirm(&prompt, &mut std::io::stderr()).await? != Some(true) {
+            output::note("Clean cancelled; nothing was removed.");
+            return Ok(());
+        }
+    }
+
+    output::action("Cleaning", &selected.join(", "));
+    if pending {
+        let mut dirs = vec![
+            ("pending sync job(s)", state_dir.join("pending-sync")),
+            (
+                "pending ingest record(s)",
+                state_dir.join(sync_pending::PENDING_INGEST_DIR),
+            ),
+        ];
+        // Jobs left in the pre-XDG directory because the new one existed.
+        if let Some(legacy_dir) = state::legacy_state_dir_path()
+            && legacy_dir != state_dir
+        {
+            dirs.push(("pending sync job(s)", legacy_dir.join("pending-sync")));
+        }
+        for (what, dir) in dirs {
+            let removed = remove_dir_entries(&dir, |_| false).await?;
+            output::success(
+                "Removed",
+                &format!("{removed} {what} from {}", dir.display()),
+            );
+        }
+        let removed =
+            remove_dir_entries(&state_dir, |name| !attribution::is_queue_file(name)).await?;
+        output::success(
+            "Removed",
+            &format!(
+                "{removed} attribution queue file(s) from {}",
+                state_dir.display()
+            ),
+        );
+    }
+    if logs {
+        let dir = state_dir.join("logs");
+        let removed = remove_dir_entries(&dir, |_| false).await?;
+        output::success(
+            "Removed",
+            &format!("{removed} log item(s) from {}", dir.display()),
+        );
+        let removed = remove_dir_entries(&config_dir, |name| !is_backfill_log(name)).await?;
+        output::success(
+            "Removed",
+            &format!("{removed} backfill log(s) from {}", config_dir.display()),
+        );
+    }
+    if token {
+        let keychain = keychain::KeyringStore::new(KEYCHAIN_SERVICE);
+        if let Err(e) = keychain.delete(KEYCHAIN_AUTH_TOKEN_ACCOUNT).await {
+            output::note(&format!("Could not clear OS keychain token: {e}"));
+        }
+        let mut cfg = config::CliConfig::load().await?;
+        if cfg.token.is_some() {
+            cfg.clear_token().await?;
+            output::success("Removed", "stored auth token");
+        } else {
+            output::detail("No stored auth token");
+        }
+    }
+    Ok(())
+}
+
+async fn run_gc(since: &str, confirm: bool, repo: Option<&Path>) -> Result<()> {
     let session_refs = [
         git::SESSION_DATA_REF,
         git::SESSION_INDEX_BRANCH_REF,
@@ -4270,7 +6597,7 @@ async fn run_gc(since: &str, confirm: bool) -> Result<()> {
     let since_secs = parse_since_duration(since)?;
     let since_days = since_secs / 86_400;
 
-    let repo_root = git::repo_root().await?;
+    let repo_root = command_repo_root(repo).await?;
 
     if !confirm {
         output::note("This will DELETE all local and remote AI session refs for this repo,");
@@ -4329,7 +6656,7 @@ async fn run_gc(since: &str, confirm: bool) -> Result<()> {
         "GC",
         &format!("Re-backfilling (last {} days) with push", since_days),
     );
-    run_backfill_inner(since, Some(&repo_root)).await?;
+    run_backfill_inner(since_secs, Some(&repo_root), None).await?;
 
     output::success("GC", "Complete. Session refs were regenerated.");
     Ok(())
@@ -4342,7 +6669,7 @@ async fn run_gc(since: &str, confirm: bool) -> Result<()> {
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
     let cli = Cli::parse();
-    output::set_verbose(cli.verbose);
+    output::set_verbosity(cli_verbosity(cli.verbose, cli.quiet));
     if let Some(url) = cli.api_url.clone() {
         let _ = API_URL_OVERRIDE.set(url);
     }
@@ -4350,9 +6677,36 @@ async fn main() {
     let is_update_command = matches!(&cli.command, Command::Update { .. });
     let is_hook_command = matches!(&cli.command, Command::Hook { .. });
 
-    // Opportunistic sweep of pending sync jobs for normal CLI flows.
-    if !matches!(&cli.command, Command::Hook { .. }) && deferred_sync::has_pending_sync_jobs().await
+    // Without git there is nothing to attach sessions to. Hooks must never
+    // break the user's git operation, so they quietly do nothing; commands
+    // that touch repositories explain what is missing.
+    if command_needs_git(&cli.command)
+        && let Err(e) = git::ensure_available().await
     {
+        if is_hook_command {
+            log::debug(&format!("{e}; skipping hook"));
+            return;
+        }
+        output::fail("Failed", &format!("{e}"));
+        process::exit(1);
+    }
+
+    // `--repo`: git hooks already run inside their repository, the deferred
+    // sync worker takes the path as-is, and global commands ignore it.
+    let repo_override = cli.repo.clone();
+    let repo_root = match &repo_override {
+        Some(path) if command_uses_repo(&cli.command) => match resolve_repo(path).await {
+            Ok(root) => Some(root),
+            Err(e) => {
+                output::fail("Failed", &format!("{e:#}"));
+                process::exit(1);
+            }
+        },
+        _ => None,
+    };
+
+    // Opportunistic sweep of pending sync jobs for normal CLI flows.
+    if command_sweeps_pending_sync(&cli.command) && deferred_sync::has_pending_sync_jobs().await {
         let _ = deferred_sync::run_sync_command(deferred_sync::SyncRunOptions {
             repo: None,
             remote: None,
@@ -4375,12 +6729,17 @@ async fn main() {
     }
 
     let result = match cli.command {
-        Command::Install { org } => run_install(org).await,
+        Command::Install { org, force } => run_install(org, force).await,
         Command::Hook { hook_command } => match hook_command {
-            HookCommand::PostCommit => run_hook_post_commit().await,
+            HookCommand::PostCommit {
+                report_json,
+                explain,
+            } => run_hook_post_commit(report_json, explain).await,
+            HookCommand::PrepareCommitMsg {
+                msg_file, source, ..
+            } => run_hook_prepare_commit_msg(&msg_file, source.as_deref()).await,
             HookCommand::PrePush { remote, url } => run_hook_pre_push(&remote, &url).await,
             HookCommand::DeferredSync {
-                repo,
                 remote,
                 all_pending,
                 background,
@@ -4388,7 +6747,7 @@ async fn main() {
                 time_budget_ms,
             } => {
                 run_sync(
-                    repo,
+                    repo_override,
                     remote,
                     all_pending,
                     background,
@@ -4399,17 +6758,25 @@ async fn main() {
             }
             HookCommand::AutoUpdate => update::run_background_auto_update().await,
         },
-        Command::Backfill { since } => run_backfill(&since).await,
-        Command::Login => run_login().await,
+        Command::Backfill {
+            since,
+            since_last,
+            agent,
+        } => run_backfill(&since, since_last, repo_root.as_deref(), agent.as_ref()).await,
+        Command::Login { timeout, print_url } => {
+            run_login(Duration::from_secs(timeout), print_url).await
+        }
         Command::Logout => run_logout().await,
-        Command::Sessions { command, all } => run_sessions(command, all).await,
-        Command::Status => run_status().await,
+        Command::Sessions { command, all } => {
+            run_sessions(command, all, repo_root.as_deref()).await
+        }
+        Command::Status => run_status(repo_root.as_deref()).await,
         Command::Config { config_command } => match config_command.unwrap_or(ConfigCommand::List) {
             ConfigCommand::Set { key, value } => run_config_set(&key, &value).await,
             ConfigCommand::Get { key } => run_config_get(&key).await,
             ConfigCommand::List => run_config_list().await,
         },
-        Command::Doctor { repair } => run_doctor(repair).await,
+        Command::Doctor { repair, json } => run_doctor(repair, json, repo_root.as_deref()).await,
         Command::Update { check, yes } => run_update(check, yes).await,
         Command::AutoUpdate { command } => run_auto_update(command).await,
         Command::Keys { keys_command } => match keys_command.unwrap_or(KeysCommands::Status) {
@@ -4418,7 +6785,16 @@ async fn main() {
             KeysCommands::Disable => run_keys_disable().await,
             KeysCommands::Refresh => run_keys_refresh().await,
         },
-        Command::Gc { since, confirm } => run_gc(&since, confirm).await,
+        Command::Clean {
+            pending,
+            logs,
+            token,
+            all,
+            yes,
+        } => run_clean(pending || all, logs || all, token || all, yes).await,
+        Command::Gc { since, confirm } => run_gc(&since, confirm, repo_root.as_deref()).await,
+        Command::Selftest => selftest::run().await,
+        Command::Bench { iterations } => run_bench(iterations, repo_root.as_deref()).await,
     };
 
     // Passive background version check: run after successful command execution
@@ -4433,6 +6809,77 @@ async fn main() {
     }
 }
 
+/// Whether `command` shells out to git. Account and update commands work
+/// without it.
+fn command_needs_git(command: &Command) -> bool {
+    !matches!(
+        command,
+        Command::Login { .. }
+            | Command::Logout
+            | Command::Update { .. }
+            | Command::AutoUpdate { .. }
+            | Command::Hook {
+                hook_command: HookCommand::AutoUpdate
+            }
+    )
+}
+
+/// Whether running `command` may first push a couple of queued sync jobs.
+/// Hooks keep to their own budget; `clean` must be able to drop jobs without
+/// pushing them; and measurement and scripted-check commands should not
+/// reach the network as a side effect.
+fn command_sweeps_pending_sync(command: &Command) -> bool {
+    !matches!(
+        command,
+        Command::Hook { .. }
+            | Command::Clean { .. }
+            | Command::Bench { .. }
+            | Command::Selftest
+            | Command::Doctor { json: true, .. }
+    )
+}
+
+/// Whether `command` acts on the repository named by `--repo`. Account,
+/// configuration and other global commands ignore the option, so they work
+/// outside any repository.
+fn command_uses_repo(command: &Command) -> bool {
+    matches!(
+        command,
+        Command::Backfill { .. }
+            | Command::Sessions { .. }
+            | Command::Status
+            | Command::Doctor { .. }
+            | Command::Gc { .. }
+            | Command::Bench { .. }
+    )
+}
+
+/// Resolve `--repo <path>` to its repository root.
+async fn resolve_repo(path: &Path) -> Result<PathBuf> {
+    git::repo_root_at(path)
+        .await
+        .with_context(|| format!("--repo {} is not a git repository", path.display()))
+}
+
+/// The repository a command acts on: the `--repo` root when given,
+/// otherwise the one containing the current directory.
+async fn command_repo_root(repo: Option<&Path>) -> Result<PathBuf> {
+    match repo {
+        Some(root) => Ok(root.to_path_buf()),
+        None => git::repo_root().await,
+    }
+}
+
+fn cli_verbosity(verbose: bool, quiet: bool) -> output::Verbosity {
+    if quiet {
+        output::Verbosity::Quiet
+    } else if verbose {
+        output::Verbosity::Verbose
+    } else {
+        output::Verbosity::Normal
+    }
+}
+
 async fn run_sync(
     repo: Option<PathBuf>,
     remote: Option<String>,
@@ -4446,15 +6893,31 @@ async fn run_sync(
     let encryption_method = resolve_encryption_method()
         .await
         .unwrap_or_else(|e| EncryptionMethod::Unavailable(format!("{e}")));
+    let mut progress = (repo_roots.len() > 1)
+        .then(|| output::ProgressCounter::new(repo_roots.len() as u64, "ingesting pending repos"));
     for repo_root in &repo_roots {
+        if let Some(counter) = progress.as_mut() {
+            counter.inc();
+        }
         let repo_root_str = repo_root.to_string_lossy().to_string();
-        if let Err(e) =
-            ingest_incremental_sessions_for_repo(repo_root, &repo_root_str, &encryption_method)
-                .await
+        let settings = settings::Settings::load(Some(repo_root)).await;
+        if let Err(e) = ingest_incremental_sessions_for_repo(
+            repo_root,
+            &repo_root_str,
+            &settings,
+            &encryption_method,
+        )
+        .await
         {
             output::note(&format!("Deferred sync ingest issue: {}", e));
         }
     }
+    if let Some(counter) = progress {
+        counter.finish();
+    }
+    if let Err(e) = attribution::flush().await {
+        output::note(&format!("Attribution events stay queued: {e:#}"));
+    }
 
     deferred_sync::run_sync_command(deferred_sync::SyncRunOptions {
         repo,
@@ -4504,8 +6967,37 @@ fn pending_repo_roots_sorted_deduped(jobs: Vec<deferred_sync::PendingSyncRecord>
 mod tests {
     use super::*;
     use deferred_sync::PendingSyncRecord;
+    use serial_test::serial;
     use tempfile::TempDir;
 
+    struct EnvGuard {
+        key: &'static str,
+        original: Option<String>,
+    }
+
+    impl EnvGuard {
+        fn unset(key: &'static str) -> Self {
+            let original = std::env::var(key).ok();
+            unsafe { std::env::remove_var(key) };
+            Self { key, original }
+        }
+
+        fn set(key: &'static str, value: &std::path::Path) -> Self {
+            let original = std::env::var(key).ok();
+            unsafe { std::env::set_var(key, value) };
+            Self { key, original }
+        }
+    }
+
+    impl Drop for EnvGuard {
+        fn drop(&mut self) {
+            match &self.original {
+                Some(v) => unsafe { std::env::set_var(self.key, v) },
+                None => unsafe { std::env::remove_var(self.key) },
+            }
+        }
+    }
+
     async fn run_git(repo: &std::path::Path, args: &[&str]) -> String {
         let out = crate::git::run_git_output_at(Some(repo), args, &[])
             .await
@@ -4534,6 +7026,107 @@ mod tests {
         dir
     }
 
+    #[tokio::test]
+    async fn head_author_skipped_matches_configured_authors() {
+        let dir = init_repo().await;
+        assert!(
+            !head_author_skipped(
+                dir.path(),
+                &settings::Settings::load(Some(dir.path())).await
+            )
+            .await
+        );
+
+        run_git(
+            dir.path(),
+            &[
+                "config",
+                "--add",
+                settings::SKIP_AUTHORS_KEY,
+                "release-bot@example.com",
+            ],
+        )
+        .await;
+        assert!(
+            !head_author_skipped(
+                dir.path(),
+                &settings::Settings::load(Some(dir.path())).await
+            )
+            .await
+        );
+
+        run_git(
+            dir.path(),
+            &[
+                "config",
+                "--add",
+                settings::SKIP_AUTHORS_KEY,
+                "dependabot, Test@Example.com",
+            ],
+        )
+        .await;
+        assert!(
+            head_author_skipped(
+                dir.path(),
+                &settings::Settings::load(Some(dir.path())).await
+            )
+            .await
+        );
+    }
+
+    #[tokio::test]
+    async fn head_authored_by_user_compares_author_with_user_email() {
+        let dir = init_repo().await;
+        assert!(head_authored_by_user(dir.path()).await);
+
+        run_git(
+            dir.path(),
+            &[
+                "commit",
+                "--allow-empty",
+                "-q",
+                "-m",
+                "cherry-picked from a teammate",
+                "--author",
+                "Teammate <teammate@example.com>",
+            ],
+        )
+        .await;
+        assert!(!head_authored_by_user(dir.path()).await);
+
+        run_git(
+            dir.path(),
+            &["config", "user.email", "TEAMMATE@example.com"],
+        )
+        .await;
+        assert!(head_authored_by_user(dir.path()).await);
+    }
+
+    #[test]
+    fn author_is_skipped_matches_name_or_email() {
+        let entries = vec!["dependabot[bot]".to_string()];
+        assert!(author_is_skipped(
+            &entries,
+            "49699333+dependabot[bot]@users.noreply.github.com",
+            "dependabot[bot]"
+        ));
+        assert!(!author_is_skipped(&entries, "dev@example.com", "Dev"));
+        assert!(!author_is_skipped(&[" , ".to_string()], "", ""));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn hooks_are_noops_when_home_is_unset() {
+        let _guards = ["HOME", "USERPROFILE", "HOMEDRIVE", "HOMEPATH"].map(EnvGuard::unset);
+        assert!(!hook_home_available());
+        run_hook_post_commit(false, false)
+            .await
+            .expect("post-commit must exit 0 without HOME");
+        run_hook_pre_push("origin", "https://example.com/repo.git")
+            .await
+            .expect("pre-push must exit 0 without HOME");
+    }
+
     #[test]
     fn cli_parses_keys_setup() {
         let cli = Cli::parse_from(["cadence", "keys", "setup"]);
@@ -4559,7 +7152,22 @@ mod tests {
     #[test]
     fn cli_parses_login_command() {
         let cli = Cli::parse_from(["cadence", "login"]);
-        assert!(matches!(cli.command, Command::Login));
+        assert!(matches!(
+            cli.command,
+            Command::Login {
+                timeout: 120,
+                print_url: false
+            }
+        ));
+        let cli = Cli::parse_from(["cadence", "login", "--timeout", "600", "--print-url"]);
+        assert!(matches!(
+            cli.command,
+            Command::Login {
+                timeout: 600,
+                print_url: true
+            }
+        ));
+        assert!(Cli::try_parse_from(["cadence", "login", "--timeout", "0"]).is_err());
     }
 
     #[test]
@@ -4572,7 +7180,7 @@ mod tests {
     fn cli_parses_backfill_command() {
         let cli = Cli::parse_from(["cadence", "backfill", "--since", "30d"]);
         match cli.command {
-            Command::Backfill { since } => {
+            Command::Backfill { since, .. } => {
                 assert_eq!(since, "30d");
             }
             _ => panic!("expected Backfill command"),
@@ -4608,29 +7216,128 @@ mod tests {
     }
 
     #[test]
-    fn cli_parses_update_yes_long() {
-        let cli = Cli::parse_from(["cadence", "update", "--yes"]);
+    fn cli_parses_update_yes_long() {
+        let cli = Cli::parse_from(["cadence", "update", "--yes"]);
+        match cli.command {
+            Command::Update { check, yes } => {
+                assert!(!check);
+                assert!(yes);
+            }
+            _ => panic!("expected Update command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_update_yes_short() {
+        let cli = Cli::parse_from(["cadence", "update", "-y"]);
+        match cli.command {
+            Command::Update { check, yes } => {
+                assert!(!check);
+                assert!(yes);
+            }
+            _ => panic!("expected Update command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_global_quiet_and_verbose_flags() {
+        let cli = Cli::parse_from(["cadence", "status", "-q"]);
+        assert!(cli.quiet);
+        assert_eq!(
+            cli_verbosity(cli.verbose, cli.quiet),
+            output::Verbosity::Quiet
+        );
+
+        let cli = Cli::parse_from(["cadence", "-v", "status"]);
+        assert!(cli.verbose);
+        assert_eq!(
+            cli_verbosity(cli.verbose, cli.quiet),
+            output::Verbosity::Verbose
+        );
+
+        assert!(Cli::try_parse_from(["cadence", "status", "-q", "-v"]).is_err());
+    }
+
+    #[test]
+    fn cli_parses_backfill_agent_filter() {
+        let cli = Cli::parse_from(["cadence", "backfill", "--agent", "claude"]);
         match cli.command {
-            Command::Update { check, yes } => {
-                assert!(!check);
-                assert!(yes);
+            Command::Backfill {
+                since,
+                since_last,
+                agent,
+            } => {
+                assert_eq!(since, "30d");
+                assert!(!since_last);
+                assert_eq!(agent, Some(scanner::AgentType::Claude));
             }
-            _ => panic!("expected Update command"),
+            other => panic!("unexpected command: {other:?}"),
         }
+
+        let err = Cli::try_parse_from(["cadence", "backfill", "--agent", "gpt"])
+            .expect_err("unknown agent should be rejected");
+        assert!(err.to_string().contains("unknown agent 'gpt'"));
     }
 
     #[test]
-    fn cli_parses_update_yes_short() {
-        let cli = Cli::parse_from(["cadence", "update", "-y"]);
+    fn only_account_and_update_commands_run_without_git() {
+        let needs_git = |args: &[&str]| command_needs_git(&Cli::parse_from(args).command);
+        assert!(needs_git(&["cadence", "status"]));
+        assert!(needs_git(&["cadence", "backfill"]));
+        assert!(needs_git(&["cadence", "hook", "post-commit"]));
+        assert!(!needs_git(&["cadence", "login"]));
+        assert!(!needs_git(&["cadence", "update", "--check"]));
+        assert!(!needs_git(&["cadence", "hook", "auto-update"]));
+    }
+
+    #[test]
+    fn cli_parses_backfill_since_last() {
+        let cli = Cli::parse_from(["cadence", "backfill", "--since-last", "--since", "7d"]);
         match cli.command {
-            Command::Update { check, yes } => {
-                assert!(!check);
-                assert!(yes);
+            Command::Backfill {
+                since, since_last, ..
+            } => {
+                assert_eq!(since, "7d");
+                assert!(since_last);
             }
-            _ => panic!("expected Update command"),
+            other => panic!("unexpected command: {other:?}"),
         }
     }
 
+    #[test]
+    fn since_last_window_starts_at_previous_run() {
+        let now = 1_800_000_000;
+        assert_eq!(
+            since_last_window_secs(Some(now - 7_200), now, 30 * 86_400),
+            7_200 + SINCE_LAST_OVERLAP_SECS
+        );
+        assert_eq!(since_last_window_secs(None, now, 30 * 86_400), 30 * 86_400);
+        assert_eq!(
+            since_last_window_secs(Some(now + 60), now, 30 * 86_400),
+            SINCE_LAST_OVERLAP_SECS
+        );
+    }
+
+    #[test]
+    fn describe_window_picks_largest_unit() {
+        assert_eq!(describe_window(30 * 86_400), "last 30 days");
+        assert_eq!(describe_window(86_400), "last 1 day");
+        assert_eq!(describe_window(7_500), "last 2 hours");
+        assert_eq!(describe_window(20), "last 1 minute");
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn last_backfill_time_round_trips_through_state_dir() {
+        let home = TempDir::new().unwrap();
+        let _home = EnvGuard::set("HOME", home.path());
+        let _xdg = EnvGuard::unset("XDG_DATA_HOME");
+
+        assert_eq!(load_last_backfill_epoch().await, None);
+        record_backfill_success(1_800_000_000).await.unwrap();
+        assert_eq!(load_last_backfill_epoch().await, Some(1_800_000_000));
+    }
+
     #[test]
     fn cli_parses_update_check_and_yes() {
         let cli = Cli::parse_from(["cadence", "update", "--check", "--yes"]);
@@ -4700,8 +7407,9 @@ mod tests {
     fn cli_parses_doctor() {
         let cli = Cli::parse_from(["cadence", "doctor"]);
         match cli.command {
-            Command::Doctor { repair } => {
+            Command::Doctor { repair, json } => {
                 assert!(!repair);
+                assert!(!json);
             }
             _ => panic!("expected Doctor command"),
         }
@@ -4711,13 +7419,56 @@ mod tests {
     fn cli_parses_doctor_repair() {
         let cli = Cli::parse_from(["cadence", "doctor", "--repair"]);
         match cli.command {
-            Command::Doctor { repair } => {
+            Command::Doctor { repair, json } => {
                 assert!(repair);
+                assert!(!json);
             }
             _ => panic!("expected Doctor command"),
         }
     }
 
+    #[test]
+    fn cli_parses_doctor_json() {
+        let cli = Cli::parse_from(["cadence", "doctor", "--json"]);
+        assert!(matches!(
+            cli.command,
+            Command::Doctor {
+                repair: false,
+                json: true
+            }
+        ));
+    }
+
+    #[test]
+    fn doctor_report_is_ok_only_without_failures() {
+        let mut report = DoctorReport::default();
+        report.record("global_hooks", CheckStatus::Ok, "~/.git-hooks");
+        report.record(
+            "repo_hooks",
+            CheckStatus::Skipped,
+            "not in a git repository",
+        );
+        assert_eq!(report.issues(), 0);
+        assert!(report.ok);
+        report.record("auto_update", CheckStatus::Fail, "failing repeatedly");
+        assert_eq!(report.issues(), 1);
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::json!({
+                "ok": false,
+                "checks": [
+                    { "name": "global_hooks", "status": "ok", "detail": "~/.git-hooks" },
+                    {
+                        "name": "repo_hooks",
+                        "status": "skipped",
+                        "detail": "not in a git repository",
+                    },
+                    { "name": "auto_update", "status": "fail", "detail": "failing repeatedly" },
+                ],
+            })
+        );
+    }
+
     #[test]
     fn cli_parses_auto_update_default_status() {
         let cli = Cli::parse_from(["cadence", "auto-update"]);
@@ -4730,53 +7481,712 @@ mod tests {
     }
 
     #[test]
-    fn cli_parses_auto_update_disable() {
-        let cli = Cli::parse_from(["cadence", "auto-update", "disable"]);
-        match cli.command {
-            Command::AutoUpdate { command } => {
-                assert!(matches!(command, Some(AutoUpdateCommand::Disable)));
+    fn cli_parses_auto_update_disable() {
+        let cli = Cli::parse_from(["cadence", "auto-update", "disable"]);
+        match cli.command {
+            Command::AutoUpdate { command } => {
+                assert!(matches!(command, Some(AutoUpdateCommand::Disable)));
+            }
+            _ => panic!("expected AutoUpdate command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_auto_update_uninstall() {
+        let cli = Cli::parse_from(["cadence", "auto-update", "uninstall"]);
+        match cli.command {
+            Command::AutoUpdate { command } => {
+                assert!(matches!(command, Some(AutoUpdateCommand::Uninstall)));
+            }
+            _ => panic!("expected AutoUpdate command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_global_repo_option() {
+        let cli = Cli::parse_from(["cadence", "status", "--repo", "/work/checkout"]);
+        assert!(matches!(cli.command, Command::Status));
+        assert_eq!(cli.repo, Some(PathBuf::from("/work/checkout")));
+
+        // The background sync worker passes its repository the same way.
+        let cli = Cli::parse_from([
+            "cadence",
+            "hook",
+            "deferred-sync",
+            "--background",
+            "--repo",
+            "/work/checkout",
+        ]);
+        assert_eq!(cli.repo, Some(PathBuf::from("/work/checkout")));
+    }
+
+    #[test]
+    fn global_commands_ignore_the_repo_option() {
+        for args in [
+            ["cadence", "login", "--repo", "/not/a/repo"],
+            ["cadence", "logout", "--repo", "/not/a/repo"],
+        ] {
+            let cli = Cli::parse_from(args);
+            assert!(!command_uses_repo(&cli.command));
+        }
+        let cli = Cli::parse_from(["cadence", "status", "--repo", "/work/checkout"]);
+        assert!(command_uses_repo(&cli.command));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn status_reports_the_repo_option_without_changing_directory() {
+        let repo = init_repo().await;
+        let repo_root = resolve_repo(repo.path()).await.expect("repo root");
+        let cwd = std::env::current_dir().unwrap();
+
+        let mut out = Vec::new();
+        run_status_inner(&mut out, Some(&repo_root))
+            .await
+            .expect("status");
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains(&format!("Repo: {}", repo_root.to_string_lossy())));
+        assert_eq!(std::env::current_dir().unwrap(), cwd);
+    }
+
+    #[tokio::test]
+    async fn resolve_repo_rejects_non_repositories() {
+        let tmp = TempDir::new().unwrap();
+        let err = resolve_repo(tmp.path()).await.unwrap_err();
+        assert!(format!("{err:#}").contains("is not a git repository"));
+    }
+
+    #[test]
+    fn cli_parses_hook_deferred_sync_defaults() {
+        let cli = Cli::parse_from(["cadence", "hook", "deferred-sync"]);
+        assert!(cli.repo.is_none());
+        match cli.command {
+            Command::Hook { hook_command } => match hook_command {
+                HookCommand::DeferredSync {
+                    remote,
+                    all_pending,
+                    background,
+                    max_items,
+                    time_budget_ms,
+                } => {
+                    assert!(remote.is_none());
+                    assert!(!all_pending);
+                    assert!(!background);
+                    assert_eq!(max_items, 4);
+                    assert_eq!(time_budget_ms, 8000);
+                }
+                _ => panic!("expected DeferredSync hook command"),
+            },
+            _ => panic!("expected Hook command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_clean_flags() {
+        let cli = Cli::parse_from(["cadence", "clean", "--logs", "--token", "-y"]);
+        assert!(matches!(
+            cli.command,
+            Command::Clean {
+                pending: false,
+                logs: true,
+                token: true,
+                all: false,
+                yes: true
+            }
+        ));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn clean_pending_removes_every_pending_record() {
+        let home = TempDir::new().unwrap();
+        let _home = EnvGuard::set("HOME", home.path());
+        let _xdg = EnvGuard::unset("XDG_DATA_HOME");
+        let state_dir = state::state_dir_path().unwrap();
+        let legacy_dir = state::legacy_state_dir_path().unwrap();
+        for dir in [
+            state_dir.join("pending-sync"),
+            state_dir.join(sync_pending::PENDING_INGEST_DIR),
+            state_dir.join("sync-cursors"),
+            legacy_dir.join("pending-sync"),
+        ] {
+            tokio::fs::create_dir_all(&dir).await.unwrap();
+            tokio::fs::write(dir.join("record.json"), "{}")
+                .await
+                .unwrap();
+        }
+        for name in [
+            "attribution-queue.jsonl",
+            "attribution-queue.123.456.sending",
+            "attribution-flush.stamp",
+        ] {
+            tokio::fs::write(state_dir.join(name), "").await.unwrap();
+        }
+
+        run_clean(true, false, false, true).await.expect("clean");
+
+        assert!(!state_dir.join("pending-sync/record.json").exists());
+        assert!(
+            !state_dir
+                .join(sync_pending::PENDING_INGEST_DIR)
+                .join("record.json")
+                .exists()
+        );
+        assert!(!legacy_dir.join("pending-sync/record.json").exists());
+        let mut left = Vec::new();
+        let mut entries = tokio::fs::read_dir(&state_dir).await.unwrap();
+        while let Some(entry) = entries.next_entry().await.unwrap() {
+            left.push(entry.file_name().to_string_lossy().to_string());
+        }
+        left.sort();
+        assert_eq!(
+            left,
+            vec![
+                "pending-ingest".to_string(),
+                "pending-sync".to_string(),
+                "sync-cursors".to_string(),
+            ]
+        );
+        assert!(state_dir.join("sync-cursors/record.json").exists());
+    }
+
+    #[tokio::test]
+    async fn remove_dir_entries_keeps_unselected_files() {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path();
+        tokio::fs::write(dir.join("config.toml"), "").await.unwrap();
+        tokio::fs::write(dir.join("backfill.2026-01-01T00-00-00Z.log"), "")
+            .await
+            .unwrap();
+        tokio::fs::write(dir.join(backfill_log::HOOK_METRICS_FILE), "")
+            .await
+            .unwrap();
+        tokio::fs::write(dir.join(backfill_log::HOOK_METRICS_OLD_FILE), "")
+            .await
+            .unwrap();
+        tokio::fs::create_dir_all(dir.join("sync")).await.unwrap();
+
+        let removed = remove_dir_entries(dir, |name| !is_backfill_log(name))
+            .await
+            .unwrap();
+        assert_eq!(removed, 3);
+        assert!(dir.join("config.toml").exists());
+        assert!(dir.join("sync").exists());
+
+        assert_eq!(remove_dir_entries(dir, |_| false).await.unwrap(), 2);
+        assert_eq!(
+            remove_dir_entries(&dir.join("missing"), |_| false)
+                .await
+                .unwrap(),
+            0
+        );
+    }
+
+    #[test]
+    fn cli_parses_install_force() {
+        let cli = Cli::parse_from(["cadence", "install", "--force"]);
+        assert!(matches!(
+            cli.command,
+            Command::Install {
+                org: None,
+                force: true
+            }
+        ));
+    }
+
+    #[test]
+    fn hook_shim_exe_reads_the_exec_line() {
+        assert_eq!(
+            hook_shim_exe(&post_commit_hook_content("cadence")).as_deref(),
+            Some("cadence")
+        );
+        assert_eq!(
+            hook_shim_exe(&pre_push_hook_content("/opt/cadence/bin/cadence")).as_deref(),
+            Some("/opt/cadence/bin/cadence")
+        );
+        assert_eq!(
+            hook_shim_exe(&post_commit_hook_content("\"/Apps/My Tools/cadence\"")).as_deref(),
+            Some("/Apps/My Tools/cadence")
+        );
+        assert_eq!(hook_shim_exe("#!/bin/sh\necho hi\n"), None);
+
+        // Paths written by `install --force` round-trip through the quoting.
+        for path in [
+            "/opt/cadence/bin/cadence",
+            "/Apps/My Tools/cadence",
+            "/home/o'brien/bin/cadence",
+            "/tmp/$HOME `x`/cadence",
+        ] {
+            let content = prepare_commit_msg_hook_content(&shell_quote(path));
+            assert_eq!(hook_shim_exe(&content).as_deref(), Some(path), "{content}");
+        }
+        assert_eq!(
+            shell_quote("/home/o'brien/cadence"),
+            r"'/home/o'\''brien/cadence'"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn quoted_shim_runs_a_binary_whose_path_needs_quoting() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("o'brien's $HOME tools");
+        std::fs::create_dir_all(&dir).unwrap();
+        let exe = dir.join("cadence");
+        let marker = tmp.path().join("ran");
+        std::fs::write(
+            &exe,
+            format!("#!/bin/sh\necho \"$@\" > '{}'\n", marker.display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&exe, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let shim = tmp.path().join("post-commit");
+        std::fs::write(
+            &shim,
+            post_commit_hook_content(&shell_quote(&exe.display().to_string())),
+        )
+        .unwrap();
+
+        let status = std::process::Command::new("sh")
+            .arg(&shim)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(
+            std::fs::read_to_string(&marker).unwrap(),
+            "hook post-commit\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn stale_hook_binary_is_detected_in_every_shim() {
+        let tmp = TempDir::new().unwrap();
+        let hooks_dir = tmp.path();
+        let gone = shell_quote(
+            &tmp.path()
+                .join("old install")
+                .join("cadence")
+                .display()
+                .to_string(),
+        );
+        let current = shell_quote(&std::env::current_exe().unwrap().display().to_string());
+        let write = |hook_name: &str, content: String| {
+            std::fs::write(hooks_dir.join(hook_name), content).unwrap();
+        };
+        write("post-commit", post_commit_hook_content(&current));
+        write("pre-push", pre_push_hook_content(&current));
+        write("prepare-commit-msg", prepare_commit_msg_hook_content(&gone));
+        let gone_path = tmp.path().join("old install").join("cadence");
+        assert_eq!(
+            stale_hook_exe(hooks_dir).await,
+            Some(("prepare-commit-msg", gone_path.display().to_string()))
+        );
+
+        write(
+            "prepare-commit-msg",
+            prepare_commit_msg_hook_content(&current),
+        );
+        assert_eq!(stale_hook_exe(hooks_dir).await, None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn prepare_commit_msg_shim_runs_the_saved_hook_first() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = TempDir::new().unwrap();
+        let shim = tmp.path().join("prepare-commit-msg");
+        let backup = tmp.path().join("prepare-commit-msg.pre-cadence");
+        let msg = tmp.path().join("COMMIT_EDITMSG");
+        std::fs::write(&shim, prepare_commit_msg_hook_content("true")).unwrap();
+        std::fs::write(&msg, "Fix parser\n").unwrap();
+        let run = || {
+            std::process::Command::new("sh")
+                .arg(&shim)
+                .arg(&msg)
+                .status()
+                .unwrap()
+        };
+
+        // Without a saved hook the shim only runs Cadence.
+        assert!(run().success());
+
+        std::fs::write(&backup, "#!/bin/sh\necho 'Ticket: ABC-1' >> \"$1\"\n").unwrap();
+        std::fs::set_permissions(&backup, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(run().success());
+        assert_eq!(
+            std::fs::read_to_string(&msg).unwrap(),
+            "Fix parser\nTicket: ABC-1\n"
+        );
+
+        // A saved hook that rejects the commit still does.
+        std::fs::write(&backup, "#!/bin/sh\nexit 3\n").unwrap();
+        assert_eq!(run().code(), Some(3));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn prepare_commit_msg_shim_starts_cadence_only_with_inject_trailer() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = TempDir::new().unwrap();
+        let exe = tmp.path().join("fake-cadence");
+        let marker = tmp.path().join("started");
+        std::fs::write(
+            &exe,
+            format!(
+                "#!/bin/sh\ntouch {}\n",
+                shell_quote(&marker.to_string_lossy())
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&exe, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let shim = tmp.path().join("prepare-commit-msg");
+        std::fs::write(
+            &shim,
+            prepare_commit_msg_hook_content(&shell_quote(&exe.to_string_lossy())),
+        )
+        .unwrap();
+        let env_key = settings::env_var_name(trailer::INJECT_TRAILER_KEY);
+        let run = |inject: Option<&str>| {
+            let mut cmd = std::process::Command::new("sh");
+            cmd.arg(&shim)
+                .arg("COMMIT_EDITMSG")
+                .current_dir(tmp.path())
+                .env("HOME", tmp.path())
+                .env("GIT_CONFIG_NOSYSTEM", "1")
+                .env_remove(&env_key);
+            if let Some(value) = inject {
+                cmd.env(&env_key, value);
+            }
+            assert!(cmd.status().unwrap().success());
+        };
+
+        run(None);
+        assert!(!marker.exists());
+        run(Some("false"));
+        assert!(!marker.exists());
+        run(Some("yes"));
+        assert!(marker.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn post_commit_and_pre_push_shims_run_the_saved_hook_first() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = TempDir::new().unwrap();
+        let log = tmp.path().join("ran");
+        for (hook_name, content) in [
+            ("post-commit", post_commit_hook_content("true")),
+            ("pre-push", pre_push_hook_content("true")),
+        ] {
+            let shim = tmp.path().join(hook_name);
+            let backup = tmp.path().join(format!("{hook_name}.pre-cadence"));
+            std::fs::write(&shim, content).unwrap();
+            std::fs::write(
+                &backup,
+                format!(
+                    "#!/bin/sh\necho {hook_name} >> {}\nexit 3\n",
+                    shell_quote(&log.to_string_lossy())
+                ),
+            )
+            .unwrap();
+            std::fs::set_permissions(&backup, std::fs::Permissions::from_mode(0o755)).unwrap();
+            let status = std::process::Command::new("sh")
+                .arg(&shim)
+                .args(["origin", "https://example.com/repo.git"])
+                .status()
+                .unwrap();
+            // git ignores the post-commit status, so Cadence still runs;
+            // a failing saved pre-push hook stops the push.
+            match hook_name {
+                "post-commit" => assert!(status.success()),
+                _ => assert_eq!(status.code(), Some(3)),
+            }
+        }
+        assert_eq!(
+            std::fs::read_to_string(&log).unwrap(),
+            "post-commit\npre-push\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn installed_hooks_include_prepare_commit_msg() {
+        let tmp = TempDir::new().unwrap();
+        let hooks_dir = tmp.path();
+        for (name, content) in [
+            ("post-commit", post_commit_hook_content("cadence")),
+            ("pre-push", pre_push_hook_content("cadence")),
+        ] {
+            std::fs::write(hooks_dir.join(name), content).unwrap();
+        }
+        let installed = cadence_hooks_installed(hooks_dir).await;
+        assert!(!installed.all());
+        assert_eq!(
+            installed.summary(),
+            "post-commit: yes, pre-push: yes, prepare-commit-msg: no"
+        );
+
+        std::fs::write(
+            hooks_dir.join("prepare-commit-msg"),
+            prepare_commit_msg_hook_content("cadence"),
+        )
+        .unwrap();
+        assert!(cadence_hooks_installed(hooks_dir).await.all());
+    }
+
+    #[test]
+    fn cli_parses_hook_post_commit_report_json() {
+        let cli = Cli::parse_from(["cadence", "hook", "post-commit", "--report-json"]);
+        match cli.command {
+            Command::Hook { hook_command } => {
+                assert!(matches!(
+                    hook_command,
+                    HookCommand::PostCommit {
+                        report_json: true,
+                        explain: false
+                    }
+                ));
+            }
+            _ => panic!("expected Hook command"),
+        }
+    }
+
+    #[test]
+    fn cli_parses_hook_post_commit_explain() {
+        let cli = Cli::parse_from(["cadence", "hook", "post-commit", "--explain"]);
+        assert!(matches!(
+            cli.command,
+            Command::Hook {
+                hook_command: HookCommand::PostCommit {
+                    report_json: false,
+                    explain: true
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn audit_flags_index_entries_pointing_at_another_sessions_object() {
+        let record: note::SessionRecord = serde_json::from_value(serde_json::json!({
+            "session_uid": "uid-of-the-stored-session",
+            "agent": "codex",
+            "session_id": "s-1",
+            "repo_root": "/repo",
+            "branch_key": "origin/main",
+            "committer_key_hash": "c",
+            "content_sha256": "h",
+            "ingested_at": "2026-10-16T00:00:00Z",
+            "cli_version": "0.0.0",
+        }))
+        .unwrap();
+        let entry = |uid: &str, agent: &str| note::IndexEntry {
+            session_uid: uid.to_string(),
+            session_blob_sha: "0123456789abcdef0123".to_string(),
+            session_start: None,
+            agent: agent.to_string(),
+            ingested_at: "2026-10-16T00:00:00Z".to_string(),
+        };
+
+        assert_eq!(
+            index_entry_mismatch(&entry("uid-of-the-stored-session", "codex"), &record),
+            None
+        );
+        assert_eq!(
+            index_entry_mismatch(&entry("uid-copied-from-elsewhere", "codex"), &record).as_deref(),
+            Some("object 0123456789ab belongs to session uid-of-the-s")
+        );
+        assert_eq!(
+            index_entry_mismatch(&entry("uid-of-the-stored-session", "claude"), &record).as_deref(),
+            Some("indexed as claude but the object records codex")
+        );
+    }
+
+    #[test]
+    fn explain_names_each_decision_and_the_best_match() {
+        let candidate = |id: &str, cwd_match, updated_at| matcher::Candidate {
+            agent: scanner::AgentType::Codex,
+            session_id: Some(id.to_string()),
+            cwd: Some("/repo".to_string()),
+            updated_at: Some(updated_at),
+            source_label: format!("rollout-{id}.jsonl"),
+            evidence: matcher::MatchEvidence {
+                cwd_match,
+                has_session_id: true,
+                session_id_inferred: false,
+                mtime_delta_secs: Some(1_000 - updated_at),
+            },
+        };
+        let stored_session = |id: &str| StoredSession {
+            agent: "codex".to_string(),
+            session_id: id.to_string(),
+        };
+        let candidates = vec![
+            candidate("late", matcher::CwdMatch::Root, 1_200),
+            candidate("sub", matcher::CwdMatch::Subdirectory, 990),
+            candidate("root", matcher::CwdMatch::Root, 900),
+            candidate("other", matcher::CwdMatch::OtherRepository, 995),
+        ];
+        let window = MatchWindow {
+            lookback_secs: 1_800,
+            not_after: Some(1_100),
+        };
+        let stored = vec![stored_session("sub"), stored_session("root")];
+        let decisions: Vec<String> = candidates
+            .iter()
+            .map(|c| explain_decision(c, window, &stored, matcher::Confidence::Low))
+            .collect();
+        assert_eq!(
+            decisions,
+            [
+                "updated after the window",
+                "stored",
+                "stored",
+                "working directory is in another repository"
+            ]
+        );
+        assert_eq!(
+            explain_decision(&candidates[1], window, &[], matcher::Confidence::High),
+            "below ai.cadence.minConfidence"
+        );
+        assert_eq!(
+            explain_winner(&candidates, &stored, 1_000),
+            Some((stored_session("root"), "high"))
+        );
+        // A stored session the window never saw was linked by subject.
+        assert_eq!(
+            explain_winner(&candidates, &[stored_session("stashed")], 1_000),
+            Some((stored_session("stashed"), "low (commit subject only)"))
+        );
+        assert_eq!(explain_winner(&candidates, &[], 1_000), None);
+        assert_eq!(format_mtime_delta(90), "1m before");
+        assert_eq!(format_mtime_delta(-7_300), "2h after");
+    }
+
+    #[test]
+    fn sweep_skips_clean_and_check_commands() {
+        let sweeps = |args: &[&str]| command_sweeps_pending_sync(&Cli::parse_from(args).command);
+        assert!(sweeps(&["cadence", "status"]));
+        assert!(sweeps(&["cadence", "doctor"]));
+        assert!(!sweeps(&["cadence", "clean", "--pending"]));
+        assert!(!sweeps(&["cadence", "bench"]));
+        assert!(!sweeps(&["cadence", "selftest"]));
+        assert!(!sweeps(&["cadence", "doctor", "--json"]));
+        assert!(!sweeps(&["cadence", "hook", "post-commit"]));
+    }
+
+    #[test]
+    fn cli_parses_bench_iterations() {
+        let cli = Cli::parse_from(["cadence", "bench"]);
+        assert!(matches!(cli.command, Command::Bench { iterations: 10 }));
+        let cli = Cli::parse_from(["cadence", "bench", "--iterations", "3"]);
+        assert!(matches!(cli.command, Command::Bench { iterations: 3 }));
+        assert!(Cli::try_parse_from(["cadence", "bench", "--iterations", "0"]).is_err());
+    }
+
+    #[test]
+    fn latency_summary_uses_nearest_rank() {
+        let ms = Duration::from_millis;
+        let mut samples: Vec<Duration> = (1..=20).rev().map(ms).collect();
+        assert_eq!(
+            LatencySummary::of(&mut samples),
+            LatencySummary {
+                min: ms(1),
+                median: ms(10),
+                p95: ms(19),
             }
-            _ => panic!("expected AutoUpdate command"),
-        }
+        );
+        assert_eq!(
+            LatencySummary::of(&mut [ms(7)]),
+            LatencySummary {
+                min: ms(7),
+                median: ms(7),
+                p95: ms(7),
+            }
+        );
+        assert_eq!(format_latency(Duration::from_micros(1_250)), "1.2ms");
     }
 
     #[test]
-    fn cli_parses_auto_update_uninstall() {
-        let cli = Cli::parse_from(["cadence", "auto-update", "uninstall"]);
-        match cli.command {
-            Command::AutoUpdate { command } => {
-                assert!(matches!(command, Some(AutoUpdateCommand::Uninstall)));
-            }
-            _ => panic!("expected AutoUpdate command"),
-        }
+    fn cli_parses_hidden_selftest() {
+        let cli = Cli::parse_from(["cadence", "selftest"]);
+        assert!(matches!(cli.command, Command::Selftest));
     }
 
     #[test]
-    fn cli_parses_hook_deferred_sync_defaults() {
-        let cli = Cli::parse_from(["cadence", "hook", "deferred-sync"]);
+    fn cli_parses_hook_prepare_commit_msg() {
+        let cli = Cli::parse_from([
+            "cadence",
+            "hook",
+            "prepare-commit-msg",
+            ".git/COMMIT_EDITMSG",
+            "message",
+        ]);
         match cli.command {
             Command::Hook { hook_command } => match hook_command {
-                HookCommand::DeferredSync {
-                    repo,
-                    remote,
-                    all_pending,
-                    background,
-                    max_items,
-                    time_budget_ms,
+                HookCommand::PrepareCommitMsg {
+                    msg_file,
+                    source,
+                    sha,
                 } => {
-                    assert!(repo.is_none());
-                    assert!(remote.is_none());
-                    assert!(!all_pending);
-                    assert!(!background);
-                    assert_eq!(max_items, 4);
-                    assert_eq!(time_budget_ms, 8000);
+                    assert_eq!(msg_file, PathBuf::from(".git/COMMIT_EDITMSG"));
+                    assert_eq!(source.as_deref(), Some("message"));
+                    assert!(sha.is_none());
                 }
-                _ => panic!("expected DeferredSync hook command"),
+                _ => panic!("expected PrepareCommitMsg hook command"),
             },
             _ => panic!("expected Hook command"),
         }
     }
 
+    #[test]
+    fn post_commit_report_is_built_from_the_outcome() {
+        let stored = |agent: &str, id: &str| StoredSession {
+            agent: agent.to_string(),
+            session_id: id.to_string(),
+        };
+        let report = PostCommitReport::from(PostCommitOutcome::Stored(vec![
+            stored("codex", "s1"),
+            stored("claude-code", "s2"),
+            stored("codex", "s3"),
+        ]));
+        assert!(report.stored);
+        assert_eq!(report.sessions, 3);
+        assert_eq!(report.agents, vec!["claude-code", "codex"]);
+        assert!(!report.pending);
+
+        let report = PostCommitReport::from(PostCommitOutcome::Stored(Vec::new()));
+        assert!(!report.stored);
+
+        let report = PostCommitReport::from(PostCommitOutcome::Deferred {
+            budget: Duration::from_millis(2_000),
+        });
+        assert!(report.pending);
+        assert_eq!(report.sessions, 0);
+
+        let report = PostCommitReport::from(PostCommitOutcome::Skipped(SkipReason::Author));
+        assert_eq!(
+            report.skipped.as_deref(),
+            Some("commit author is listed in ai.cadence.skipAuthors")
+        );
+    }
+
+    #[test]
+    fn post_commit_report_serializes_as_one_line() {
+        let report = PostCommitReport {
+            commit: Some("abc123".to_string()),
+            stored: true,
+            sessions: 2,
+            agents: vec!["claude-code".to_string()],
+            ..Default::default()
+        };
+        let line = serde_json::to_string(&report).unwrap();
+        assert!(!line.contains('\n'));
+        assert_eq!(
+            line,
+            r#"{"commit":"abc123","stored":true,"sessions":2,"agents":["claude-code"],"pending":false,"skipped":null,"error":null}"#
+        );
+    }
+
     #[test]
     fn cli_parses_hidden_hook_auto_update() {
         let cli = Cli::parse_from(["cadence", "hook", "auto-update"]);
@@ -4800,17 +8210,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cli_parses_sessions_detect() {
+        let cli = Cli::parse_from(["cadence", "sessions", "detect", "--since", "2d"]);
+        match cli.command {
+            Command::Sessions {
+                command: Some(SessionsCommand::Detect { since }),
+                ..
+            } => assert_eq!(since, "2d"),
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
     #[test]
     fn install_auto_update_ftue_state_matches_config() {
-        let mut enabled = config::CliConfig::default();
-        enabled.auto_update = Some(true);
+        let enabled = config::CliConfig {
+            auto_update: Some(true),
+            ..Default::default()
+        };
         assert_eq!(
             install_auto_update_ftue_state(&enabled),
             InstallAutoUpdateFtueState::Disclosure
         );
 
-        let mut disabled = config::CliConfig::default();
-        disabled.auto_update = Some(false);
+        let disabled = config::CliConfig {
+            auto_update: Some(false),
+            ..Default::default()
+        };
         assert_eq!(
             install_auto_update_ftue_state(&disabled),
             InstallAutoUpdateFtueState::Skip
@@ -4839,8 +8265,10 @@ mod tests {
     async fn install_auto_update_disclosure_skips_prompt_when_enabled() {
         let dir = TempDir::new().expect("tempdir");
         let config_path = dir.path().join("config.toml");
-        let mut cfg = config::CliConfig::default();
-        cfg.auto_update = Some(true);
+        let cfg = config::CliConfig {
+            auto_update: Some(true),
+            ..Default::default()
+        };
 
         run_install_auto_update_prompt_inner(&cfg, &config_path, true).await;
 
@@ -4851,8 +8279,10 @@ mod tests {
     async fn install_auto_update_respects_explicit_disable() {
         let dir = TempDir::new().expect("tempdir");
         let config_path = dir.path().join("config.toml");
-        let mut cfg = config::CliConfig::default();
-        cfg.auto_update = Some(false);
+        let cfg = config::CliConfig {
+            auto_update: Some(false),
+            ..Default::default()
+        };
 
         run_install_auto_update_prompt_inner(&cfg, &config_path, true).await;
 
@@ -4997,7 +8427,82 @@ mod tests {
 
     #[test]
     fn match_window_defaults_are_stable() {
-        assert_eq!(POST_COMMIT_MATCH_WINDOW_SECS, 1_800);
+        assert_eq!(settings::POST_COMMIT_MATCH_WINDOW_SECS, 1_800);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn post_commit_window_follows_the_time_source() {
+        let repo = init_repo().await;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        // A rebased commit: authored an hour ago, committed just now.
+        let authored = format!("--date=@{} +0000", now - 3_600);
+        run_git(
+            repo.path(),
+            &["commit", "--amend", "--no-edit", authored.as_str()],
+        )
+        .await;
+
+        let settings = settings::Settings::load(Some(repo.path())).await;
+        let window = MatchWindow::around(
+            commit_anchor(repo.path(), &settings, now).await,
+            &settings,
+            now,
+        );
+        assert!(
+            (settings::POST_COMMIT_MATCH_WINDOW_SECS..settings::POST_COMMIT_MATCH_WINDOW_SECS + 5)
+                .contains(&window.lookback_secs)
+        );
+        assert_eq!(window.not_after, None);
+
+        run_git(
+            repo.path(),
+            &["config", settings::TIME_SOURCE_KEY, "author"],
+        )
+        .await;
+        let settings = settings::Settings::load(Some(repo.path())).await;
+        let window = MatchWindow::around(
+            commit_anchor(repo.path(), &settings, now).await,
+            &settings,
+            now,
+        );
+        assert_eq!(
+            window.lookback_secs,
+            3_600 + settings::POST_COMMIT_MATCH_WINDOW_SECS
+        );
+
+        run_git(repo.path(), &["config", settings::WINDOW_BEFORE_KEY, "600"]).await;
+        run_git(repo.path(), &["config", settings::WINDOW_AFTER_KEY, "120"]).await;
+        let settings = settings::Settings::load(Some(repo.path())).await;
+        assert_eq!(
+            MatchWindow::around(
+                commit_anchor(repo.path(), &settings, now).await,
+                &settings,
+                now
+            ),
+            MatchWindow {
+                lookback_secs: 3_600 + 600,
+                not_after: Some(now - 3_600 + 120),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn hook_join_error_message_includes_panic_text() {
+        let err = tokio::spawn(async { panic!("index out of range: {}", 7) })
+            .await
+            .expect_err("task should panic");
+        assert_eq!(
+            hook_join_error_message(err),
+            "Hook panicked: index out of range: 7 (please report this issue)"
+        );
+
+        let err = tokio::spawn(async { std::panic::panic_any(42u8) })
+            .await
+            .expect_err("task should panic");
+        assert!(hook_join_error_message(err).contains("unknown panic payload"));
     }
 
     #[test]
@@ -5023,6 +8528,7 @@ mod tests {
                 attempt_count: 0,
                 next_attempt_at_epoch: 0,
                 last_error: None,
+                reason: String::new(),
             },
             PendingSyncRecord {
                 repo_root: "/tmp/a-repo".to_string(),
@@ -5032,6 +8538,7 @@ mod tests {
                 attempt_count: 0,
                 next_attempt_at_epoch: 0,
                 last_error: None,
+                reason: String::new(),
             },
             PendingSyncRecord {
                 repo_root: "/tmp/z-repo".to_string(),
@@ -5041,86 +8548,902 @@ mod tests {
                 attempt_count: 0,
                 next_attempt_at_epoch: 0,
                 last_error: None,
+                reason: String::new(),
             },
         ];
         let roots = pending_repo_roots_sorted_deduped(jobs);
         assert_eq!(
-            roots,
-            vec![PathBuf::from("/tmp/a-repo"), PathBuf::from("/tmp/z-repo")]
+            roots,
+            vec![PathBuf::from("/tmp/a-repo"), PathBuf::from("/tmp/z-repo")]
+        );
+    }
+
+    #[test]
+    fn cursor_advances_for_indexed_logs() {
+        let updated =
+            advance_cursor_for_disposition(100, Some(150), IncrementalLogDisposition::Indexed);
+        assert_eq!(updated, 150);
+    }
+
+    #[test]
+    fn cursor_advances_for_permanent_skips() {
+        let updated = advance_cursor_for_disposition(
+            100,
+            Some(140),
+            IncrementalLogDisposition::SkippedPermanent,
+        );
+        assert_eq!(updated, 140);
+    }
+
+    #[test]
+    fn cursor_does_not_advance_for_retriable_errors() {
+        let updated = advance_cursor_for_disposition(
+            100,
+            Some(180),
+            IncrementalLogDisposition::ErrorRetriable,
+        );
+        assert_eq!(updated, 100);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn ingest_session_writes_data_and_indexes() {
+        let repo = init_repo().await;
+        run_git(
+            repo.path(),
+            &[
+                "remote",
+                "add",
+                "origin",
+                "git@github.com:example-org/example-repo.git",
+            ],
+        )
+        .await;
+
+        let session_log = include_str!("../tests/fixtures/backfill/session_no_ranked.jsonl");
+        let info = ingest_session_from_log(
+            &scanner::AgentType::Claude,
+            "aaa111",
+            false,
+            false,
+            &repo.path().to_string_lossy(),
+            session_log,
+            None,
+            &EncryptionMethod::None,
+            Some(1_707_526_800),
+            Some(repo.path()),
+            &settings::Settings::load(Some(repo.path())).await,
+            None,
+        )
+        .await
+        .expect("ingest");
+
+        assert_eq!(info.blob_sha.len(), 40);
+        assert!(
+            git::ref_exists_at(Some(repo.path()), git::SESSION_DATA_REF)
+                .await
+                .expect("data ref exists")
+        );
+        assert!(
+            git::ref_exists_at(Some(repo.path()), git::SESSION_INDEX_BRANCH_REF)
+                .await
+                .expect("branch index ref exists")
+        );
+        assert!(
+            git::ref_exists_at(Some(repo.path()), git::SESSION_INDEX_COMMITTER_REF)
+                .await
+                .expect("committer index ref exists")
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn reference_body_stores_path_and_resolves_from_disk() {
+        let repo = init_repo().await;
+        run_git(
+            repo.path(),
+            &["config", note::SESSION_BODY_KEY, "reference"],
+        )
+        .await;
+        let logs = TempDir::new().expect("logs");
+        let log_path = logs.path().join("session.jsonl");
+        let session_log = include_str!("../tests/fixtures/backfill/session_no_ranked.jsonl");
+        tokio::fs::write(&log_path, session_log).await.unwrap();
+
+        let info = ingest_session_from_log(
+            &scanner::AgentType::Claude,
+            "ref-1",
+            false,
+            false,
+            &repo.path().to_string_lossy(),
+            session_log,
+            Some(&log_path),
+            &EncryptionMethod::None,
+            Some(1_707_526_800),
+            Some(repo.path()),
+            &settings::Settings::load(Some(repo.path())).await,
+            None,
+        )
+        .await
+        .expect("ingest");
+
+        let blob = git::read_blob_at(Some(repo.path()), &info.blob_sha)
+            .await
+            .expect("read blob");
+        let decoded = load_decrypted_session_blob(&blob).await.expect("decode");
+        let envelope: note::SessionEnvelope = serde_json::from_slice(&decoded).expect("parse");
+        assert!(envelope.session_content.is_empty());
+        assert_eq!(
+            envelope.record.session_path.as_deref(),
+            Some(log_path.to_string_lossy().as_ref())
+        );
+        assert_eq!(
+            envelope.resolve_content().await,
+            note::SessionContent::Referenced {
+                content: session_log.to_string(),
+                changed: false,
+            }
+        );
+
+        tokio::fs::remove_file(&log_path).await.unwrap();
+        assert_eq!(
+            envelope.resolve_content().await,
+            note::SessionContent::Missing {
+                path: log_path.to_string_lossy().to_string(),
+            }
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn post_commit_ingest_stores_codex_sessions() {
+        let repo = init_repo().await;
+        let repo_root =
+            PathBuf::from(run_git(repo.path(), &["rev-parse", "--show-toplevel"]).await);
+        let home = TempDir::new().expect("home");
+        let _home = EnvGuard::set("HOME", home.path());
+        let _codex_home = EnvGuard::unset("CODEX_HOME");
+
+        // Codex writes rollouts under ~/.codex/sessions/YYYY/MM/DD/, with the
+        // session id and cwd nested in a leading `session_meta` payload.
+        let session_dir = home.path().join(".codex/sessions/2026/10/16");
+        tokio::fs::create_dir_all(&session_dir).await.unwrap();
+        let session_id = "0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a10";
+        let lines = [
+            serde_json::json!({
+                "timestamp": "2026-10-16T09:00:00.000Z",
+                "type": "session_meta",
+                "payload": {
+                    "id": session_id,
+                    "timestamp": "2026-10-16T09:00:00.000Z",
+                    "cwd": repo_root.to_string_lossy(),
+                    "originator": "codex_cli_rs",
+                    "cli_version": "0.46.0",
+                },
+            }),
+            serde_json::json!({
+                "timestamp": "2026-10-16T09:00:05.000Z",
+                "type": "response_item",
+                "payload": {
+                    "type": "message",
+                    "role": "user",
+                    "content": [{ "type": "input_text", "text": "update the readme" }],
+                },
+            }),
+            serde_json::json!({
+                "timestamp": "2026-10-16T09:00:30.000Z",
+                "type": "event_msg",
+                "payload": { "type": "agent_message", "message": "Updated README.md." },
+            }),
+        ];
+        let content: String = lines.iter().map(|l| format!("{l}\n")).collect();
+        tokio::fs::write(
+            session_dir.join(format!("rollout-2026-10-16T09-00-00-{session_id}.jsonl")),
+            content,
+        )
+        .await
+        .unwrap();
+
+        let mut metrics = HookMetrics::default();
+        let stored = ingest_recent_sessions_for_repo(
+            &repo_root,
+            &repo_root.to_string_lossy(),
+            settings::POST_COMMIT_MATCH_WINDOW_SECS,
+            None,
+            &settings::Settings::load(Some(&repo_root)).await,
+            &EncryptionMethod::None,
+            &mut metrics,
+        )
+        .await
+        .expect("ingest");
+
+        assert_eq!(
+            stored,
+            vec![StoredSession {
+                agent: "codex".to_string(),
+                session_id: session_id.to_string(),
+            }]
+        );
+        assert!(
+            git::ref_exists_at(Some(&repo_root), git::SESSION_DATA_REF)
+                .await
+                .expect("data ref exists")
+        );
+        assert_eq!(metrics.candidates, 1);
+        assert_eq!(metrics.confidence, Some(matcher::Confidence::High));
+    }
+
+    #[test]
+    fn hook_metrics_row_summarizes_the_run() {
+        let report = PostCommitReport::from(PostCommitOutcome::Deferred {
+            budget: Duration::from_millis(2_000),
+        });
+        let metrics = HookMetrics {
+            candidates: 3,
+            confidence: None,
+            subject_fallback: false,
+        };
+        let row = hook_metrics_row(&report, &metrics, Duration::from_millis(2_150));
+        assert_eq!(
+            row,
+            serde_json::json!({
+                "duration_ms": 2150,
+                "candidates": 3,
+                "matched": false,
+                "sessions": 0,
+                "confidence": null,
+                "pending": true,
+                "skipped": false,
+                "error": false,
+            })
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn session_data_size_warns_past_the_threshold() {
+        let repo = init_repo().await;
+        let root = repo.path();
+        let blob = git::store_blob_at(Some(root), &[b'x'; 2048]).await.unwrap();
+        let tree = git::mktree_at(Some(root), &[format!("100644 blob {blob}\tsession")])
+            .await
+            .unwrap();
+        let commit = git::commit_tree_at(Some(root), &tree, "sessions", None)
+            .await
+            .unwrap();
+        git::update_ref_at(Some(root), git::SESSION_DATA_REF, &commit)
+            .await
+            .unwrap();
+
+        let mut quiet = Vec::new();
+        {
+            let _limit = EnvGuard::set("CADENCE_SESSION_DATA_WARN_BYTES", Path::new("4096"));
+            report_session_data_size(&mut quiet, root).await;
+        }
+        let quiet = String::from_utf8(quiet).unwrap();
+        assert!(
+            quiet.contains("Session data: 1 objects, 0.0 MiB"),
+            "{quiet}"
+        );
+        assert!(!quiet.contains("gc --confirm"));
+
+        let mut warned = Vec::new();
+        {
+            let _limit = EnvGuard::set("CADENCE_SESSION_DATA_WARN_BYTES", Path::new("1024"));
+            report_session_data_size(&mut warned, root).await;
+        }
+        let warned = String::from_utf8(warned).unwrap();
+        assert!(
+            warned.contains("ai.cadence.sessionBody reference"),
+            "{warned}"
+        );
+        // gc rewrites the shared ref, so a size warning must not suggest it.
+        assert!(!warned.contains("gc"), "{warned}");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn post_commit_ingest_skips_a_corrupt_line() {
+        let repo = init_repo().await;
+        let repo_root =
+            PathBuf::from(run_git(repo.path(), &["rev-parse", "--show-toplevel"]).await);
+        let home = TempDir::new().expect("home");
+        let _home = EnvGuard::set("HOME", home.path());
+        let _codex_home = EnvGuard::unset("CODEX_HOME");
+
+        // A torn first write ahead of the session metadata.
+        let session_dir = home.path().join(".codex/sessions/2026/10/16");
+        tokio::fs::create_dir_all(&session_dir).await.unwrap();
+        let session_id = "0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a11";
+        let meta = serde_json::json!({
+            "timestamp": "2026-10-16T09:00:00.000Z",
+            "type": "session_meta",
+            "payload": { "id": session_id, "cwd": repo_root.to_string_lossy() },
+        });
+        let content = format!("{{\"timestamp\":\"2026-10-16T08:59\n{meta}\n");
+        let log_path = session_dir.join(format!("rollout-{session_id}.jsonl"));
+        tokio::fs::write(&log_path, &content).await.unwrap();
+
+        let metadata =
+            scanner::parse_agent_session_metadata_str(&scanner::AgentType::Codex, &content);
+        assert_eq!(metadata.skipped_lines, 1);
+        let stored = ingest_recent_sessions_for_repo(
+            &repo_root,
+            &repo_root.to_string_lossy(),
+            settings::POST_COMMIT_MATCH_WINDOW_SECS,
+            None,
+            &settings::Settings::load(Some(&repo_root)).await,
+            &EncryptionMethod::None,
+            &mut HookMetrics::default(),
+        )
+        .await
+        .expect("ingest");
+        assert_eq!(
+            stored,
+            vec![StoredSession {
+                agent: "codex".to_string(),
+                session_id: session_id.to_string(),
+            }]
         );
     }
 
-    #[test]
-    fn cursor_advances_for_indexed_logs() {
-        let updated =
-            advance_cursor_for_disposition(100, Some(150), IncrementalLogDisposition::Indexed);
-        assert_eq!(updated, 150);
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn min_confidence_limits_which_matches_are_stored() {
+        let repo = init_repo().await;
+        let repo_root =
+            PathBuf::from(run_git(repo.path(), &["rev-parse", "--show-toplevel"]).await);
+        let subdir = repo_root.join("src");
+        tokio::fs::create_dir_all(&subdir).await.unwrap();
+        let home = TempDir::new().expect("home");
+        let _home = EnvGuard::set("HOME", home.path());
+        let _xdg = EnvGuard::unset("XDG_DATA_HOME");
+        let _codex_home = EnvGuard::unset("CODEX_HOME");
+
+        // Two live sessions, one at the root and one in a subdirectory, and
+        // one that went quiet a day ago but names the commit subject.
+        let subject = "Teach the parser about quoted names";
+        let session_dir = home.path().join(".codex/sessions/2026/10/16");
+        tokio::fs::create_dir_all(&session_dir).await.unwrap();
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        for (session_id, cwd, age) in [
+            ("0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a31", &repo_root, 0),
+            ("0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a32", &subdir, 0),
+            ("0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a33", &repo_root, 86_400),
+        ] {
+            let lines = [
+                serde_json::json!({
+                    "timestamp": "2026-10-16T09:00:00.000Z",
+                    "type": "session_meta",
+                    "payload": { "id": session_id, "cwd": cwd.to_string_lossy() },
+                }),
+                serde_json::json!({
+                    "timestamp": "2026-10-16T09:01:00.000Z",
+                    "type": "response_item",
+                    "payload": { "type": "message", "role": "assistant", "content": [
+                        { "type": "output_text", "text": format!("git commit -m {subject:?}") },
+                    ] },
+                }),
+            ];
+            let log_path = session_dir.join(format!("rollout-{session_id}.jsonl"));
+            let content: String = lines.iter().map(|line| format!("{line}\n")).collect();
+            tokio::fs::write(&log_path, content).await.unwrap();
+            let mtime = filetime::FileTime::from_unix_time(now - age, 0);
+            filetime::set_file_mtime(&log_path, mtime).unwrap();
+        }
+        run_git(
+            repo.path(),
+            &["commit", "-q", "--allow-empty", "-m", subject],
+        )
+        .await;
+
+        let window = MatchWindow {
+            lookback_secs: settings::POST_COMMIT_MATCH_WINDOW_SECS,
+            not_after: None,
+        };
+        for (level, in_window, by_subject) in [
+            ("weak", vec!["8a31", "8a32"], true),
+            ("strong", vec!["8a31", "8a32"], false),
+            ("exact", vec!["8a31"], false),
+        ] {
+            run_git(
+                repo.path(),
+                &["config", settings::MIN_CONFIDENCE_KEY, level],
+            )
+            .await;
+            let stored = ingest_recent_sessions_for_repo(
+                &repo_root,
+                &repo_root.to_string_lossy(),
+                window.lookback_secs,
+                window.not_after,
+                &settings::Settings::load(Some(&repo_root)).await,
+                &EncryptionMethod::None,
+                &mut HookMetrics::default(),
+            )
+            .await
+            .expect("ingest");
+            let mut ids: Vec<&str> = stored
+                .iter()
+                .map(|s| &s.session_id[s.session_id.len() - 4..])
+                .collect();
+            ids.sort();
+            assert_eq!(ids, in_window, "minConfidence={level}");
+
+            let stored = ingest_session_by_subject(
+                &repo_root,
+                &repo_root.to_string_lossy(),
+                window,
+                &settings::Settings::load(Some(&repo_root)).await,
+                &EncryptionMethod::None,
+                &mut HookMetrics::default(),
+            )
+            .await
+            .expect("ingest by subject");
+            assert_eq!(stored.is_some(), by_subject, "minConfidence={level}");
+        }
+
+        // The subdirectory session missed `exact` and is left for a retry.
+        let pending = sync_pending::pending_ingests(&repo_root.to_string_lossy())
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 1);
+        let sync_pending::PendingIngest::SessionLog { path, .. } = &pending[0].1.target else {
+            panic!("expected a pending session log");
+        };
+        assert!(path.ends_with("8a32.jsonl"));
     }
 
-    #[test]
-    fn cursor_advances_for_permanent_skips() {
-        let updated = advance_cursor_for_disposition(
-            100,
-            Some(140),
-            IncrementalLogDisposition::SkippedPermanent,
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn deferred_commit_is_stored_by_the_next_push() {
+        let repo = init_repo().await;
+        let repo_root =
+            PathBuf::from(run_git(repo.path(), &["rev-parse", "--show-toplevel"]).await);
+        let repo_root_str = repo_root.to_string_lossy().to_string();
+        let home = TempDir::new().expect("home");
+        let _home = EnvGuard::set("HOME", home.path());
+        let _xdg = EnvGuard::unset("XDG_DATA_HOME");
+        let _codex_home = EnvGuard::unset("CODEX_HOME");
+
+        let session_id = "0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a41";
+        let session_dir = home.path().join(".codex/sessions/2026/10/16");
+        tokio::fs::create_dir_all(&session_dir).await.unwrap();
+        let meta = serde_json::json!({
+            "timestamp": "2026-10-16T09:00:00.000Z",
+            "type": "session_meta",
+            "payload": { "id": session_id, "cwd": repo_root.to_string_lossy() },
+        });
+        tokio::fs::write(
+            session_dir.join(format!("rollout-{session_id}.jsonl")),
+            format!("{meta}\n"),
+        )
+        .await
+        .unwrap();
+
+        // The post-commit hook ran out of time before finding anything.
+        let settings = settings::Settings::load(Some(&repo_root)).await;
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let anchor = commit_anchor(&repo_root, &settings, now).await;
+        defer_commit(&repo_root, &repo_root_str, anchor)
+            .await
+            .expect("defer");
+        let pending = sync_pending::pending_ingests(&repo_root_str).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        let sync_pending::PendingIngest::Commit { sha, anchor_epoch } = &pending[0].1.target else {
+            panic!("expected a pending commit");
+        };
+        assert_eq!(sha, &run_git(repo.path(), &["rev-parse", "HEAD"]).await);
+        assert_eq!(*anchor_epoch, anchor);
+
+        // The next push scans the commit's window and stores the session.
+        let stored = retry_pending_ingests(
+            &repo_root,
+            &repo_root_str,
+            &settings,
+            &EncryptionMethod::None,
+            &std::collections::HashSet::new(),
+        )
+        .await
+        .expect("retry");
+        assert_eq!(stored, 1);
+        assert!(
+            sync_pending::pending_ingests(&repo_root_str)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(
+            git::ref_exists_at(Some(&repo_root), git::SESSION_DATA_REF)
+                .await
+                .expect("data ref exists")
         );
-        assert_eq!(updated, 140);
     }
 
-    #[test]
-    fn cursor_does_not_advance_for_retriable_errors() {
-        let updated = advance_cursor_for_disposition(
-            100,
-            Some(180),
-            IncrementalLogDisposition::ErrorRetriable,
-        );
-        assert_eq!(updated, 100);
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn pre_push_ingest_retries_matches_below_min_confidence() {
+        let repo = init_repo().await;
+        let repo_root =
+            PathBuf::from(run_git(repo.path(), &["rev-parse", "--show-toplevel"]).await);
+        let subdir = repo_root.join("src");
+        tokio::fs::create_dir_all(&subdir).await.unwrap();
+        let home = TempDir::new().expect("home");
+        let _home = EnvGuard::set("HOME", home.path());
+        let _xdg = EnvGuard::unset("XDG_DATA_HOME");
+        let _codex_home = EnvGuard::unset("CODEX_HOME");
+
+        // A high-confidence session at the root and a medium one in a
+        // subdirectory.
+        let session_dir = home.path().join(".codex/sessions/2026/10/16");
+        tokio::fs::create_dir_all(&session_dir).await.unwrap();
+        for (session_id, cwd) in [
+            ("0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a51", &repo_root),
+            ("0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a52", &subdir),
+        ] {
+            let meta = serde_json::json!({
+                "timestamp": "2026-10-16T09:00:00.000Z",
+                "type": "session_meta",
+                "payload": { "id": session_id, "cwd": cwd.to_string_lossy() },
+            });
+            let log_path = session_dir.join(format!("rollout-{session_id}.jsonl"));
+            tokio::fs::write(&log_path, format!("{meta}\n"))
+                .await
+                .unwrap();
+        }
+        let ingest = || async {
+            ingest_incremental_sessions_for_repo(
+                &repo_root,
+                &repo_root.to_string_lossy(),
+                &settings::Settings::load(Some(&repo_root)).await,
+                &EncryptionMethod::None,
+            )
+            .await
+            .expect("ingest")
+        };
+
+        // HEAD's author being skipped does not matter here, since the
+        // sessions may belong to earlier commits. Only the session clearing
+        // minConfidence is stored; the other is left pending.
+        run_git(
+            repo.path(),
+            &["config", settings::SKIP_AUTHORS_KEY, "test@example.com"],
+        )
+        .await;
+        run_git(
+            repo.path(),
+            &["config", settings::MIN_CONFIDENCE_KEY, "exact"],
+        )
+        .await;
+        assert_eq!(ingest().await, 1);
+        let pending = || async {
+            sync_pending::pending_ingests(&repo_root.to_string_lossy())
+                .await
+                .unwrap()
+                .len()
+        };
+        assert_eq!(pending().await, 1);
+
+        // The cursor has moved past both logs, but the pending one is
+        // stored once it clears the minimum.
+        assert_eq!(ingest().await, 0);
+        assert_eq!(pending().await, 1);
+        run_git(
+            repo.path(),
+            &["config", settings::MIN_CONFIDENCE_KEY, "strong"],
+        )
+        .await;
+        assert_eq!(ingest().await, 1);
+        assert_eq!(pending().await, 0);
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn ingest_session_writes_data_and_indexes() {
+    #[serial]
+    async fn bench_and_explain_see_what_the_hook_stores() {
         let repo = init_repo().await;
+        let repo_root =
+            PathBuf::from(run_git(repo.path(), &["rev-parse", "--show-toplevel"]).await);
+        let subdir = repo_root.join("src");
+        tokio::fs::create_dir_all(&subdir).await.unwrap();
+        let home = TempDir::new().expect("home");
+        let _home = EnvGuard::set("HOME", home.path());
+        let _xdg = EnvGuard::unset("XDG_DATA_HOME");
+        let _codex_home = EnvGuard::unset("CODEX_HOME");
+
+        let session_dir = home.path().join(".codex/sessions/2026/10/16");
+        tokio::fs::create_dir_all(&session_dir).await.unwrap();
+        for (session_id, cwd) in [
+            ("0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a61", &repo_root),
+            ("0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a62", &subdir),
+        ] {
+            let meta = serde_json::json!({
+                "timestamp": "2026-10-16T09:00:00.000Z",
+                "type": "session_meta",
+                "payload": { "id": session_id, "cwd": cwd.to_string_lossy() },
+            });
+            let log_path = session_dir.join(format!("rollout-{session_id}.jsonl"));
+            tokio::fs::write(&log_path, format!("{meta}\n"))
+                .await
+                .unwrap();
+        }
         run_git(
             repo.path(),
-            &[
-                "remote",
-                "add",
-                "origin",
-                "git@github.com:example-org/example-repo.git",
-            ],
+            &["config", settings::MIN_CONFIDENCE_KEY, "high"],
         )
         .await;
+        let settings = settings::Settings::load(Some(&repo_root)).await;
 
-        let session_log = include_str!("../tests/fixtures/backfill/session_no_ranked.jsonl");
-        let info = ingest_session_from_log(
-            &scanner::AgentType::Claude,
-            "aaa111",
-            &repo.path().to_string_lossy(),
-            session_log,
+        let considered = consider_recent_sessions(
+            &repo_root,
+            settings::POST_COMMIT_MATCH_WINDOW_SECS,
+            None,
+            &settings,
+            &mut HookMetrics::default(),
+        )
+        .await;
+        assert_eq!(considered.len(), 2);
+        let selected: Vec<&str> = considered
+            .iter()
+            .filter(|c| c.selected(&settings))
+            .filter_map(|c| c.candidate.session_id.as_deref())
+            .collect();
+        assert_eq!(selected, ["0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a61"]);
+
+        let stored = ingest_recent_sessions_for_repo(
+            &repo_root,
+            &repo_root.to_string_lossy(),
+            settings::POST_COMMIT_MATCH_WINDOW_SECS,
+            None,
+            &settings,
             &EncryptionMethod::None,
-            Some(1_707_526_800),
-            Some(repo.path()),
+            &mut HookMetrics::default(),
+        )
+        .await
+        .expect("ingest");
+        let stored: Vec<&str> = stored.iter().map(|s| s.session_id.as_str()).collect();
+        assert_eq!(stored, selected);
+
+        // A log without a session id is stored under "unknown" and
+        // `--explain` reports it as stored, not rejected.
+        let meta = serde_json::json!({
+            "timestamp": "2026-10-16T09:00:00.000Z",
+            "type": "session_meta",
+            "payload": { "cwd": repo_root.to_string_lossy() },
+        });
+        tokio::fs::write(session_dir.join("rollout-no-id.jsonl"), format!("{meta}\n"))
+            .await
+            .unwrap();
+        let settings = settings::Settings {
+            min_confidence: matcher::Confidence::Medium,
+            ..settings
+        };
+        let considered = consider_recent_sessions(
+            &repo_root,
+            settings::POST_COMMIT_MATCH_WINDOW_SECS,
             None,
+            &settings,
+            &mut HookMetrics::default(),
+        )
+        .await;
+        let no_id = considered
+            .iter()
+            .find(|c| c.candidate.session_id.is_none())
+            .expect("log without a session id");
+        assert!(no_id.selected(&settings));
+        let stored = ingest_recent_sessions_for_repo(
+            &repo_root,
+            &repo_root.to_string_lossy(),
+            settings::POST_COMMIT_MATCH_WINDOW_SECS,
+            None,
+            &settings,
+            &EncryptionMethod::None,
+            &mut HookMetrics::default(),
         )
         .await
         .expect("ingest");
-
-        assert_eq!(info.blob_sha.len(), 40);
-        assert!(
-            git::ref_exists_at(Some(repo.path()), git::SESSION_DATA_REF)
-                .await
-                .expect("data ref exists")
+        assert!(stored.iter().any(|s| s.session_id == "unknown"));
+        let window = MatchWindow {
+            lookback_secs: settings::POST_COMMIT_MATCH_WINDOW_SECS,
+            not_after: None,
+        };
+        assert_eq!(
+            explain_decision(&no_id.candidate, window, &stored, settings.min_confidence),
+            "stored"
         );
-        assert!(
-            git::ref_exists_at(Some(repo.path()), git::SESSION_INDEX_BRANCH_REF)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn recheck_delay_picks_up_a_log_written_after_the_commit() {
+        let repo = init_repo().await;
+        let repo_root =
+            PathBuf::from(run_git(repo.path(), &["rev-parse", "--show-toplevel"]).await);
+        let home = TempDir::new().expect("home");
+        let _home = EnvGuard::set("HOME", home.path());
+        let _codex_home = EnvGuard::unset("CODEX_HOME");
+        let session_dir = home.path().join(".codex/sessions/2026/10/16");
+        tokio::fs::create_dir_all(&session_dir).await.unwrap();
+        let window = MatchWindow {
+            lookback_secs: settings::POST_COMMIT_MATCH_WINDOW_SECS,
+            not_after: None,
+        };
+
+        // Nothing on disk yet and no second look: nothing is stored.
+        let stored = ingest_for_commit(
+            &repo_root,
+            &repo_root.to_string_lossy(),
+            window,
+            &settings::Settings {
+                recheck_delay: None,
+                ..settings::Settings::load(Some(&repo_root)).await
+            },
+            &EncryptionMethod::None,
+            &mut HookMetrics::default(),
+        )
+        .await
+        .expect("ingest");
+        assert!(stored.is_empty());
+
+        // The agent flushes its log shortly after the hook starts.
+        let session_id = "0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a41";
+        let meta = serde_json::json!({
+            "timestamp": "2026-10-16T09:00:00.000Z",
+            "type": "session_meta",
+            "payload": { "id": session_id, "cwd": repo_root.to_string_lossy() },
+        });
+        let log_path = session_dir.join(format!("rollout-{session_id}.jsonl"));
+        let writer = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            tokio::fs::write(&log_path, format!("{meta}\n"))
                 .await
-                .expect("branch index ref exists")
+                .unwrap();
+        });
+        let stored = ingest_for_commit(
+            &repo_root,
+            &repo_root.to_string_lossy(),
+            window,
+            &settings::Settings {
+                recheck_delay: Some(Duration::from_millis(500)),
+                ..settings::Settings::load(Some(&repo_root)).await
+            },
+            &EncryptionMethod::None,
+            &mut HookMetrics::default(),
+        )
+        .await
+        .expect("ingest");
+        writer.await.unwrap();
+        assert_eq!(
+            stored,
+            vec![StoredSession {
+                agent: "codex".to_string(),
+                session_id: session_id.to_string(),
+            }]
         );
-        assert!(
-            git::ref_exists_at(Some(repo.path()), git::SESSION_INDEX_COMMITTER_REF)
-                .await
-                .expect("committer index ref exists")
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn post_commit_falls_back_to_a_session_naming_the_commit_subject() {
+        let repo = init_repo().await;
+        let repo_root =
+            PathBuf::from(run_git(repo.path(), &["rev-parse", "--show-toplevel"]).await);
+        let home = TempDir::new().expect("home");
+        let _home = EnvGuard::set("HOME", home.path());
+        let _codex_home = EnvGuard::unset("CODEX_HOME");
+
+        // Two sessions in this repo, both quiet for a day: the work was
+        // stashed and popped long after. Only one mentions the subject.
+        let session_dir = home.path().join(".codex/sessions/2026/10/15");
+        tokio::fs::create_dir_all(&session_dir).await.unwrap();
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        let subject = "Teach the parser about quoted names";
+        for (session_id, text) in [
+            ("0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a21", "unrelated refactor"),
+            (
+                "0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a22",
+                "git commit -m \"Teach the parser about quoted names\"",
+            ),
+        ] {
+            let lines = [
+                serde_json::json!({
+                    "timestamp": "2026-10-15T09:00:00.000Z",
+                    "type": "session_meta",
+                    "payload": { "id": session_id, "cwd": repo_root.to_string_lossy() },
+                }),
+                serde_json::json!({
+                    "timestamp": "2026-10-15T09:01:00.000Z",
+                    "type": "response_item",
+                    "payload": { "type": "message", "role": "assistant", "content": [
+                        { "type": "output_text", "text": text },
+                    ] },
+                }),
+            ];
+            let log_path = session_dir.join(format!("rollout-{session_id}.jsonl"));
+            let content: String = lines.iter().map(|line| format!("{line}\n")).collect();
+            tokio::fs::write(&log_path, content).await.unwrap();
+            let mtime = filetime::FileTime::from_unix_time(now - 86_400, 0);
+            filetime::set_file_mtime(&log_path, mtime).unwrap();
+        }
+        tokio::fs::write(repo.path().join("parser.rs"), "// quoted\n")
+            .await
+            .unwrap();
+        run_git(repo.path(), &["add", "parser.rs"]).await;
+        run_git(repo.path(), &["commit", "-q", "-m", subject]).await;
+
+        let window = MatchWindow {
+            lookback_secs: settings::POST_COMMIT_MATCH_WINDOW_SECS,
+            not_after: None,
+        };
+        let stored = ingest_recent_sessions_for_repo(
+            &repo_root,
+            &repo_root.to_string_lossy(),
+            window.lookback_secs,
+            window.not_after,
+            &settings::Settings::load(Some(&repo_root)).await,
+            &EncryptionMethod::None,
+            &mut HookMetrics::default(),
+        )
+        .await
+        .expect("ingest");
+        assert!(stored.is_empty());
+
+        // The fallback is opt-in.
+        let mut metrics = HookMetrics::default();
+        let stored = ingest_for_commit(
+            &repo_root,
+            &repo_root.to_string_lossy(),
+            window,
+            &settings::Settings {
+                recheck_delay: None,
+                ..settings::Settings::load(Some(&repo_root)).await
+            },
+            &EncryptionMethod::None,
+            &mut metrics,
+        )
+        .await
+        .expect("ingest");
+        assert!(stored.is_empty());
+        assert!(!metrics.subject_fallback);
+
+        let stored = ingest_session_by_subject(
+            &repo_root,
+            &repo_root.to_string_lossy(),
+            window,
+            &settings::Settings::load(Some(&repo_root)).await,
+            &EncryptionMethod::None,
+            &mut HookMetrics::default(),
+        )
+        .await
+        .expect("ingest by subject");
+        assert_eq!(
+            stored,
+            Some(StoredSession {
+                agent: "codex".to_string(),
+                session_id: "0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a22".to_string(),
+            })
         );
+        let tree = run_git(repo.path(), &["ls-tree", "-r", git::SESSION_DATA_REF]).await;
+        let blob_sha = tree.split_whitespace().nth(2).expect("session object");
+        let blob = git::read_blob_at(Some(repo.path()), blob_sha)
+            .await
+            .expect("read blob");
+        let decoded = load_decrypted_session_blob(&blob).await.expect("decode");
+        let envelope: note::SessionEnvelope = serde_json::from_slice(&decoded).expect("parse");
+        assert!(envelope.record.matched_by_subject);
+
+        // A generic subject is not enough to link anything.
+        run_git(repo.path(), &["commit", "-q", "--allow-empty", "-m", "wip"]).await;
+        let stored = ingest_session_by_subject(
+            &repo_root,
+            &repo_root.to_string_lossy(),
+            window,
+            &settings::Settings::load(Some(&repo_root)).await,
+            &EncryptionMethod::None,
+            &mut HookMetrics::default(),
+        )
+        .await
+        .expect("ingest by subject");
+        assert_eq!(stored, None);
     }
 
     #[tokio::test(flavor = "multi_thread")]
@@ -5142,11 +9465,15 @@ mod tests {
         let info = ingest_session_from_log(
             &scanner::AgentType::Claude,
             "multi-branch",
+            false,
+            false,
             &repo.path().to_string_lossy(),
             session_log,
+            None,
             &EncryptionMethod::None,
             Some(1_707_526_800),
             Some(repo.path()),
+            &settings::Settings::load(Some(repo.path())).await,
             Some(&branch_keys),
         )
         .await
@@ -5173,11 +9500,15 @@ mod tests {
         let first_info = ingest_session_from_log(
             &scanner::AgentType::Claude,
             "stable-1",
+            false,
+            false,
             &repo.path().to_string_lossy(),
             session_log,
+            None,
             &EncryptionMethod::None,
             Some(1_707_526_800),
             Some(repo.path()),
+            &settings::Settings::load(Some(repo.path())).await,
             None,
         )
         .await
@@ -5186,11 +9517,15 @@ mod tests {
         let second_info = ingest_session_from_log(
             &scanner::AgentType::Claude,
             "stable-1",
+            false,
+            false,
             &repo.path().to_string_lossy(),
             session_log,
+            None,
             &EncryptionMethod::None,
             Some(1_707_526_800),
             Some(repo.path()),
+            &settings::Settings::load(Some(repo.path())).await,
             None,
         )
         .await
@@ -5200,6 +9535,47 @@ mod tests {
         assert_eq!(first_info.session_uid, second_info.session_uid);
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn ingest_reuses_the_stored_object_for_unchanged_content() {
+        let repo = init_repo().await;
+        let session_log = "{\"type\":\"user\",\"message\":\"key sk-abcdefghijklmnopqrstuvwxyz\"}\n";
+        let ingest = || async {
+            ingest_session_from_log(
+                &scanner::AgentType::Claude,
+                "dedup-1",
+                false,
+                false,
+                repo.path().to_str().unwrap(),
+                session_log,
+                None,
+                &EncryptionMethod::None,
+                Some(1_707_526_800),
+                Some(repo.path()),
+                &settings::Settings::load(Some(repo.path())).await,
+                None,
+            )
+            .await
+        };
+
+        let first = ingest().await.expect("first ingest");
+        let tip = git::rev_parse_at(Some(repo.path()), git::SESSION_DATA_REF)
+            .await
+            .expect("data ref");
+
+        // Redacting would change the stored bytes, but the content hash is
+        // unchanged, so the existing object is referenced instead.
+        run_git(repo.path(), &["config", note::REDACT_SECRETS_KEY, "true"]).await;
+        let second = ingest().await.expect("second ingest");
+        assert_eq!(second.blob_sha, first.blob_sha);
+        assert_eq!(second.encoding, note::ContentEncoding::Zstd);
+        assert_eq!(
+            git::rev_parse_at(Some(repo.path()), git::SESSION_DATA_REF)
+                .await
+                .expect("data ref"),
+            tip
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn backfill_uploads_session_without_commit_candidates() {
         let repo = init_repo().await;
@@ -5214,6 +9590,8 @@ mod tests {
             session_id: Some("no-candidate".to_string()),
             cwd: Some(repo.path().to_string_lossy().to_string()),
             agent_type: Some(scanner::AgentType::Claude),
+            session_id_inferred: false,
+            skipped_lines: 0,
         };
         let stats = process_repo_backfill(
             "example-org/example-repo".to_string(),
@@ -5241,6 +9619,62 @@ mod tests {
         );
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    #[serial]
+    async fn backfill_leaves_matches_below_min_confidence_pending() {
+        let repo = init_repo().await;
+        let home = TempDir::new().expect("home");
+        let _home = EnvGuard::set("HOME", home.path());
+        let _xdg = EnvGuard::unset("XDG_DATA_HOME");
+        run_git(
+            repo.path(),
+            &["config", settings::MIN_CONFIDENCE_KEY, "exact"],
+        )
+        .await;
+        let session_file = repo.path().join("session-subdir.jsonl");
+        tokio::fs::write(
+            &session_file,
+            r#"{"timestamp":"2026-02-10T01:00:00Z","session_id":"subdir","cwd":"/tmp/repo"}"#,
+        )
+        .await
+        .expect("write session file");
+        let metadata = scanner::SessionMetadata {
+            session_id: Some("subdir".to_string()),
+            cwd: Some(repo.path().join("src").to_string_lossy().to_string()),
+            agent_type: Some(scanner::AgentType::Claude),
+            session_id_inferred: false,
+            skipped_lines: 0,
+        };
+        let stats = process_repo_backfill(
+            "example-org/example-repo".to_string(),
+            vec![SessionInfo {
+                log: agents::SessionLog {
+                    agent_type: scanner::AgentType::Claude,
+                    source: agents::SessionSource::File(session_file),
+                    updated_at: Some(0),
+                },
+                session_id: "subdir".to_string(),
+                repo_root: repo.path().to_path_buf(),
+                metadata,
+            }],
+            EncryptionMethod::None,
+            None,
+            backfill_log::BackfillLogger::disabled(),
+        )
+        .await;
+
+        assert_eq!((stats.attached, stats.skipped), (0, 1));
+        assert!(
+            !git::ref_exists_at(Some(repo.path()), git::SESSION_DATA_REF)
+                .await
+                .unwrap()
+        );
+        let pending = sync_pending::pending_ingests(&repo.path().to_string_lossy())
+            .await
+            .unwrap();
+        assert_eq!(pending.len(), 1);
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn backfill_uploads_session_without_explicit_commit_matches() {
         let repo = init_repo().await;
@@ -5255,6 +9689,8 @@ mod tests {
             session_id: Some("missing-commit".to_string()),
             cwd: Some(repo.path().to_string_lossy().to_string()),
             agent_type: Some(scanner::AgentType::Claude),
+            session_id_inferred: false,
+            skipped_lines: 0,
         };
         let stats = process_repo_backfill(
             "example-org/example-repo".to_string(),
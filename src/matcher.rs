@@ -0,0 +1,741 @@
+//! Match agent sessions to a repository.
+//!
+//! A session belongs to a repository when the working directory recorded in
+//! its log resolves (via `git rev-parse --show-toplevel`) to that
+//! repository's root. When several sessions match, [`Matcher::best_session`]
+//! ranks them deterministically; see [`rank_key`].
+//!
+//! Submodules and nested repositories are their own repositories: a session
+//! started in the outer checkout resolves to the outer root and does not match
+//! commits made in the inner one. With [`Matcher::match_parent_cwd`] (git
+//! config `ai.cadence.matchParentCwd`), a working directory that is an
+//! ancestor of the repository root matches as well, as long as it is inside
+//! the superproject or enclosing work tree (see [`parent_cwd_scope`]).
+//! `$HOME` and `/` never match as parents.
+//!
+//! [`Matcher`] reads only log metadata, for quick lookups before a commit
+//! exists (the trailer added by prepare-commit-msg) and for
+//! `cadence sessions detect`. The post-commit hook, `cadence bench` and
+//! `--explain` read whole logs and build their [`Candidate`]s themselves,
+//! with the same [`MatchEvidence`] and [`CwdMatch`] rules.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::agents::{self, SessionLog, SessionSource};
+use crate::scanner::{self, AgentType, SessionMetadata};
+
+/// A session log whose working directory is inside the matched repository.
+#[derive(Debug, Clone)]
+pub struct MatchedSession {
+    pub agent: AgentType,
+    pub session_id: String,
+    /// Working directory recorded in the session log.
+    pub cwd: String,
+    /// Whether `cwd` is exactly the repository root rather than a subdirectory.
+    pub cwd_is_root: bool,
+    /// Last modification of the session log (unix seconds), if known.
+    pub updated_at: Option<i64>,
+    /// Log file path or inline source label.
+    pub source_label: String,
+}
+
+/// How a session log's working directory relates to a repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CwdMatch {
+    /// The repository root itself.
+    Root,
+    /// A directory inside the repository.
+    Subdirectory,
+    /// A parent directory of the repository root inside its parent scope
+    /// (see [`parent_cwd_scope`]), e.g. the checkout that contains a
+    /// submodule. Only reported when parent matching is enabled.
+    Parent,
+    /// Inside a different repository.
+    OtherRepository,
+    /// Not inside any git repository (or no longer on disk).
+    NotInRepository,
+    /// The log records no working directory.
+    Unknown,
+}
+
+impl CwdMatch {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CwdMatch::Root => "root",
+            CwdMatch::Subdirectory => "subdir",
+            CwdMatch::Parent => "parent",
+            CwdMatch::OtherRepository => "other repo",
+            CwdMatch::NotInRepository => "no repo",
+            CwdMatch::Unknown => "unknown",
+        }
+    }
+
+    /// Whether sessions with this working directory belong to the repository.
+    pub fn matches(self) -> bool {
+        matches!(
+            self,
+            CwdMatch::Root | CwdMatch::Subdirectory | CwdMatch::Parent
+        )
+    }
+
+    /// Classify working directory `cwd`, which git resolved to `resolved`
+    /// (`None` outside any repository), against `repo_root`. Parent
+    /// directories match only inside `parent_scope`, which is `None` when
+    /// parent matching is off, and never when they are `$HOME` or `/`.
+    pub fn classify(
+        cwd: &Path,
+        resolved: Option<&Path>,
+        repo_root: &Path,
+        parent_scope: Option<&Path>,
+    ) -> Self {
+        match resolved {
+            Some(root) if scanner::same_path(root, repo_root) => {
+                if scanner::same_path(cwd, repo_root) {
+                    CwdMatch::Root
+                } else {
+                    CwdMatch::Subdirectory
+                }
+            }
+            _ if parent_scope.is_some_and(|scope| is_parent_in_scope(cwd, repo_root, scope)) => {
+                CwdMatch::Parent
+            }
+            Some(_) => CwdMatch::OtherRepository,
+            None => CwdMatch::NotInRepository,
+        }
+    }
+}
+
+fn is_parent_in_scope(cwd: &Path, repo_root: &Path, scope: &Path) -> bool {
+    scanner::is_ancestor_path(cwd, repo_root)
+        && (scanner::same_path(cwd, scope) || scanner::is_ancestor_path(scope, cwd))
+        && !is_too_broad(cwd)
+}
+
+/// `/` and the home directory contain every repository on the machine.
+fn is_too_broad(dir: &Path) -> bool {
+    dir.parent().is_none() || agents::home_dir().is_some_and(|home| scanner::same_path(dir, &home))
+}
+
+/// The outermost directory whose sessions may match `repo_root` as a
+/// parent: the superproject's work tree when `repo_root` is a submodule,
+/// otherwise the nearest work tree enclosing it. `None` when there is
+/// neither, or it is `$HOME` or `/`.
+pub async fn parent_cwd_scope(repo_root: &Path) -> Option<PathBuf> {
+    let superproject = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(repo_root)
+        .args(["rev-parse", "--show-superproject-working-tree"])
+        .output()
+        .await
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|root| root.trim().to_string())
+        .filter(|root| !root.is_empty());
+    let scope = match superproject {
+        Some(root) => PathBuf::from(root),
+        None => repo_root_of(repo_root.parent()?).await?,
+    };
+    (!is_too_broad(&scope)).then_some(scope)
+}
+
+/// How strongly a match ties a session log to a commit, weakest first.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Confidence {
+    /// Linked only because the log mentions the commit subject.
+    #[default]
+    Low,
+    /// Started in a subdirectory or parent directory, or the session id was
+    /// taken from the file name or is missing.
+    Medium,
+    /// Started at the repository root, with its own session id.
+    High,
+}
+
+impl Confidence {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        }
+    }
+
+    /// Parse `weak`, `strong` or `exact`, or their aliases `low`, `medium`
+    /// and `high`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "weak" | "low" => Some(Confidence::Low),
+            "strong" | "medium" => Some(Confidence::Medium),
+            "exact" | "high" => Some(Confidence::High),
+            _ => None,
+        }
+    }
+
+    /// Whether a match of `confidence` clears this minimum. `Low` admits
+    /// everything.
+    pub fn admits(self, confidence: Option<Confidence>) -> bool {
+        self == Confidence::Low || confidence >= Some(self)
+    }
+}
+
+/// The checks behind a match decision for one session log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MatchEvidence {
+    pub cwd_match: CwdMatch,
+    /// Whether the log has a session id, read from it or from its file name.
+    pub has_session_id: bool,
+    /// The session id came from the file name.
+    pub session_id_inferred: bool,
+    /// Seconds from the log's last update to the match time; negative when
+    /// the log was updated later. `None` when the update time is unknown.
+    pub mtime_delta_secs: Option<i64>,
+}
+
+impl MatchEvidence {
+    /// Whether the log belongs to the repository. A log without a session
+    /// id matches too; the hooks store it under an unknown id.
+    pub fn is_match(&self) -> bool {
+        self.cwd_match.matches()
+    }
+
+    /// Whether the log matches at `min_confidence` or better: what the
+    /// hooks store, and what `--explain` reports as a match.
+    pub fn clears(&self, min_confidence: Confidence) -> bool {
+        self.is_match() && min_confidence.admits(self.confidence())
+    }
+
+    /// How strongly the evidence ties a matching log to the repository;
+    /// never [`Confidence::Low`], which only subject matches get. `None`
+    /// when the log does not match.
+    pub fn confidence(&self) -> Option<Confidence> {
+        if !self.is_match() {
+            return None;
+        }
+        if self.cwd_match == CwdMatch::Root && self.has_session_id && !self.session_id_inferred {
+            Some(Confidence::High)
+        } else {
+            Some(Confidence::Medium)
+        }
+    }
+
+    /// Why the log does not match, or `None` if it does.
+    pub fn rejection(&self) -> Option<&'static str> {
+        match self.cwd_match {
+            CwdMatch::OtherRepository => Some("working directory is in another repository"),
+            CwdMatch::NotInRepository => Some("working directory is not in a git repository"),
+            CwdMatch::Unknown => Some("log has no working directory"),
+            _ => None,
+        }
+    }
+}
+
+/// A recent session log as seen by the matcher, whether or not it matches.
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub agent: AgentType,
+    pub session_id: Option<String>,
+    pub cwd: Option<String>,
+    pub updated_at: Option<i64>,
+    pub source_label: String,
+    pub evidence: MatchEvidence,
+}
+
+impl Candidate {
+    /// Shorthand for `self.evidence.is_match()`.
+    pub fn is_match(&self) -> bool {
+        self.evidence.is_match()
+    }
+
+    /// The matched session, if this log belongs to the repository and has an id.
+    pub fn into_matched(self) -> Option<MatchedSession> {
+        if !self.is_match() {
+            return None;
+        }
+        Some(MatchedSession {
+            agent: self.agent,
+            session_id: self.session_id?,
+            cwd: self.cwd?,
+            cwd_is_root: self.evidence.cwd_match == CwdMatch::Root,
+            updated_at: self.updated_at,
+            source_label: self.source_label,
+        })
+    }
+}
+
+/// Finds the agent sessions that ran in a repository.
+#[derive(Debug, Clone, Copy)]
+pub struct Matcher {
+    window_secs: i64,
+    match_parent_cwd: bool,
+    min_confidence: Confidence,
+}
+
+impl Matcher {
+    /// Consider session logs updated within `window_secs` of the match time.
+    pub fn new(window_secs: i64) -> Self {
+        Self {
+            window_secs,
+            match_parent_cwd: false,
+            min_confidence: Confidence::default(),
+        }
+    }
+
+    /// Also match sessions whose working directory is a parent of the
+    /// repository root within its enclosing work tree (see
+    /// [`CwdMatch::Parent`]).
+    pub fn match_parent_cwd(mut self, enabled: bool) -> Self {
+        self.match_parent_cwd = enabled;
+        self
+    }
+
+    /// Leave out sessions whose evidence is weaker than `min_confidence`
+    /// from [`Matcher::sessions_for_repo`] and [`Matcher::best_session`].
+    pub fn min_confidence(mut self, min_confidence: Confidence) -> Self {
+        self.min_confidence = min_confidence;
+        self
+    }
+
+    /// Every session log updated within the window, with the evidence for
+    /// whether it matches `repo_root` at `now`. Sorted by [`closeness`].
+    pub async fn candidates(&self, repo_root: &Path, now: i64) -> Vec<Candidate> {
+        let parent_scope = if self.match_parent_cwd {
+            parent_cwd_scope(repo_root).await
+        } else {
+            None
+        };
+        let logs = agents::discover_recent_sessions(now, self.window_secs).await;
+        let (logs, _) = agents::partition_oversized(logs, agents::max_session_log_bytes()).await;
+        let mut repo_roots: HashMap<String, Option<PathBuf>> = HashMap::new();
+        let mut candidates = Vec::with_capacity(logs.len());
+        for log in logs {
+            let metadata = session_metadata(&log).await;
+            let cwd_match = match metadata.cwd.as_deref() {
+                None => CwdMatch::Unknown,
+                Some(cwd) => {
+                    let resolved = match repo_roots.get(cwd) {
+                        Some(cached) => cached.clone(),
+                        None => {
+                            let resolved = repo_root_of(Path::new(cwd)).await;
+                            repo_roots.insert(cwd.to_string(), resolved.clone());
+                            resolved
+                        }
+                    };
+                    CwdMatch::classify(
+                        Path::new(cwd),
+                        resolved.as_deref(),
+                        repo_root,
+                        parent_scope.as_deref(),
+                    )
+                }
+            };
+            let evidence = MatchEvidence {
+                cwd_match,
+                has_session_id: metadata.session_id.is_some(),
+                session_id_inferred: metadata.session_id_inferred,
+                mtime_delta_secs: log.updated_at.map(|updated| now - updated),
+            };
+            candidates.push(Candidate {
+                agent: log.agent_type.clone(),
+                session_id: metadata.session_id,
+                cwd: metadata.cwd,
+                updated_at: log.updated_at,
+                source_label: log.source_label(),
+                evidence,
+            });
+        }
+        candidates.sort_by(|a, b| closeness(a).cmp(&closeness(b)));
+        candidates
+    }
+
+    /// Sessions that ran in `repo_root`, best match first.
+    pub async fn sessions_for_repo(&self, repo_root: &Path, now: i64) -> Vec<MatchedSession> {
+        let mut matched: Vec<MatchedSession> = self
+            .candidates(repo_root, now)
+            .await
+            .into_iter()
+            .filter(|c| self.min_confidence.admits(c.evidence.confidence()))
+            .filter_map(Candidate::into_matched)
+            .collect();
+        matched.sort_by(|a, b| rank_key(a, now).cmp(&rank_key(b, now)));
+        matched
+    }
+
+    /// The session a commit made at `now` in `repo_root` most likely came from.
+    pub async fn best_session(&self, repo_root: &Path, now: i64) -> Option<MatchedSession> {
+        self.sessions_for_repo(repo_root, now)
+            .await
+            .into_iter()
+            .next()
+    }
+}
+
+/// Ordering for candidates by how close their last update is to the match
+/// time; lower sorts first. Ties go to the newer log, then the log label,
+/// and logs with an unknown update time come last.
+pub fn closeness(candidate: &Candidate) -> (u64, std::cmp::Reverse<Option<i64>>, &str) {
+    (
+        candidate
+            .evidence
+            .mtime_delta_secs
+            .map_or(u64::MAX, i64::unsigned_abs),
+        std::cmp::Reverse(candidate.updated_at),
+        candidate.source_label.as_str(),
+    )
+}
+
+/// Ranking for sessions matched at `reference`; lower sorts first.
+///
+/// Sessions are ranked by, in order:
+/// 1. working directory is exactly the repository root;
+/// 2. last update closest to `reference` (unknown times rank last);
+/// 3. newest last update;
+/// 4. lowest session id, so the result never depends on discovery order.
+pub fn rank_key(
+    session: &MatchedSession,
+    reference: i64,
+) -> (bool, i64, std::cmp::Reverse<Option<i64>>, &str) {
+    (
+        !session.cwd_is_root,
+        session
+            .updated_at
+            .map_or(i64::MAX, |t| (t - reference).abs()),
+        std::cmp::Reverse(session.updated_at),
+        session.session_id.as_str(),
+    )
+}
+
+/// Commit subjects shorter than this ("wip", "fix tests") are too generic
+/// to link a session on their own.
+pub const MIN_SUBJECT_CHARS: usize = 12;
+
+/// Whether session log `content` mentions commit `subject`, either as typed
+/// or escaped the way a JSON string stores it. Short subjects never match.
+pub fn log_mentions_subject(content: &str, subject: &str) -> bool {
+    let subject = subject.trim();
+    if subject.chars().count() < MIN_SUBJECT_CHARS {
+        return false;
+    }
+    if content.contains(subject) {
+        return true;
+    }
+    let escaped = serde_json::to_string(subject).unwrap_or_default();
+    let escaped = escaped.trim_matches('"');
+    escaped != subject && content.contains(escaped)
+}
+
+async fn session_metadata(log: &SessionLog) -> SessionMetadata {
+    match &log.source {
+        SessionSource::File(path) => {
+            scanner::parse_agent_session_metadata(&log.agent_type, path).await
+        }
+        SessionSource::Inline { content, .. } => {
+            scanner::parse_agent_session_metadata_str(&log.agent_type, content)
+        }
+    }
+}
+
+/// `git -C <dir> rev-parse --show-toplevel`, or `None` outside a repository.
+async fn repo_root_of(dir: &Path) -> Option<PathBuf> {
+    let output = tokio::process::Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let root = String::from_utf8(output.stdout).ok()?;
+    Some(PathBuf::from(root.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str, cwd_is_root: bool, updated_at: Option<i64>) -> MatchedSession {
+        MatchedSession {
+            agent: AgentType::Claude,
+            session_id: id.to_string(),
+            cwd: "/repo".to_string(),
+            cwd_is_root,
+            updated_at,
+            source_label: format!("{id}.jsonl"),
+        }
+    }
+
+    fn best(mut sessions: Vec<MatchedSession>) -> Option<String> {
+        sessions.sort_by(|a, b| rank_key(a, 1_000).cmp(&rank_key(b, 1_000)));
+        sessions.into_iter().next().map(|s| s.session_id)
+    }
+
+    #[test]
+    fn ranking_prefers_root_cwd_then_closest_update() {
+        // A session in the repo root beats a fresher one in a subdirectory.
+        assert_eq!(
+            best(vec![
+                session("sub", false, Some(999)),
+                session("root", true, Some(400))
+            ])
+            .as_deref(),
+            Some("root")
+        );
+        // Between equally specific sessions, the closest update wins...
+        assert_eq!(
+            best(vec![
+                session("early", true, Some(700)),
+                session("near", true, Some(1_050))
+            ])
+            .as_deref(),
+            Some("near")
+        );
+        // ...then the newer one, then the lower id; unknown times rank last.
+        assert_eq!(
+            best(vec![
+                session("before", true, Some(900)),
+                session("after", true, Some(1_100))
+            ])
+            .as_deref(),
+            Some("after")
+        );
+        assert_eq!(
+            best(vec![
+                session("b", true, Some(900)),
+                session("a", true, Some(900)),
+                session("c", true, None)
+            ])
+            .as_deref(),
+            Some("a")
+        );
+        assert_eq!(best(Vec::new()), None);
+    }
+
+    #[test]
+    fn only_repo_candidates_with_ids_are_matched() {
+        let candidate = |session_id: Option<&str>, cwd_match| Candidate {
+            agent: AgentType::Codex,
+            session_id: session_id.map(str::to_string),
+            cwd: Some("/repo/src".to_string()),
+            updated_at: Some(1_000),
+            source_label: "rollout.jsonl".to_string(),
+            evidence: MatchEvidence {
+                cwd_match,
+                has_session_id: session_id.is_some(),
+                session_id_inferred: false,
+                mtime_delta_secs: Some(30),
+            },
+        };
+        let matched = candidate(Some("abc"), CwdMatch::Subdirectory)
+            .into_matched()
+            .unwrap();
+        assert_eq!(matched.session_id, "abc");
+        assert!(!matched.cwd_is_root);
+        assert!(
+            candidate(Some("abc"), CwdMatch::Root)
+                .into_matched()
+                .unwrap()
+                .cwd_is_root
+        );
+        // A log without a session id matches, but has no id to report.
+        assert!(candidate(None, CwdMatch::Root).is_match());
+        assert!(candidate(None, CwdMatch::Root).into_matched().is_none());
+        assert_eq!(candidate(None, CwdMatch::Root).evidence.rejection(), None);
+        assert_eq!(
+            candidate(Some("abc"), CwdMatch::OtherRepository)
+                .evidence
+                .rejection(),
+            Some("working directory is in another repository")
+        );
+        assert_eq!(
+            candidate(Some("abc"), CwdMatch::Root).evidence.rejection(),
+            None
+        );
+        assert_eq!(
+            candidate(Some("abc"), CwdMatch::Root).evidence.confidence(),
+            Some(Confidence::High)
+        );
+        assert_eq!(
+            candidate(Some("abc"), CwdMatch::Subdirectory)
+                .evidence
+                .confidence(),
+            Some(Confidence::Medium)
+        );
+        assert_eq!(
+            candidate(None, CwdMatch::Root).evidence.confidence(),
+            Some(Confidence::Medium)
+        );
+        assert!(
+            candidate(None, CwdMatch::Root)
+                .evidence
+                .clears(Confidence::Medium)
+        );
+        assert!(
+            !candidate(None, CwdMatch::Root)
+                .evidence
+                .clears(Confidence::High)
+        );
+        assert!(
+            !candidate(Some("abc"), CwdMatch::OtherRepository)
+                .evidence
+                .clears(Confidence::Low)
+        );
+        assert!(
+            candidate(Some("abc"), CwdMatch::OtherRepository)
+                .into_matched()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn minimum_confidence_admits_matches_at_or_above_it() {
+        assert_eq!(Confidence::parse(" High "), Some(Confidence::High));
+        assert_eq!(Confidence::parse("weak"), Some(Confidence::Low));
+        assert_eq!(Confidence::parse("strong"), Some(Confidence::Medium));
+        assert_eq!(Confidence::parse(" Exact "), Some(Confidence::High));
+        assert_eq!(Confidence::parse("certain"), None);
+        assert!(Confidence::Low.admits(None));
+        assert!(Confidence::Low.admits(Some(Confidence::Low)));
+        assert!(!Confidence::Medium.admits(None));
+        assert!(!Confidence::Medium.admits(Some(Confidence::Low)));
+        assert!(Confidence::Medium.admits(Some(Confidence::Medium)));
+        assert!(Confidence::Medium.admits(Some(Confidence::High)));
+        assert!(!Confidence::High.admits(Some(Confidence::Medium)));
+        assert!(Confidence::High.admits(Some(Confidence::High)));
+    }
+
+    #[test]
+    fn candidates_sort_by_closeness_to_the_match_time() {
+        let candidate = |label: &str, delta: Option<i64>| Candidate {
+            agent: AgentType::Claude,
+            session_id: None,
+            cwd: None,
+            updated_at: delta.map(|d| 1_000 - d),
+            source_label: label.to_string(),
+            evidence: MatchEvidence {
+                cwd_match: CwdMatch::Unknown,
+                has_session_id: false,
+                session_id_inferred: false,
+                mtime_delta_secs: delta,
+            },
+        };
+        let mut candidates = [
+            candidate("unknown", None),
+            candidate("old", Some(600)),
+            candidate("skewed", Some(-20)),
+            candidate("b-near", Some(30)),
+            candidate("a-near", Some(30)),
+        ];
+        candidates.sort_by(|a, b| closeness(a).cmp(&closeness(b)));
+        let labels: Vec<&str> = candidates.iter().map(|c| c.source_label.as_str()).collect();
+        assert_eq!(labels, ["skewed", "a-near", "b-near", "old", "unknown"]);
+    }
+
+    #[test]
+    fn subject_mentions_need_a_specific_subject() {
+        let log = r#"{"text":"Committed: Handle \"quoted\" names in parser"}
+{"text":"git commit -m wip"}"#;
+        assert!(log_mentions_subject(
+            log,
+            r#"Handle "quoted" names in parser"#
+        ));
+        assert!(log_mentions_subject(
+            "ran: Add retry to uploader\n",
+            "Add retry to uploader"
+        ));
+        assert!(!log_mentions_subject(log, "wip"));
+        assert!(!log_mentions_subject(log, "Add retry to uploader"));
+    }
+
+    #[tokio::test]
+    async fn parent_cwd_of_a_nested_repo_matches_only_when_enabled() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let outer = tmp.path().canonicalize().unwrap();
+        let inner = outer.join("vendor").join("lib");
+        std::fs::create_dir_all(&inner).unwrap();
+        for dir in [&outer, &inner] {
+            let status = tokio::process::Command::new("git")
+                .arg("init")
+                .arg("-q")
+                .arg(dir)
+                .status()
+                .await
+                .unwrap();
+            assert!(status.success());
+        }
+
+        // The nearest work tree around the inner repo bounds parent matches.
+        let scope = parent_cwd_scope(&inner).await.unwrap();
+        assert!(scanner::same_path(&scope, &outer));
+
+        // A session started in the outer checkout resolves to the outer repo.
+        let resolved = repo_root_of(&outer).await.unwrap();
+        let classify = |scope: Option<&Path>| {
+            CwdMatch::classify(&outer.join("vendor"), Some(&resolved), &inner, scope)
+        };
+        assert_eq!(classify(None), CwdMatch::OtherRepository);
+        assert_eq!(classify(Some(&scope)), CwdMatch::Parent);
+        assert!(classify(Some(&scope)).matches());
+
+        // Sessions inside the inner repo are unaffected by the setting.
+        let inner_root = repo_root_of(&inner).await.unwrap();
+        assert_eq!(
+            CwdMatch::classify(&inner, Some(&inner_root), &inner, Some(&scope)),
+            CwdMatch::Root
+        );
+        // Ancestors outside the enclosing work tree never match.
+        let above = outer.parent().unwrap();
+        assert_eq!(
+            CwdMatch::classify(above, None, &inner, Some(&scope)),
+            CwdMatch::NotInRepository
+        );
+        // Unrelated directories never match.
+        assert_eq!(
+            CwdMatch::classify(&inner.join("src"), None, &outer.join("other"), Some(&scope)),
+            CwdMatch::NotInRepository
+        );
+    }
+
+    #[test]
+    fn home_and_root_never_match_as_parents() {
+        let Some(home) = agents::home_dir() else {
+            return;
+        };
+        // Even with a work tree at `$HOME` (a dotfiles checkout, say).
+        let repo = home.join("src").join("app");
+        let classify =
+            |cwd: &Path| CwdMatch::classify(cwd, Some(&home), &repo, Some(home.as_path()));
+        assert_eq!(classify(&home), CwdMatch::OtherRepository);
+        assert_eq!(classify(&home.join("src")), CwdMatch::Parent);
+
+        let root = Path::new("/");
+        assert_eq!(
+            CwdMatch::classify(root, None, Path::new("/srv/app"), Some(root)),
+            CwdMatch::NotInRepository
+        );
+    }
+
+    #[tokio::test]
+    async fn repo_root_of_resolves_subdirectories() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let status = tokio::process::Command::new("git")
+            .arg("init")
+            .arg("-q")
+            .arg(tmp.path())
+            .status()
+            .await
+            .unwrap();
+        assert!(status.success());
+        let sub = tmp.path().join("src");
+        std::fs::create_dir_all(&sub).unwrap();
+
+        let root = repo_root_of(&sub).await.unwrap();
+        assert_eq!(
+            root.canonicalize().unwrap(),
+            tmp.path().canonicalize().unwrap()
+        );
+        assert_eq!(repo_root_of(&tmp.path().join("missing")).await, None);
+    }
+}
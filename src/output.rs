@@ -1,18 +1,89 @@
 use console::{Color, Term, style};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
 use std::io::{self, Write};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicU8, Ordering};
 
-static VERBOSE: AtomicBool = AtomicBool::new(false);
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+/// How much informational output the CLI prints.
+///
+/// `Quiet` hides progress and informational lines but keeps notes and
+/// failures. `Verbose` additionally prints debug detail (git commands,
+/// candidate scanning).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet = 0,
+    Normal = 1,
+    Verbose = 2,
+}
+
+/// Terminal width assumed when neither the TTY nor `COLUMNS` reports one.
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
 
 fn stderr_is_tty() -> bool {
     Term::stderr().is_term()
 }
 
-fn format_label(label: &str, color: Color, is_tty: bool) -> String {
-    if is_tty {
-        style(label).bold().fg(color).to_string()
+/// Color palette depth supported by the attached terminal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+/// Probe the terminal's color depth from `COLORTERM` and `TERM`.
+///
+/// This only picks the palette; whether color is emitted at all is still
+/// decided by `console` (`NO_COLOR`, `CLICOLOR`, dumb terminals, pipes).
+pub fn color_depth() -> ColorDepth {
+    color_depth_from_env(
+        std::env::var("COLORTERM").ok().as_deref(),
+        std::env::var("TERM").ok().as_deref(),
+    )
+}
+
+fn color_depth_from_env(colorterm: Option<&str>, term: Option<&str>) -> ColorDepth {
+    let term = term.unwrap_or_default();
+    if term == "dumb" {
+        return ColorDepth::Ansi16;
+    }
+    let colorterm = colorterm.unwrap_or_default().to_ascii_lowercase();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        ColorDepth::TrueColor
+    } else if term.contains("256color") {
+        ColorDepth::Ansi256
     } else {
-        label.to_string()
+        ColorDepth::Ansi16
+    }
+}
+
+/// Richer RGB and 256-palette accents for the basic label colors.
+fn accent_for(color: Color) -> Option<((u8, u8, u8), u8)> {
+    match color {
+        Color::Cyan => Some(((34, 211, 238), 45)),
+        Color::Green => Some(((74, 222, 128), 78)),
+        Color::Red => Some(((248, 113, 113), 203)),
+        Color::Yellow => Some(((250, 204, 21), 220)),
+        _ => None,
+    }
+}
+
+/// Wrap `text` in a 24-bit foreground color escape sequence.
+pub fn paint_rgb(text: &str, (r, g, b): (u8, u8, u8), bold: bool) -> String {
+    let weight = if bold { "1;" } else { "" };
+    format!("\x1b[{weight}38;2;{r};{g};{b}m{text}\x1b[0m")
+}
+
+fn format_label(label: &str, color: Color, is_tty: bool) -> String {
+    if !is_tty {
+        return label.to_string();
+    }
+    let accent = accent_for(color).filter(|_| console::colors_enabled_stderr());
+    match (color_depth(), accent) {
+        (ColorDepth::TrueColor, Some((rgb, _))) => paint_rgb(label, rgb, true),
+        (ColorDepth::Ansi256, Some((_, idx))) => style(label).bold().color256(idx).to_string(),
+        _ => style(label).bold().fg(color).to_string(),
     }
 }
 
@@ -57,10 +128,16 @@ pub fn detail_to_with_tty(w: &mut dyn Write, msg: &str, is_tty: bool) {
 }
 
 pub fn action(label: &str, msg: &str) {
+    if is_quiet() {
+        return;
+    }
     action_to_with_tty(&mut io::stderr(), label, msg, stderr_is_tty());
 }
 
 pub fn success(label: &str, msg: &str) {
+    if is_quiet() {
+        return;
+    }
     success_to_with_tty(&mut io::stderr(), label, msg, stderr_is_tty());
 }
 
@@ -73,9 +150,105 @@ pub fn note(msg: &str) {
 }
 
 pub fn detail(msg: &str) {
+    if is_quiet() {
+        return;
+    }
     detail_to_with_tty(&mut io::stderr(), msg, stderr_is_tty());
 }
 
+/// Gap between table columns on a TTY.
+const TABLE_COLUMN_GAP: usize = 2;
+
+/// Narrowest a table column will be squeezed to when fitting the terminal.
+const TABLE_MIN_COLUMN_WIDTH: usize = 4;
+
+/// Write an aligned table to `w`.
+///
+/// On a TTY, columns are padded to a common width, headers are bold, and the
+/// widest columns are shrunk (with `…` truncation) to fit `max_width`. Off a
+/// TTY the table is emitted as tab-separated lines so it stays easy to parse.
+pub fn table_to_with_tty(
+    w: &mut dyn Write,
+    headers: &[&str],
+    rows: &[Vec<String>],
+    is_tty: bool,
+    max_width: Option<usize>,
+) {
+    if !is_tty {
+        let _ = writeln!(w, "{}", headers.join("\t"));
+        for row in rows {
+            let _ = writeln!(w, "{}", row.join("\t"));
+        }
+        return;
+    }
+
+    let widths = table_column_widths(headers, rows, max_width);
+    let header_cells: Vec<String> = headers.iter().map(|h| h.to_string()).collect();
+    let header = format_table_row(&header_cells, &widths);
+    let _ = writeln!(w, "{}", style(header).bold());
+    for row in rows {
+        let _ = writeln!(w, "{}", format_table_row(row, &widths));
+    }
+}
+
+pub fn table(headers: &[&str], rows: &[Vec<String>]) {
+    let is_tty = stderr_is_tty();
+    let max_width = is_tty.then(terminal_width);
+    table_to_with_tty(&mut io::stderr(), headers, rows, is_tty, max_width);
+}
+
+/// Compute display widths for each column, shrinking the widest columns
+/// until the table fits within `max_width` (if given).
+fn table_column_widths(
+    headers: &[&str],
+    rows: &[Vec<String>],
+    max_width: Option<usize>,
+) -> Vec<usize> {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+    for row in rows {
+        for (idx, cell) in row.iter().enumerate() {
+            let len = cell.chars().count();
+            match widths.get_mut(idx) {
+                Some(width) => *width = (*width).max(len),
+                None => widths.push(len),
+            }
+        }
+    }
+
+    let Some(max_width) = max_width else {
+        return widths;
+    };
+    let gaps = TABLE_COLUMN_GAP * widths.len().saturating_sub(1);
+    while widths.iter().sum::<usize>() + gaps > max_width {
+        let Some((idx, widest)) = widths
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by_key(|(_, width)| *width)
+        else {
+            break;
+        };
+        if widest <= TABLE_MIN_COLUMN_WIDTH {
+            break;
+        }
+        widths[idx] = widest - 1;
+    }
+    widths
+}
+
+fn format_table_row(cells: &[String], widths: &[usize]) -> String {
+    let gap = " ".repeat(TABLE_COLUMN_GAP);
+    let padded: Vec<String> = widths
+        .iter()
+        .enumerate()
+        .map(|(idx, width)| {
+            let cell = fit_to_width(cells.get(idx).map(String::as_str).unwrap_or(""), *width);
+            format!("{cell:<width$}")
+        })
+        .collect();
+    padded.join(&gap).trim_end().to_string()
+}
+
 /// Format a detail message for display without writing it.
 /// Used when output needs to be routed through a progress bar via `pb.println()`.
 #[allow(dead_code)]
@@ -87,14 +260,337 @@ pub fn format_detail(msg: &str) -> String {
     }
 }
 
+/// Transient status line for a single long-running step.
+///
+/// On a TTY this animates a spinner on stderr and replaces it with a `✓`/`✗`
+/// line when finished. Otherwise it prints plain start and end lines.
+pub struct Spinner {
+    pb: Option<ProgressBar>,
+    silent: bool,
+}
+
+impl Spinner {
+    pub fn start(msg: &str) -> Self {
+        if is_quiet() {
+            return Self {
+                pb: None,
+                silent: true,
+            };
+        }
+        if !stderr_is_tty() {
+            let _ = writeln!(io::stderr(), "{msg}");
+            return Self {
+                pb: None,
+                silent: false,
+            };
+        }
+        let pb = ProgressBar::new_spinner();
+        pb.set_draw_target(ProgressDrawTarget::stderr());
+        pb.set_style(
+            ProgressStyle::with_template("{spinner} {msg}")
+                .unwrap_or_else(|_| ProgressStyle::default_spinner()),
+        );
+        pb.enable_steady_tick(std::time::Duration::from_millis(100));
+        // Leave room for the spinner glyph so the line never wraps.
+        pb.set_message(fit_to_width(msg, terminal_width().saturating_sub(2)));
+        Self {
+            pb: Some(pb),
+            silent: false,
+        }
+    }
+
+    pub fn finish_ok(self, msg: &str) {
+        self.finish_with_mark("✓", Color::Green, msg);
+    }
+
+    pub fn finish_err(self, msg: &str) {
+        self.finish_with_mark("✗", Color::Red, msg);
+    }
+
+    /// Remove the spinner without printing a completion line.
+    pub fn finish_and_clear(self) {
+        if let Some(pb) = self.pb {
+            pb.finish_and_clear();
+        }
+    }
+
+    fn finish_with_mark(self, mark: &str, color: Color, msg: &str) {
+        match self.pb {
+            Some(pb) => {
+                let mark = style(mark).fg(color).to_string();
+                pb.finish_with_message(format!("{mark} {msg}"));
+            }
+            None if !self.silent => {
+                let _ = writeln!(io::stderr(), "{mark} {msg}");
+            }
+            None => {}
+        }
+    }
+}
+
+/// Lightweight `[pos/total] message` counter for long-running loops.
+///
+/// On a TTY the counter redraws a single line in place. When stderr is not a
+/// terminal it prints the first item, every ~10% step, and the last item, so
+/// CI logs show forward progress without one line per item.
+pub struct ProgressCounter {
+    total: u64,
+    pos: u64,
+    msg: String,
+    is_tty: bool,
+    step: u64,
+    silent: bool,
+}
+
+impl ProgressCounter {
+    pub fn new(total: u64, msg: &str) -> Self {
+        let mut counter = Self::with_tty(total, msg, stderr_is_tty());
+        counter.silent = is_quiet();
+        counter
+    }
+
+    pub fn with_tty(total: u64, msg: &str, is_tty: bool) -> Self {
+        Self {
+            total,
+            pos: 0,
+            msg: msg.to_string(),
+            is_tty,
+            step: (total / 10).max(1),
+            silent: false,
+        }
+    }
+
+    /// Advance by one item and redraw to stderr.
+    pub fn inc(&mut self) {
+        if self.silent {
+            return;
+        }
+        self.inc_to(&mut io::stderr());
+    }
+
+    pub fn inc_to(&mut self, w: &mut dyn Write) {
+        self.pos = (self.pos + 1).min(self.total);
+        let line = format!("[{}/{}] {}…", self.pos, self.total, self.msg);
+        if self.is_tty {
+            // A wrapped line would break the `\r` redraw.
+            let line = fit_to_width(&line, terminal_width().saturating_sub(1));
+            let _ = write!(w, "\r\x1b[2K{}", style(line).dim());
+            let _ = w.flush();
+        } else if self.pos == 1 || self.pos == self.total || self.pos.is_multiple_of(self.step) {
+            let _ = writeln!(w, "{line}");
+        }
+    }
+
+    /// Clear the in-place line on a TTY. No-op otherwise.
+    pub fn finish(self) {
+        self.finish_to(&mut io::stderr());
+    }
+
+    pub fn finish_to(self, w: &mut dyn Write) {
+        if self.is_tty && self.pos > 0 {
+            let _ = write!(w, "\r\x1b[2K");
+            let _ = w.flush();
+        }
+    }
+}
+
+/// Width of the attached terminal in columns.
+///
+/// Probes the stderr TTY first, then the `COLUMNS` environment variable, and
+/// falls back to 80 columns.
+pub fn terminal_width() -> usize {
+    let probed = Term::stderr().size_checked().map(|(_, cols)| cols as usize);
+    terminal_width_from(probed, std::env::var("COLUMNS").ok().as_deref())
+}
+
+fn terminal_width_from(probed: Option<usize>, columns_env: Option<&str>) -> usize {
+    probed
+        .filter(|cols| *cols > 0)
+        .or_else(|| {
+            columns_env
+                .and_then(|v| v.trim().parse::<usize>().ok())
+                .filter(|cols| *cols > 0)
+        })
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
+/// Truncate `msg` to at most `width` display columns, ending with `…` when
+/// cut. ANSI styling in `msg` is preserved and not counted toward the width.
+pub fn fit_to_width(msg: &str, width: usize) -> String {
+    if console::measure_text_width(msg) <= width {
+        return msg.to_string();
+    }
+    console::truncate_str(msg, width, "…").into_owned()
+}
+
 pub fn is_stderr_tty() -> bool {
     stderr_is_tty()
 }
 
-pub fn set_verbose(enabled: bool) {
-    VERBOSE.store(enabled, Ordering::Relaxed);
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::Relaxed);
+}
+
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        0 => Verbosity::Quiet,
+        2 => Verbosity::Verbose,
+        _ => Verbosity::Normal,
+    }
 }
 
 pub fn is_verbose() -> bool {
-    VERBOSE.load(Ordering::Relaxed)
+    verbosity() == Verbosity::Verbose
+}
+
+pub fn is_quiet() -> bool {
+    verbosity() == Verbosity::Quiet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_depth_from_env_detects_palettes() {
+        assert_eq!(
+            color_depth_from_env(Some("truecolor"), Some("xterm-256color")),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            color_depth_from_env(Some("24bit"), None),
+            ColorDepth::TrueColor
+        );
+        assert_eq!(
+            color_depth_from_env(None, Some("xterm-256color")),
+            ColorDepth::Ansi256
+        );
+        assert_eq!(
+            color_depth_from_env(None, Some("xterm")),
+            ColorDepth::Ansi16
+        );
+        assert_eq!(
+            color_depth_from_env(Some("truecolor"), Some("dumb")),
+            ColorDepth::Ansi16
+        );
+    }
+
+    #[test]
+    fn paint_rgb_emits_24bit_escape() {
+        assert_eq!(
+            paint_rgb("ok", (1, 2, 3), false),
+            "\x1b[38;2;1;2;3mok\x1b[0m"
+        );
+        assert_eq!(
+            paint_rgb("ok", (1, 2, 3), true),
+            "\x1b[1;38;2;1;2;3mok\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn table_column_widths_use_widest_cell() {
+        let rows = vec![
+            vec!["1".to_string(), "claude-code".to_string()],
+            vec!["12345".to_string(), "x".to_string()],
+        ];
+        assert_eq!(
+            table_column_widths(&["start", "agent"], &rows, None),
+            vec![5, 11]
+        );
+        assert_eq!(table_column_widths(&["id", "agent"], &[], None), vec![2, 5]);
+    }
+
+    #[test]
+    fn table_column_widths_shrink_widest_to_fit() {
+        let rows = vec![vec!["abc".to_string(), "a".repeat(40)]];
+        let widths = table_column_widths(&["id", "label"], &rows, Some(20));
+        assert_eq!(widths, vec![3, 15]);
+        assert!(widths.iter().sum::<usize>() + TABLE_COLUMN_GAP <= 20);
+    }
+
+    #[test]
+    fn table_column_widths_stop_at_minimum() {
+        let rows = vec![vec!["abcdefgh".to_string(), "abcdefgh".to_string()]];
+        let widths = table_column_widths(&["a", "b"], &rows, Some(3));
+        assert_eq!(widths, vec![TABLE_MIN_COLUMN_WIDTH, TABLE_MIN_COLUMN_WIDTH]);
+    }
+
+    #[test]
+    fn table_tty_pads_and_truncates() {
+        let mut buf = Vec::new();
+        let rows = vec![vec!["1".to_string(), "a-very-long-label".to_string()]];
+        table_to_with_tty(&mut buf, &["n", "label"], &rows, true, Some(12));
+        let out = console::strip_ansi_codes(&String::from_utf8(buf).unwrap()).to_string();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines, vec!["n  label", "1  a-very-l…"]);
+    }
+
+    #[test]
+    fn table_non_tty_is_tab_separated() {
+        let mut buf = Vec::new();
+        let rows = vec![vec!["1".to_string(), "codex".to_string()]];
+        table_to_with_tty(&mut buf, &["start", "agent"], &rows, false, Some(5));
+        assert_eq!(String::from_utf8(buf).unwrap(), "start\tagent\n1\tcodex\n");
+    }
+
+    #[test]
+    fn terminal_width_prefers_probe_then_columns_env() {
+        assert_eq!(terminal_width_from(Some(120), Some("60")), 120);
+        assert_eq!(terminal_width_from(None, Some("60")), 60);
+        assert_eq!(
+            terminal_width_from(Some(0), Some("bogus")),
+            DEFAULT_TERMINAL_WIDTH
+        );
+        assert_eq!(terminal_width_from(None, None), DEFAULT_TERMINAL_WIDTH);
+    }
+
+    #[test]
+    fn fit_to_width_truncates_with_ellipsis() {
+        assert_eq!(fit_to_width("short", 10), "short");
+        assert_eq!(fit_to_width("exactly-10", 10), "exactly-10");
+        assert_eq!(fit_to_width("a status message", 8), "a statu…");
+        let styled = style("styled label").bold().force_styling(true).to_string();
+        assert_eq!(
+            console::strip_ansi_codes(&fit_to_width(&styled, 7)),
+            "styled…"
+        );
+    }
+
+    #[test]
+    fn labels_stay_plain_without_tty() {
+        let mut buf = Vec::new();
+        success_to_with_tty(&mut buf, "Done", "all good", false);
+        assert_eq!(String::from_utf8(buf).unwrap(), "Done all good\n");
+    }
+
+    #[test]
+    fn progress_counter_non_tty_prints_periodic_lines() {
+        let mut buf = Vec::new();
+        let mut counter = ProgressCounter::with_tty(25, "attaching", false);
+        for _ in 0..25 {
+            counter.inc_to(&mut buf);
+        }
+        counter.finish_to(&mut buf);
+
+        let out = String::from_utf8(buf).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.first(), Some(&"[1/25] attaching…"));
+        assert_eq!(lines.last(), Some(&"[25/25] attaching…"));
+        assert!(lines.len() < 25, "expected throttled output, got {lines:?}");
+        assert!(!out.contains('\r'));
+    }
+
+    #[test]
+    fn progress_counter_tty_redraws_in_place_and_clears() {
+        let mut buf = Vec::new();
+        let mut counter = ProgressCounter::with_tty(2, "attaching", true);
+        counter.inc_to(&mut buf);
+        counter.inc_to(&mut buf);
+        counter.finish_to(&mut buf);
+
+        let out = String::from_utf8(buf).unwrap();
+        assert!(!out.contains('\n'));
+        assert!(out.contains("[2/2] attaching…"));
+        assert!(out.ends_with("\r\x1b[2K"));
+    }
 }
@@ -1,6 +1,7 @@
 //! Push decision and sync logic for canonical session refs.
 
 use crate::git;
+use crate::settings::Settings;
 use anyhow::{Context, Result};
 use std::collections::BTreeMap;
 use std::path::Path;
@@ -12,24 +13,61 @@ const SESSION_REFS: [&str; 3] = [
     git::SESSION_INDEX_COMMITTER_REF,
 ];
 
+/// Global kill switch. When set to `never` in global git config, session refs
+/// are never pushed, regardless of any repo-local setting.
+pub const AUTOPUSH_KEY: &str = "ai.cadence.autopush";
+
 /// Determine whether session refs should be pushed for a specific remote.
-pub async fn should_push_remote(remote: &str) -> bool {
+///
+/// Precedence: a global `ai.cadence.autopush = never` wins over everything,
+/// then `ai.cadence.remote` (when set) must name this remote, and finally the
+/// remote must exist and pass the org filter.
+pub async fn should_push_remote(remote: &str, settings: &Settings) -> bool {
     if remote.is_empty() || remote == "." {
         return false;
     }
 
+    if settings.autopush_never {
+        return false;
+    }
+
+    if let Some(pinned) = &settings.session_remote
+        && pinned != remote
+    {
+        return false;
+    }
+
     match git::remote_url(remote).await {
         Ok(Some(_)) => {}
         _ => return false,
     }
 
-    check_org_filter_remote(remote).await
+    check_org_filter_remote(remote, settings.org.as_deref()).await
 }
 
-pub async fn try_push_remote_at_quiet(repo: &Path, remote: &str) -> Result<()> {
+pub async fn try_push_remote_at_quiet(
+    repo: &Path,
+    remote: &str,
+    settings: &Settings,
+) -> Result<()> {
+    if settings.autopush_never {
+        info!(remote = %remote, "autopush=never; skipping session ref sync");
+        return Ok(());
+    }
     sync_session_refs_for_remote_at(repo, remote).await
 }
 
+/// Whether the global `ai.cadence.autopush` kill switch is set to `never`.
+///
+/// Only global config is read, so repo-local config cannot re-enable pushing.
+pub async fn autopush_disabled_globally() -> bool {
+    Settings::load(None).await.autopush_never
+}
+
+pub(crate) fn is_autopush_never(value: &str) -> bool {
+    value.trim().eq_ignore_ascii_case("never")
+}
+
 pub async fn sync_session_refs_for_remote_at(repo: &Path, remote: &str) -> Result<()> {
     if remote.is_empty() || remote == "." {
         anyhow::bail!("invalid remote name");
@@ -471,27 +509,39 @@ async fn merge_index_shard_blobs(repo: &Path, local_sha: &str, remote_sha: &str)
     let remote_blob = git::read_blob_at(Some(repo), remote_sha).await?;
     let local_text = String::from_utf8(local_blob)?;
     let remote_text = String::from_utf8(remote_blob)?;
-
-    let mut seen = std::collections::BTreeSet::<String>::new();
-    let mut merged_lines = Vec::<String>::new();
-    for line in local_text.lines().chain(remote_text.lines()) {
-        let trimmed = line.trim();
-        if trimmed.is_empty() {
-            continue;
-        }
-        if seen.insert(trimmed.to_string()) {
-            merged_lines.push(trimmed.to_string());
-        }
-    }
-
-    if merged_lines.is_empty() {
-        return git::store_blob_at(Some(repo), b"").await;
-    }
-
-    let merged_text = format!("{}\n", merged_lines.join("\n"));
+    let merged_text = merge_index_shard_text(&local_text, &remote_text);
     git::store_blob_at(Some(repo), merged_text.as_bytes()).await
 }
 
+/// Union of two index shards' entries in canonical order: by agent, then
+/// session uid, then the raw line.
+///
+/// The order does not depend on which side is local, so every clone that
+/// merges the same two shards writes the same blob, and merging a merged
+/// shard again changes nothing. Readers sort entries themselves.
+fn merge_index_shard_text(local: &str, remote: &str) -> String {
+    let lines: std::collections::BTreeSet<&str> = local
+        .lines()
+        .chain(remote.lines())
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let mut keyed: Vec<((String, String), &str)> = lines
+        .into_iter()
+        .map(|line| {
+            let key = serde_json::from_str::<crate::note::IndexEntry>(line)
+                .map(|entry| (entry.agent, entry.session_uid))
+                .unwrap_or_default();
+            (key, line)
+        })
+        .collect();
+    keyed.sort();
+    keyed
+        .into_iter()
+        .map(|(_, line)| format!("{line}\n"))
+        .collect()
+}
+
 async fn ref_map_from_ref(repo: &Path, ref_name: &str) -> Result<BTreeMap<String, String>> {
     if !git::ref_exists_at(Some(repo), ref_name).await? {
         return Ok(BTreeMap::new());
@@ -573,10 +623,9 @@ fn is_ref_push_race(stderr_trim: &str, ref_name: &str) -> bool {
 
 /// Check the org filter: if a global org is configured, verify that the
 /// selected remote belongs to that org.
-pub async fn check_org_filter_remote(remote: &str) -> bool {
-    let configured_org = match git::config_get_global("ai.cadence.org").await {
-        Ok(Some(org)) => org,
-        _ => return true,
+async fn check_org_filter_remote(remote: &str, configured_org: Option<&str>) -> bool {
+    let Some(configured_org) = configured_org else {
+        return true;
     };
 
     let url = match git::remote_url(remote).await {
@@ -589,12 +638,13 @@ pub async fn check_org_filter_remote(remote: &str) -> bool {
         None => return false,
     };
 
-    remote_org.eq_ignore_ascii_case(&configured_org)
+    remote_org.eq_ignore_ascii_case(configured_org)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serial_test::serial;
     use std::collections::BTreeMap;
     use tempfile::TempDir;
 
@@ -702,6 +752,94 @@ mod tests {
         assert!(merged_text.contains("\"session_uid\":\"remote\""));
     }
 
+    #[test]
+    fn merged_index_shards_are_canonical_and_idempotent() {
+        let entry = |agent: &str, uid: &str| {
+            format!(
+                r#"{{"session_uid":"{uid}","session_blob_sha":"b-{uid}","agent":"{agent}","ingested_at":"1"}}"#
+            )
+        };
+        let local = format!(
+            "{}\n{}\n",
+            entry("codex", "s-2"),
+            entry("claude-code", "s-9")
+        );
+        let remote = format!(
+            "{}\n\n{}\n{}\n",
+            entry("codex", "s-1"),
+            entry("codex", "s-2"),
+            entry("claude-code", "s-3")
+        );
+
+        let merged = merge_index_shard_text(&local, &remote);
+        assert_eq!(
+            merged,
+            format!(
+                "{}\n{}\n{}\n{}\n",
+                entry("claude-code", "s-3"),
+                entry("claude-code", "s-9"),
+                entry("codex", "s-1"),
+                entry("codex", "s-2")
+            )
+        );
+        // Either clone may be the local side.
+        assert_eq!(merge_index_shard_text(&remote, &local), merged);
+        // Merging again is byte-identical.
+        assert_eq!(merge_index_shard_text(&merged, &merged), merged);
+        assert_eq!(merge_index_shard_text(&merged, &local), merged);
+        assert_eq!(merge_index_shard_text("", "\n"), "");
+    }
+
+    #[test]
+    fn autopush_never_is_case_insensitive() {
+        assert!(is_autopush_never("never"));
+        assert!(is_autopush_never(" NEVER "));
+        assert!(!is_autopush_never("true"));
+        assert!(!is_autopush_never("false"));
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn global_autopush_never_overrides_local_true() {
+        let (local, remote) = init_repo_with_remote().await;
+        run_git(local.path(), &["config", AUTOPUSH_KEY, "true"]).await;
+        let mut map = BTreeMap::new();
+        let blob = git::store_blob_at(Some(local.path()), br#"{"k":"v"}"#)
+            .await
+            .expect("store blob");
+        map.insert("item.json".to_string(), blob);
+        write_ref_map(local.path(), git::SESSION_DATA_REF, &map).await;
+
+        let global_config = local.path().join("fake-global-gitconfig");
+        tokio::fs::write(&global_config, "[ai \"cadence\"]\n    autopush = never\n")
+            .await
+            .expect("write global config");
+        let original_global = std::env::var("GIT_CONFIG_GLOBAL").ok();
+        unsafe {
+            std::env::set_var("GIT_CONFIG_GLOBAL", &global_config);
+        }
+
+        let disabled = autopush_disabled_globally().await;
+        let settings = Settings::load(Some(local.path())).await;
+        let push_result = try_push_remote_at_quiet(local.path(), "origin", &settings).await;
+
+        unsafe {
+            match original_global {
+                Some(g) => std::env::set_var("GIT_CONFIG_GLOBAL", g),
+                None => std::env::remove_var("GIT_CONFIG_GLOBAL"),
+            }
+        }
+
+        assert!(disabled);
+        push_result.expect("skipped push should not error");
+        assert!(
+            !git::ref_exists_at(Some(remote.path()), git::SESSION_DATA_REF)
+                .await
+                .expect("ref exists check"),
+            "remote should not receive session refs"
+        );
+    }
+
     #[tokio::test]
     async fn sync_session_refs_returns_error_when_remote_hash_lookup_fails() {
         let repo = init_repo().await;
@@ -0,0 +1,374 @@
+//! Repository-local settings from a committed `.cadence.toml`.
+//!
+//! The file sits at the repository root and holds a flat table of Cadence
+//! settings, named like their git config keys without the `ai.cadence.`
+//! prefix (kebab-case works too):
+//!
+//! ```toml
+//! redact-secrets = true
+//! window-before = 600
+//! trailerFormat = "full"
+//! redact = ['password=(\S+)']
+//! ```
+//!
+//! Values here take precedence over git config for the same key. Only the
+//! keys in [`REPO_KEYS`] are honored: a checked-out repository must not be
+//! able to redirect where session data is pushed or reported. Privacy
+//! controls and anything that widens matching can only be tightened: a
+//! repository may turn Cadence, parent-directory matching or the subject
+//! fallback off, narrow the match windows or raise `minConfidence`, but not
+//! the reverse. Its `redact` patterns and `skipAuthors` entries add to the
+//! user's own (see [`Merge`]). The file is parsed once per process; a
+//! malformed file is reported and ignored.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use crate::{config, log, note, settings};
+use cadence_cli::matcher::Confidence;
+
+pub const REPO_CONFIG_FILE: &str = ".cadence.toml";
+
+const KEY_PREFIX: &str = "ai.cadence.";
+
+/// How a repository's value for a key combines with the user's git config.
+#[derive(Clone, Copy)]
+enum Merge {
+    /// The repository's value wins.
+    Override,
+    /// The repository's value is used only when it makes Cadence stricter
+    /// than the user's value (the second argument, `None` when unset); any
+    /// other value is ignored and the user's setting stands.
+    Tighten(fn(&str, Option<&str>) -> bool),
+    /// The repository's values are added after the user's.
+    Append,
+}
+
+/// Settings a repository may set for itself, without [`KEY_PREFIX`].
+const REPO_KEYS: &[(&str, Merge)] = &[
+    ("enabled", Merge::Tighten(is_false)),
+    ("hookTimeoutMs", Merge::Override),
+    ("injectTrailer", Merge::Tighten(is_false)),
+    ("matchParentCwd", Merge::Tighten(is_false)),
+    ("minConfidence", Merge::Tighten(raises_confidence)),
+    ("recheckDelayMs", Merge::Override),
+    ("redact", Merge::Append),
+    ("redactSecrets", Merge::Tighten(is_true)),
+    ("sessionBody", Merge::Tighten(is_reference)),
+    ("skipAuthors", Merge::Append),
+    ("subjectFallback", Merge::Tighten(is_false)),
+    ("timeSource", Merge::Override),
+    ("trailerFormat", Merge::Override),
+    ("windowAfter", Merge::Tighten(narrows_window_after)),
+    ("windowBefore", Merge::Tighten(narrows_window_before)),
+];
+
+fn is_false(value: &str, _user: Option<&str>) -> bool {
+    config::parse_bool_value(value).is_ok_and(|v| !v)
+}
+
+fn is_true(value: &str, _user: Option<&str>) -> bool {
+    config::parse_bool_value(value).unwrap_or(false)
+}
+
+fn is_reference(value: &str, _user: Option<&str>) -> bool {
+    note::SessionBody::parse(value) == Some(note::SessionBody::Reference)
+}
+
+fn window_secs(value: &str) -> Option<i64> {
+    value.trim().parse::<i64>().ok().filter(|secs| *secs >= 0)
+}
+
+/// `windowBefore` no wider than the user's, which defaults to
+/// [`settings::POST_COMMIT_MATCH_WINDOW_SECS`].
+fn narrows_window_before(value: &str, user: Option<&str>) -> bool {
+    let user = user
+        .and_then(window_secs)
+        .unwrap_or(settings::POST_COMMIT_MATCH_WINDOW_SECS);
+    window_secs(value).is_some_and(|secs| secs <= user)
+}
+
+/// `windowAfter` no wider than the user's; unset means unbounded.
+fn narrows_window_after(value: &str, user: Option<&str>) -> bool {
+    window_secs(value).is_some_and(|secs| user.and_then(window_secs).is_none_or(|u| secs <= u))
+}
+
+fn raises_confidence(value: &str, user: Option<&str>) -> bool {
+    let user = user.and_then(Confidence::parse).unwrap_or_default();
+    Confidence::parse(value).is_some_and(|min| min >= user)
+}
+
+/// Parsed `.cadence.toml`, keyed by full git config key.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct RepoConfig {
+    values: HashMap<String, Vec<String>>,
+}
+
+impl RepoConfig {
+    /// Parse the file's contents. Keys outside [`REPO_KEYS`] and values that
+    /// are not strings, numbers, booleans or arrays of those are skipped
+    /// with a warning.
+    pub fn parse(content: &str) -> Result<Self, toml::de::Error> {
+        let table: toml::Table = toml::from_str(content)?;
+        let mut values = HashMap::new();
+        for (name, value) in table {
+            let Some(key) = canonical_key(&name) else {
+                log::warn(&format!(
+                    "{REPO_CONFIG_FILE}: ignoring unsupported key {name:?}"
+                ));
+                continue;
+            };
+            let items = match value {
+                toml::Value::Array(items) => items.iter().map(scalar_string).collect(),
+                other => scalar_string(&other).map(|v| vec![v]),
+            };
+            match items {
+                Some(items) => {
+                    values.insert(format!("{KEY_PREFIX}{key}"), items);
+                }
+                None => log::warn(&format!(
+                    "{REPO_CONFIG_FILE}: ignoring {name:?}: expected a string, number or boolean"
+                )),
+            }
+        }
+        Ok(Self { values })
+    }
+
+    /// Every value for `key`, like `git config --get-all`.
+    pub fn get_all(&self, key: &str) -> Option<&[String]> {
+        self.values.get(key).map(Vec::as_slice)
+    }
+}
+
+/// `window-before` and `windowBefore` both name `windowBefore`.
+fn canonical_key(name: &str) -> Option<&'static str> {
+    let mut camel = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.trim().chars() {
+        if c == '-' || c == '_' {
+            upper = true;
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    REPO_KEYS
+        .iter()
+        .map(|(key, _)| *key)
+        .find(|key| *key == camel)
+}
+
+fn merge_for(key: &str) -> Option<Merge> {
+    let name = key.strip_prefix(KEY_PREFIX)?;
+    REPO_KEYS
+        .iter()
+        .find(|(repo_key, _)| *repo_key == name)
+        .map(|(_, merge)| *merge)
+}
+
+fn scalar_string(value: &toml::Value) -> Option<String> {
+    match value {
+        toml::Value::String(s) => Some(s.clone()),
+        toml::Value::Integer(i) => Some(i.to_string()),
+        toml::Value::Float(f) => Some(f.to_string()),
+        toml::Value::Boolean(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn cache() -> &'static Mutex<HashMap<PathBuf, Arc<RepoConfig>>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, Arc<RepoConfig>>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// The `.cadence.toml` settings for the repository rooted at `repo_root`;
+/// empty when there is no file or it cannot be parsed.
+pub fn load(repo_root: &Path) -> Arc<RepoConfig> {
+    let mut cache = cache().lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .entry(repo_root.to_path_buf())
+        .or_insert_with(|| Arc::new(read(&repo_root.join(REPO_CONFIG_FILE))))
+        .clone()
+}
+
+fn read(path: &Path) -> RepoConfig {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return RepoConfig::default();
+    };
+    RepoConfig::parse(&content).unwrap_or_else(|e| {
+        log::warn(&format!("ignoring malformed {}: {e}", path.display()));
+        RepoConfig::default()
+    })
+}
+
+/// The root of the repository containing the current directory, found by
+/// looking for `.git` so that no git process is needed.
+fn current_repo_root() -> Option<&'static Path> {
+    static ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+    ROOT.get_or_init(|| {
+        let cwd = std::env::current_dir().ok()?;
+        cwd.ancestors()
+            .find(|dir| dir.join(".git").exists())
+            .map(Path::to_path_buf)
+    })
+    .as_deref()
+}
+
+fn file_values(repo: Option<&Path>, key: &str) -> Option<Vec<String>> {
+    let root = match repo {
+        Some(repo) => repo,
+        None => current_repo_root()?,
+    };
+    load(root).get_all(key).map(<[String]>::to_vec)
+}
+
+/// `.cadence.toml` values for `key` in `repo` (or the current repository)
+/// that replace `user`, the user's own value: keys a repository may
+/// override, and tighten-only keys set to something stricter than `user`.
+pub fn lookup(repo: Option<&Path>, key: &str, user: Option<&str>) -> Option<Vec<String>> {
+    match merge_for(key)? {
+        Merge::Override => file_values(repo, key),
+        Merge::Tighten(stricter) => {
+            file_values(repo, key).filter(|values| values.last().is_some_and(|v| stricter(v, user)))
+        }
+        Merge::Append => None,
+    }
+}
+
+/// `.cadence.toml` values for `key` to add after the user's own, for keys
+/// like `redact` that a repository may only extend.
+pub fn additions(repo: Option<&Path>, key: &str) -> Vec<String> {
+    match merge_for(key) {
+        Some(Merge::Append) => file_values(repo, key).unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git;
+    use crate::settings::Settings;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parses_supported_keys_in_either_case() {
+        let config = RepoConfig::parse(
+            r#"
+            enabled = false
+            window-before = 600
+            trailerFormat = "full"
+            redact = ['token=(\S+)', 'secret']
+            remote = "attacker"
+            "#,
+        )
+        .unwrap();
+        let get = |key| config.get_all(key)?.last().map(String::as_str);
+        assert_eq!(get("ai.cadence.enabled"), Some("false"));
+        assert_eq!(get("ai.cadence.windowBefore"), Some("600"));
+        assert_eq!(get("ai.cadence.trailerFormat"), Some("full"));
+        assert_eq!(
+            config.get_all("ai.cadence.redact"),
+            Some(&[r"token=(\S+)".to_string(), "secret".to_string()][..])
+        );
+        assert_eq!(get("ai.cadence.remote"), None);
+        assert!(RepoConfig::parse("enabled = ").is_err());
+    }
+
+    #[tokio::test]
+    async fn repo_file_overrides_git_config_and_malformed_files_are_ignored() {
+        let repo = TempDir::new().unwrap();
+        let root = repo.path().join("repo");
+        let output = git::run_git_output_at(None, &["init", "-q", root.to_str().unwrap()], &[])
+            .await
+            .unwrap();
+        assert!(output.status.success());
+        git::run_git_output_at(
+            Some(&root),
+            &["config", "ai.cadence.windowBefore", "900"],
+            &[],
+        )
+        .await
+        .unwrap();
+        git::run_git_output_at(
+            Some(&root),
+            &["config", "ai.cadence.timeSource", "author"],
+            &[],
+        )
+        .await
+        .unwrap();
+        std::fs::write(root.join(REPO_CONFIG_FILE), "window-before = 120\n").unwrap();
+
+        let settings = Settings::load(Some(&root)).await;
+        assert_eq!(settings.window_before_secs, 120);
+        // Keys the file does not set still come from git config.
+        assert_eq!(settings.time_source, git::TimeSource::Author);
+
+        let broken = repo.path().join("broken");
+        std::fs::create_dir_all(&broken).unwrap();
+        std::fs::write(broken.join(REPO_CONFIG_FILE), "window-before = [unclosed").unwrap();
+        assert_eq!(*load(&broken), RepoConfig::default());
+    }
+
+    #[tokio::test]
+    async fn repo_file_can_only_tighten_privacy_settings() {
+        let repo = TempDir::new().unwrap();
+        let set = |key: &str, value: &str| {
+            let root = repo.path().to_path_buf();
+            let (key, value) = (key.to_string(), value.to_string());
+            async move {
+                git::run_git_output_at(Some(&root), &["config", &key, &value], &[])
+                    .await
+                    .unwrap();
+            }
+        };
+        let output = git::run_git_output_at(Some(repo.path()), &["init", "-q"], &[])
+            .await
+            .unwrap();
+        assert!(output.status.success());
+        set("ai.cadence.enabled", "false").await;
+        set("ai.cadence.redactSecrets", "true").await;
+        std::fs::write(
+            repo.path().join(REPO_CONFIG_FILE),
+            "enabled = true\nredact-secrets = false\nsession-body = \"full\"\nredact = ['ticket-\\d+']\n",
+        )
+        .unwrap();
+
+        // Loosening values are ignored and the user's settings stand.
+        let settings = Settings::load(Some(repo.path())).await;
+        assert!(!settings.enabled);
+        assert!(settings.redact_secrets);
+        assert_eq!(
+            lookup(Some(repo.path()), "ai.cadence.sessionBody", None),
+            None
+        );
+        // Patterns add to the user's instead of replacing them.
+        assert_eq!(lookup(Some(repo.path()), "ai.cadence.redact", None), None);
+        assert_eq!(
+            additions(Some(repo.path()), "ai.cadence.redact"),
+            [r"ticket-\d+"]
+        );
+        assert!(additions(Some(repo.path()), "ai.cadence.windowBefore").is_empty());
+
+        // Tightening values apply.
+        let strict = TempDir::new().unwrap();
+        std::fs::write(
+            strict.path().join(REPO_CONFIG_FILE),
+            "enabled = false\nredactSecrets = true\nsessionBody = \"reference\"\n",
+        )
+        .unwrap();
+        let get = |key| lookup(Some(strict.path()), key, None);
+        assert_eq!(get("ai.cadence.enabled"), Some(vec!["false".to_string()]));
+        assert_eq!(
+            get("ai.cadence.redactSecrets"),
+            Some(vec!["true".to_string()])
+        );
+        assert_eq!(
+            get("ai.cadence.sessionBody"),
+            Some(vec!["reference".to_string()])
+        );
+    }
+}
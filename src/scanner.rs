@@ -29,6 +29,36 @@ pub enum AgentType {
     Warp,
 }
 
+impl AgentType {
+    /// Every supported agent, in discovery order.
+    pub const ALL: [AgentType; 12] = [
+        AgentType::Claude,
+        AgentType::Codex,
+        AgentType::Cursor,
+        AgentType::Copilot,
+        AgentType::Cline,
+        AgentType::RooCode,
+        AgentType::OpenCode,
+        AgentType::Kiro,
+        AgentType::AmpCode,
+        AgentType::Antigravity,
+        AgentType::Windsurf,
+        AgentType::Warp,
+    ];
+
+    /// Parse a user-supplied agent name (case-insensitive).
+    ///
+    /// Accepts the display name (`claude-code`) and the same name without a
+    /// trailing `-code` (`claude`, `roo`, `amp`).
+    pub fn from_name(name: &str) -> Option<AgentType> {
+        let name = name.trim().to_ascii_lowercase();
+        Self::ALL.into_iter().find(|agent| {
+            let display = agent.to_string();
+            display == name || display.strip_suffix("-code") == Some(name.as_str())
+        })
+    }
+}
+
 impl std::fmt::Display for AgentType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -60,6 +90,13 @@ pub struct SessionMetadata {
     pub cwd: Option<String>,
     /// The agent type, if determinable from the log content.
     pub agent_type: Option<AgentType>,
+    /// `session_id` was taken from the file name because the log has no
+    /// session id field.
+    pub session_id_inferred: bool,
+    /// Lines of a JSON Lines log that were not valid JSON and were skipped,
+    /// among those read before the metadata was complete. Zero for logs
+    /// that parse as a single JSON document.
+    pub skipped_lines: usize,
 }
 
 // ---------------------------------------------------------------------------
@@ -80,7 +117,7 @@ pub struct SessionMetadata {
 /// (once a field is found, later occurrences are ignored).
 pub async fn parse_session_metadata(file: &Path) -> SessionMetadata {
     let mut metadata = SessionMetadata::default();
-    let content = match tokio::fs::read_to_string(file).await {
+    let content = match crate::agents::read_session_file(file).await {
         Ok(c) => c,
         Err(_) => return metadata,
     };
@@ -91,6 +128,7 @@ pub async fn parse_session_metadata(file: &Path) -> SessionMetadata {
     if (metadata.session_id.is_none() || metadata.cwd.is_none())
         && let Some(value) = read_json_value(file).await
     {
+        metadata.skipped_lines = 0;
         apply_metadata_from_value(&mut metadata, &value);
     }
 
@@ -109,12 +147,138 @@ pub fn parse_session_metadata_str(content: &str) -> SessionMetadata {
     if (metadata.session_id.is_none() || metadata.cwd.is_none())
         && let Ok(value) = serde_json::from_str::<serde_json::Value>(content)
     {
+        metadata.skipped_lines = 0;
         apply_metadata_from_value(&mut metadata, &value);
     }
 
     metadata
 }
 
+/// Parse metadata with the parser for `agent`'s log format.
+///
+/// Codex rollouts get a dedicated parser (see [`parse_codex_metadata`]);
+/// other agents use [`parse_session_metadata`]. `agent_type` is always set
+/// to `agent`. A log without a session id field gets one from its file name
+/// (see [`session_id_from_file_name`]), flagged as inferred.
+pub async fn parse_agent_session_metadata(agent: &AgentType, file: &Path) -> SessionMetadata {
+    let mut metadata = match agent {
+        AgentType::Codex => match crate::agents::read_session_file(file).await {
+            Ok(content) => parse_codex_metadata(&content),
+            Err(_) => SessionMetadata::default(),
+        },
+        _ => parse_session_metadata(file).await,
+    };
+    metadata.agent_type = Some(agent.clone());
+    if metadata.session_id.is_none()
+        && let Some(id) = session_id_from_file_name(file)
+    {
+        metadata.session_id = Some(id);
+        metadata.session_id_inferred = true;
+    }
+    metadata
+}
+
+/// A session id derived from a log's file name: the last UUID in the file
+/// stem if there is one (as in `rollout-2025-01-01T00-00-00-<uuid>.jsonl`),
+/// otherwise the whole stem.
+pub fn session_id_from_file_name(file: &Path) -> Option<String> {
+    let mut stem = file.file_stem()?.to_str()?;
+    if crate::agents::is_gzip_log(file) {
+        stem = Path::new(stem).file_stem()?.to_str()?;
+    }
+    let stem = stem.trim();
+    if stem.is_empty() {
+        return None;
+    }
+    let uuid = (0..stem.len().saturating_sub(35))
+        .rev()
+        .filter_map(|start| stem.get(start..start + 36))
+        .find(|candidate| is_uuid(candidate));
+    Some(uuid.unwrap_or(stem).to_string())
+}
+
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.bytes().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        })
+}
+
+/// String variant of [`parse_agent_session_metadata`].
+pub fn parse_agent_session_metadata_str(agent: &AgentType, content: &str) -> SessionMetadata {
+    let mut metadata = match agent {
+        AgentType::Codex => parse_codex_metadata(content),
+        _ => parse_session_metadata_str(content),
+    };
+    metadata.agent_type = Some(agent.clone());
+    metadata
+}
+
+/// Parse a Codex rollout.
+///
+/// Current rollouts start with a `session_meta` record whose payload holds
+/// the session id and cwd; `turn_context` records repeat the cwd. Older
+/// rollouts start with a bare `{"id", "timestamp", "instructions", "git"}`
+/// header and only carry the cwd inside the `<environment_context>` message.
+/// Other records' `id` fields (messages, tool calls) are never taken as the
+/// session id. Anything still missing is filled from the generic parser.
+fn parse_codex_metadata(content: &str) -> SessionMetadata {
+    let mut metadata = SessionMetadata::default();
+    for (index, line) in content.lines().enumerate() {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            metadata.skipped_lines += usize::from(!line.trim().is_empty());
+            continue;
+        };
+        let payload = value.get("payload");
+        let record_type = value.get("type").and_then(|v| v.as_str());
+        let (id, cwd) = match record_type {
+            Some("session_meta") => (
+                payload.and_then(|p| p.get("id")),
+                payload.and_then(|p| p.get("cwd")),
+            ),
+            Some("turn_context") => (None, payload.and_then(|p| p.get("cwd"))),
+            // Legacy header: the first record, untyped, with the git context.
+            None if index == 0 && value.get("git").is_some() => (value.get("id"), None),
+            _ => (None, None),
+        };
+        if metadata.session_id.is_none() {
+            metadata.session_id = id.and_then(|v| v.as_str()).map(str::to_string);
+        }
+        if metadata.cwd.is_none() {
+            metadata.cwd = cwd
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+                .or_else(|| codex_environment_cwd(payload.unwrap_or(&value)));
+        }
+        if metadata.session_id.is_some() && metadata.cwd.is_some() {
+            return metadata;
+        }
+    }
+
+    let generic = parse_session_metadata_str(content);
+    metadata.session_id = metadata.session_id.or(generic.session_id);
+    metadata.cwd = metadata.cwd.or(generic.cwd);
+    metadata
+}
+
+/// The `<cwd>` from a Codex `<environment_context>` user message.
+fn codex_environment_cwd(message: &serde_json::Value) -> Option<String> {
+    if message.get("role").and_then(|v| v.as_str()) != Some("user") {
+        return None;
+    }
+    message
+        .get("content")?
+        .as_array()?
+        .iter()
+        .filter_map(|item| item.get("text").and_then(|v| v.as_str()))
+        .find_map(|text| {
+            let start = text.find("<cwd>")? + "<cwd>".len();
+            let end = start + text[start..].find("</cwd>")?;
+            Some(text[start..end].trim().to_string())
+        })
+}
+
 /// Extract the session time range (start, end) from a session log file.
 ///
 /// Scans each line as JSON and looks for known timestamp keys:
@@ -124,7 +288,7 @@ pub fn parse_session_metadata_str(content: &str) -> SessionMetadata {
 /// Parses RFC3339/ISO8601 timestamps and returns the min/max epoch seconds.
 /// Returns `None` if no parseable timestamps are found.
 pub async fn session_time_range(file: &Path) -> Option<(i64, i64)> {
-    let content = tokio::fs::read_to_string(file).await.ok()?;
+    let content = crate::agents::read_session_file(file).await.ok()?;
     let reader = BufReader::new(Cursor::new(content.as_bytes()));
     let range = session_time_range_reader(reader);
 
@@ -246,7 +410,7 @@ fn parse_numeric_timestamp(value: &serde_json::Value) -> Option<i64> {
 }
 
 async fn read_json_value(file: &Path) -> Option<serde_json::Value> {
-    let content = tokio::fs::read_to_string(file).await.ok()?;
+    let content = crate::agents::read_session_file(file).await.ok()?;
     serde_json::from_str(&content).ok()
 }
 
@@ -260,10 +424,13 @@ fn parse_session_metadata_reader<R: BufRead>(
             Err(_) => continue,
         };
 
-        // Attempt to parse as JSON
+        // Attempt to parse as JSON; a corrupt line does not end the scan.
         let value: serde_json::Value = match serde_json::from_str(&line) {
             Ok(v) => v,
-            Err(_) => continue,
+            Err(_) => {
+                metadata.skipped_lines += usize::from(!line.trim().is_empty());
+                continue;
+            }
         };
 
         apply_metadata_from_value(&mut metadata, &value);
@@ -474,6 +641,43 @@ fn strip_file_uri_prefix(path: &str) -> &str {
     trimmed
 }
 
+/// Whether two paths name the same directory once Windows spellings are
+/// normalized; see [`comparable_path`].
+pub fn same_path(a: &Path, b: &Path) -> bool {
+    comparable_path(&a.to_string_lossy()) == comparable_path(&b.to_string_lossy())
+}
+
+/// Whether `ancestor` is a proper ancestor directory of `path`, compared
+/// with [`comparable_path`].
+pub fn is_ancestor_path(ancestor: &Path, path: &Path) -> bool {
+    let ancestor = comparable_path(&ancestor.to_string_lossy());
+    let path = comparable_path(&path.to_string_lossy());
+    let prefix = if ancestor.ends_with('/') {
+        ancestor
+    } else {
+        format!("{ancestor}/")
+    };
+    path.len() > prefix.len() && path.starts_with(&prefix)
+}
+
+/// Normalize a path for equality checks.
+///
+/// Agents record Windows working directories as `C:\Users\me\repo` (or with
+/// either drive letter case) while git reports `C:/Users/me/repo`.
+/// Backslashes become forward slashes, the drive letter is lowercased and
+/// trailing separators are dropped. Unix paths only lose trailing slashes.
+pub fn comparable_path(path: &str) -> String {
+    let mut normalized = path.replace('\\', "/");
+    let bytes = normalized.as_bytes();
+    if bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' {
+        normalized[..1].make_ascii_lowercase();
+    }
+    while normalized.len() > 1 && normalized.ends_with('/') && !normalized.ends_with(":/") {
+        normalized.pop();
+    }
+    normalized
+}
+
 fn is_windows_drive_path(path: &str) -> bool {
     let bytes = path.as_bytes();
     bytes.len() >= 3
@@ -537,6 +741,38 @@ mod tests {
         path
     }
 
+    // -----------------------------------------------------------------------
+    // Codex rollouts
+    // -----------------------------------------------------------------------
+
+    #[test]
+    fn codex_rollout_metadata_comes_from_session_meta() {
+        let content = include_str!("../tests/fixtures/codex/rollout.jsonl");
+        let metadata = parse_agent_session_metadata_str(&AgentType::Codex, content);
+        assert_eq!(
+            metadata.session_id.as_deref(),
+            Some("0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a10")
+        );
+        assert_eq!(metadata.cwd.as_deref(), Some("/Users/dev/src/widgets"));
+        assert_eq!(metadata.agent_type, Some(AgentType::Codex));
+    }
+
+    #[test]
+    fn legacy_codex_rollout_metadata_uses_header_and_environment_context() {
+        let content = include_str!("../tests/fixtures/codex/legacy_rollout.jsonl");
+        let metadata = parse_agent_session_metadata_str(&AgentType::Codex, content);
+        assert_eq!(
+            metadata.session_id.as_deref(),
+            Some("5973b6c0-94b8-487b-a530-2aeb6098ae0e")
+        );
+        assert_eq!(metadata.cwd.as_deref(), Some("/Users/dev/src/widgets"));
+
+        // The generic parser finds neither field in this layout.
+        let generic = parse_session_metadata_str(content);
+        assert!(generic.session_id.is_none());
+        assert!(generic.cwd.is_none());
+    }
+
     // -----------------------------------------------------------------------
     // infer_agent_type
     // -----------------------------------------------------------------------
@@ -674,6 +910,18 @@ mod tests {
     // AgentType display
     // -----------------------------------------------------------------------
 
+    #[test]
+    fn test_agent_type_from_name() {
+        assert_eq!(AgentType::from_name("claude"), Some(AgentType::Claude));
+        assert_eq!(AgentType::from_name("Claude-Code"), Some(AgentType::Claude));
+        assert_eq!(AgentType::from_name("roo"), Some(AgentType::RooCode));
+        assert_eq!(AgentType::from_name("codex"), Some(AgentType::Codex));
+        assert_eq!(AgentType::from_name("gpt"), None);
+        for agent in AgentType::ALL {
+            assert_eq!(AgentType::from_name(&agent.to_string()), Some(agent));
+        }
+    }
+
     #[tokio::test]
     async fn test_agent_type_display_claude() {
         assert_eq!(AgentType::Claude.to_string(), "claude-code");
@@ -752,6 +1000,40 @@ mod tests {
         assert_eq!(metadata.cwd, Some("/Users/foo/bar".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_agent_metadata_infers_session_id_from_file_name() {
+        let dir = TempDir::new().unwrap();
+        let content = r#"{"cwd":"/Users/foo/bar","type":"message","content":"hello"}"#;
+        let file = write_temp_file(dir.path(), "task-42.jsonl", content).await;
+
+        let metadata = parse_agent_session_metadata(&AgentType::Claude, &file).await;
+        assert_eq!(metadata.session_id.as_deref(), Some("task-42"));
+        assert!(metadata.session_id_inferred);
+        assert_eq!(metadata.cwd.as_deref(), Some("/Users/foo/bar"));
+
+        let file = write_temp_file(
+            dir.path(),
+            "rollout-2025-01-01T00-00-00-0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a10.jsonl",
+            content,
+        )
+        .await;
+        let metadata = parse_agent_session_metadata(&AgentType::Claude, &file).await;
+        assert_eq!(
+            metadata.session_id.as_deref(),
+            Some("0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a10")
+        );
+
+        let file = write_temp_file(
+            dir.path(),
+            "named.jsonl",
+            r#"{"session_id":"abc-123","cwd":"/Users/foo/bar"}"#,
+        )
+        .await;
+        let metadata = parse_agent_session_metadata(&AgentType::Claude, &file).await;
+        assert_eq!(metadata.session_id.as_deref(), Some("abc-123"));
+        assert!(!metadata.session_id_inferred);
+    }
+
     #[tokio::test]
     async fn test_parse_metadata_camel_case_session_id() {
         let dir = TempDir::new().unwrap();
@@ -921,6 +1203,15 @@ also not json {{{{
 
         assert_eq!(metadata.session_id, Some("valid-id".to_string()));
         assert_eq!(metadata.cwd, Some("/valid/path".to_string()));
+        assert_eq!(metadata.skipped_lines, 2);
+    }
+
+    #[test]
+    fn test_single_json_documents_report_no_skipped_lines() {
+        let content = "{\n  \"sessionId\": \"doc-id\",\n  \"cwd\": \"/doc/path\"\n}\n";
+        let metadata = parse_session_metadata_str(content);
+        assert_eq!(metadata.session_id, Some("doc-id".to_string()));
+        assert_eq!(metadata.skipped_lines, 0);
     }
 
     #[tokio::test]
@@ -1013,6 +1304,30 @@ also not json {{{{
         assert_eq!(metadata.cwd, Some("/Users/foo/dev/repo".to_string()));
     }
 
+    #[test]
+    fn comparable_path_normalizes_windows_spellings() {
+        assert_eq!(comparable_path(r"C:\Users\me\repo"), "c:/Users/me/repo");
+        assert_eq!(comparable_path("c:/Users/me/repo/"), "c:/Users/me/repo");
+        assert_eq!(comparable_path(r"C:\"), "c:/");
+        assert_eq!(comparable_path("/home/me/repo/"), "/home/me/repo");
+        assert_eq!(comparable_path("/"), "/");
+        assert!(same_path(
+            Path::new(r"C:\Users\me\repo"),
+            Path::new("C:/Users/me/repo")
+        ));
+        assert!(!same_path(
+            Path::new(r"C:\Users\me\repo"),
+            Path::new("D:/Users/me/repo")
+        ));
+        assert!(is_ancestor_path(
+            Path::new(r"C:\Users\me"),
+            Path::new("c:/Users/me/repo")
+        ));
+        assert!(is_ancestor_path(Path::new("/"), Path::new("/repo")));
+        assert!(!is_ancestor_path(Path::new("/repo"), Path::new("/repo")));
+        assert!(!is_ancestor_path(Path::new("/re"), Path::new("/repo")));
+    }
+
     #[test]
     fn test_normalize_cwd_path_handles_localhost_windows_file_uri() {
         let normalized = normalize_cwd_path("file://localhost/C:/Users/foo/dev/repo");
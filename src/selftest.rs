@@ -0,0 +1,251 @@
+//! `cadence selftest`: an end-to-end check that the post-commit hook stores
+//! sessions on this machine, without `cargo test`.
+//!
+//! Everything happens in a temporary directory standing in for the home
+//! directory: a fresh repository, a fake Codex session started in it, a
+//! commit, and then this binary's own `hook post-commit`. The user's git
+//! config, agent logs and Cadence state are never read or written, and the
+//! directory is removed afterwards.
+
+use anyhow::{Context, Result, bail};
+use std::path::PathBuf;
+
+use crate::{git, output};
+
+const SESSION_ID: &str = "0199e3a1-7c2b-7d10-9f4e-3b1f5c2d8a10";
+const AGENT: &str = "codex";
+
+/// A scratch home directory and the repository inside it.
+struct Sandbox {
+    home: PathBuf,
+    repo: PathBuf,
+}
+
+impl Sandbox {
+    /// Environment that keeps git and the hook inside the sandbox.
+    fn env(&self) -> Vec<(&'static str, String)> {
+        let home = self.home.to_string_lossy().to_string();
+        vec![
+            ("HOME", home.clone()),
+            ("USERPROFILE", home),
+            (
+                "GIT_CONFIG_GLOBAL",
+                self.home.join(".gitconfig").to_string_lossy().to_string(),
+            ),
+            ("GIT_CONFIG_NOSYSTEM", "1".to_string()),
+        ]
+    }
+
+    async fn git(&self, args: &[&str]) -> Result<String> {
+        let env = self.env();
+        let env: Vec<(&str, &str)> = env.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        let output = git::run_git_output_at(Some(&self.repo), args, &env).await?;
+        if !output.status.success() {
+            bail!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+/// Run the self-test, printing one line per step. Fails if any step does.
+pub async fn run() -> Result<()> {
+    output::action("Self-test", "post-commit hook in a scratch repository");
+    let tmp = tempfile::TempDir::new().context("create scratch directory")?;
+    let sandbox = Sandbox {
+        home: tmp.path().join("home"),
+        repo: tmp.path().join("repo"),
+    };
+
+    step("Repository", create_repo(&sandbox).await)?;
+    step("Session", write_session(&sandbox).await)?;
+    step("Hook", commit_and_run_hook(&sandbox).await)?;
+    step("Session refs", check_session_refs(&sandbox).await)?;
+    output::success("Self-test", "passed");
+    Ok(())
+}
+
+/// Print the outcome of one step; a failed step ends the self-test.
+fn step(label: &str, result: Result<String>) -> Result<()> {
+    match result {
+        Ok(detail) => {
+            output::success(label, &detail);
+            Ok(())
+        }
+        Err(e) => {
+            output::fail(label, &format!("{e:#}"));
+            bail!("self-test failed at step {label:?}")
+        }
+    }
+}
+
+async fn create_repo(sandbox: &Sandbox) -> Result<String> {
+    tokio::fs::create_dir_all(&sandbox.home).await?;
+    tokio::fs::create_dir_all(&sandbox.repo).await?;
+    tokio::fs::write(sandbox.home.join(".gitconfig"), "").await?;
+    sandbox.git(&["init", "-q"]).await?;
+    sandbox
+        .git(&["config", "user.email", "selftest@example.com"])
+        .await?;
+    sandbox
+        .git(&["config", "user.name", "Cadence Self-test"])
+        .await?;
+    tokio::fs::write(sandbox.repo.join("README.md"), "self-test\n").await?;
+    sandbox.git(&["add", "README.md"]).await?;
+    sandbox.git(&["commit", "-q", "-m", "init"]).await?;
+    Ok(sandbox.repo.display().to_string())
+}
+
+/// A minimal Codex rollout whose working directory is the scratch repo.
+async fn write_session(sandbox: &Sandbox) -> Result<String> {
+    let root = sandbox.git(&["rev-parse", "--show-toplevel"]).await?;
+    let now = crate::note::now_rfc3339();
+    let lines = [
+        serde_json::json!({
+            "timestamp": now,
+            "type": "session_meta",
+            "payload": { "id": SESSION_ID, "timestamp": now, "cwd": root },
+        }),
+        serde_json::json!({
+            "timestamp": now,
+            "type": "response_item",
+            "payload": {
+                "type": "message",
+                "role": "user",
+                "content": [{ "type": "input_text", "text": "update the readme" }],
+            },
+        }),
+    ];
+    let dir = sandbox.home.join(".codex/sessions/selftest");
+    tokio::fs::create_dir_all(&dir).await?;
+    let content: String = lines.iter().map(|line| format!("{line}\n")).collect();
+    tokio::fs::write(dir.join(format!("rollout-{SESSION_ID}.jsonl")), content).await?;
+    Ok(format!("{AGENT} session {SESSION_ID}"))
+}
+
+/// Commit a change, then run `hook post-commit --report-json` from this
+/// binary the way the installed hook would.
+async fn commit_and_run_hook(sandbox: &Sandbox) -> Result<String> {
+    tokio::fs::write(sandbox.repo.join("README.md"), "self-test\nupdated\n").await?;
+    sandbox
+        .git(&["commit", "-q", "-am", "update readme"])
+        .await?;
+    let head = sandbox.git(&["rev-parse", "HEAD"]).await?;
+
+    let exe = std::env::current_exe().context("locate the cadence binary")?;
+    let mut command = tokio::process::Command::new(&exe);
+    command
+        .args(["hook", "post-commit", "--report-json"])
+        .current_dir(&sandbox.repo)
+        .env_remove("CODEX_HOME")
+        .envs(sandbox.env());
+    // Settings overrides from the caller's environment would leak in.
+    for (name, _) in std::env::vars_os() {
+        if name.to_string_lossy().starts_with("CADENCE_") {
+            command.env_remove(name);
+        }
+    }
+    let output = command
+        .output()
+        .await
+        .with_context(|| format!("run {}", exe.display()))?;
+    if !output.status.success() {
+        bail!(
+            "hook exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let report: serde_json::Value = stdout
+        .lines()
+        .rev()
+        .find_map(|line| serde_json::from_str(line).ok())
+        .context("hook printed no report")?;
+    check_report(&report, &head)
+}
+
+fn check_report(report: &serde_json::Value, head: &str) -> Result<String> {
+    let field = |name: &str| report.get(name).and_then(|v| v.as_str());
+    if let Some(error) = field("error") {
+        bail!("hook reported an error: {error}");
+    }
+    if let Some(skipped) = field("skipped") {
+        bail!("hook skipped the commit: {skipped}");
+    }
+    if field("commit") != Some(head) {
+        bail!(
+            "hook reported commit {:?}, expected {head}",
+            field("commit")
+        );
+    }
+    let agents: Vec<&str> = report
+        .get("agents")
+        .and_then(|v| v.as_array())
+        .map(|agents| agents.iter().filter_map(|a| a.as_str()).collect())
+        .unwrap_or_default();
+    if report.get("stored").and_then(|v| v.as_bool()) != Some(true) || !agents.contains(&AGENT) {
+        bail!("the {AGENT} session was not stored (agents: {agents:?})");
+    }
+    Ok(format!("stored the {AGENT} session for {}", &head[..7]))
+}
+
+/// The session object and the branch index must both be in the repository.
+async fn check_session_refs(sandbox: &Sandbox) -> Result<String> {
+    let objects = git::ls_tree_at(Some(&sandbox.repo), git::SESSION_DATA_REF)
+        .await
+        .with_context(|| format!("read {}", git::SESSION_DATA_REF))?;
+    if objects.is_empty() {
+        bail!("{} holds no session objects", git::SESSION_DATA_REF);
+    }
+    sandbox
+        .git(&[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            git::SESSION_INDEX_BRANCH_REF,
+        ])
+        .await
+        .with_context(|| format!("{} is missing", git::SESSION_INDEX_BRANCH_REF))?;
+    let noun = if objects.len() == 1 {
+        "object"
+    } else {
+        "objects"
+    };
+    Ok(format!(
+        "{} session {noun} under {}",
+        objects.len(),
+        git::SESSION_DATA_REF
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_must_name_the_commit_and_the_stored_agent() {
+        let head = "0123456789abcdef0123456789abcdef01234567";
+        let ok = serde_json::json!({
+            "commit": head, "stored": true, "sessions": 1, "agents": ["codex"],
+            "pending": false, "skipped": null, "error": null,
+        });
+        assert_eq!(
+            check_report(&ok, head).unwrap(),
+            "stored the codex session for 0123456"
+        );
+
+        let mut skipped = ok.clone();
+        skipped["skipped"] = "disabled for this repository".into();
+        assert!(check_report(&skipped, head).is_err());
+
+        let mut empty = ok.clone();
+        empty["stored"] = false.into();
+        empty["agents"] = serde_json::json!([]);
+        assert!(check_report(&empty, head).is_err());
+    }
+}
@@ -0,0 +1,555 @@
+//! Typed Cadence settings, resolved in one place.
+//!
+//! Every `ai.cadence.*` setting is looked up in the same order, and the first
+//! source that sets it wins:
+//!
+//! 1. the environment, as `CADENCE_` plus the key in screaming snake case
+//!    (`ai.cadence.windowBefore` is `CADENCE_WINDOW_BEFORE`); multi-valued
+//!    settings take one value per line
+//! 2. the repository's `.cadence.toml`, for the keys a repository may set;
+//!    privacy controls and settings that widen matching only when the file
+//!    is stricter than the user's own value, and list settings like
+//!    `redact` are extended rather than replaced (see [`repo_config`])
+//! 3. repository git config
+//! 4. global and system git config
+//! 5. the built-in default
+//!
+//! Settings that decide where session data may go (`org`, `autopush`) skip
+//! steps 2 and 3, so a checked-out repository cannot change them. Code reads
+//! [`Settings`] instead of calling `git config` for these keys.
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::trailer::{self, TrailerFormat};
+use crate::{attribution, config, git, log, note, push, repo_config};
+use cadence_cli::matcher::Confidence;
+
+const KEY_PREFIX: &str = "ai.cadence.";
+
+/// Matches every Cadence key in `git config --get-regexp`.
+const KEY_PATTERN: &str = r"^ai\.cadence\.";
+
+/// Git config key that turns Cadence off for a repository (or, set
+/// globally, everywhere) when set to a false value.
+pub const ENABLED_KEY: &str = "ai.cadence.enabled";
+
+/// Git config key that routes hook output away from the terminal.
+pub const HOOK_LOG_KEY: &str = "ai.cadence.hookLog";
+
+/// Git config key for the post-commit time budget in milliseconds.
+pub const HOOK_TIMEOUT_KEY: &str = "ai.cadence.hookTimeoutMs";
+const DEFAULT_HOOK_TIMEOUT_MS: u64 = 2_000;
+
+/// Git config key for how long the post-commit hook waits before looking for
+/// a session a second time when the first look finds none, in milliseconds.
+/// Off (`0`) by default.
+pub const RECHECK_DELAY_KEY: &str = "ai.cadence.recheckDelayMs";
+
+/// Git config key that lets sessions started in a parent directory of the
+/// repository (e.g. the checkout containing a submodule) match its commits.
+pub const MATCH_PARENT_CWD_KEY: &str = "ai.cadence.matchParentCwd";
+
+/// Git config key that lets the post-commit hook, when no session log was
+/// updated in the window, store the newest session from the last week whose
+/// log mentions the commit subject. Off by default, since it reads every
+/// recent log.
+pub const SUBJECT_FALLBACK_KEY: &str = "ai.cadence.subjectFallback";
+
+/// Git config key choosing whether HEAD's committer (`commit`, the default)
+/// or author (`author`) date anchors the post-commit window.
+pub const TIME_SOURCE_KEY: &str = "ai.cadence.timeSource";
+
+/// Git config keys for how many seconds of session activity before and
+/// after the anchor timestamp count as a match. `windowBefore` defaults to
+/// [`POST_COMMIT_MATCH_WINDOW_SECS`]; without `windowAfter`, logs updated
+/// any time after the anchor match.
+pub const WINDOW_BEFORE_KEY: &str = "ai.cadence.windowBefore";
+pub const WINDOW_AFTER_KEY: &str = "ai.cadence.windowAfter";
+pub const POST_COMMIT_MATCH_WINDOW_SECS: i64 = 1_800;
+
+/// Git config key listing commit authors (emails or names) whose commits the
+/// post-commit hook ignores. Multi-valued; each value may be comma-separated.
+pub const SKIP_AUTHORS_KEY: &str = "ai.cadence.skipAuthors";
+
+/// Git config key that limits the post-commit hook to commits whose author
+/// email is the user's own `user.email`.
+pub const ONLY_MY_COMMITS_KEY: &str = "ai.cadence.onlyMyCommits";
+
+/// Git config key for the weakest match (`weak`, `strong` or `exact`; see
+/// [`Confidence`]) the hooks store. Weaker matches are left as pending
+/// records and retried.
+pub const MIN_CONFIDENCE_KEY: &str = "ai.cadence.minConfidence";
+
+/// Git config key for the retry jitter window, as a percentage of the backoff.
+pub const RETRY_JITTER_KEY: &str = "ai.cadence.retryJitterPercent";
+const DEFAULT_RETRY_JITTER_PERCENT: u64 = 20;
+
+/// Global git config key restricting Cadence to one organization's remotes.
+pub const ORG_KEY: &str = "ai.cadence.org";
+
+/// Resolved Cadence settings for one repository.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Settings {
+    /// Hooks and backfill do nothing when this is off.
+    pub enabled: bool,
+    /// `hookLog = file`: hook output goes to the hook log, not the terminal.
+    pub hook_log_to_file: bool,
+    /// Post-commit time budget; `None` when disabled with `0`.
+    pub hook_timeout: Option<Duration>,
+    /// Wait before the post-commit hook's second look; `None` when off.
+    pub recheck_delay: Option<Duration>,
+    pub inject_trailer: bool,
+    pub trailer_format: TrailerFormat,
+    pub match_parent_cwd: bool,
+    pub subject_fallback: bool,
+    pub time_source: git::TimeSource,
+    pub window_before_secs: i64,
+    pub window_after_secs: Option<i64>,
+    /// Raw `skipAuthors` entries, each possibly comma-separated.
+    pub skip_authors: Vec<String>,
+    pub only_my_commits: bool,
+    pub min_confidence: Confidence,
+    pub session_body: note::SessionBody,
+    pub redact_secrets: bool,
+    pub redact_patterns: Vec<String>,
+    pub report_attribution: bool,
+    pub retry_jitter_percent: u64,
+    /// Remote pinned with `ai.cadence.remote`.
+    pub session_remote: Option<String>,
+    /// Global `autopush = never` kill switch.
+    pub autopush_never: bool,
+    /// Global organization filter.
+    pub org: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hook_log_to_file: false,
+            hook_timeout: parse_hook_timeout(None),
+            recheck_delay: None,
+            inject_trailer: false,
+            trailer_format: TrailerFormat::default(),
+            match_parent_cwd: false,
+            subject_fallback: false,
+            time_source: git::TimeSource::default(),
+            window_before_secs: POST_COMMIT_MATCH_WINDOW_SECS,
+            window_after_secs: None,
+            skip_authors: Vec::new(),
+            only_my_commits: false,
+            min_confidence: Confidence::default(),
+            session_body: note::SessionBody::default(),
+            redact_secrets: false,
+            redact_patterns: Vec::new(),
+            report_attribution: false,
+            retry_jitter_percent: DEFAULT_RETRY_JITTER_PERCENT,
+            session_remote: None,
+            autopush_never: false,
+            org: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Resolve every setting for `repo`, or the current repository when
+    /// `None`. Unreadable git config counts as unset.
+    pub async fn load(repo: Option<&Path>) -> Self {
+        let config = git::config_entries_at(repo, KEY_PATTERN, false)
+            .await
+            .unwrap_or_else(|e| {
+                log::debug(&format!("could not read git config: {e:#}"));
+                Vec::new()
+            });
+        let global = git::config_entries_at(repo, KEY_PATTERN, true)
+            .await
+            .unwrap_or_default();
+        Sources {
+            repo,
+            env: &|name| std::env::var(name).ok(),
+            config,
+            global,
+        }
+        .resolve()
+    }
+}
+
+/// Whether a setting may come from the repository or only from global config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Repo,
+    Global,
+}
+
+struct Sources<'a> {
+    repo: Option<&'a Path>,
+    env: &'a dyn Fn(&str) -> Option<String>,
+    /// Merged git config entries, lowest precedence first.
+    config: Vec<(String, String)>,
+    global: Vec<(String, String)>,
+}
+
+impl Sources<'_> {
+    fn resolve(&self) -> Settings {
+        let defaults = Settings::default();
+        let inject_trailer = self.flag(trailer::INJECT_TRAILER_KEY);
+        let trailer_format = match self.value(trailer::TRAILER_FORMAT_KEY) {
+            None => defaults.trailer_format,
+            Some(value) => TrailerFormat::parse(&value).unwrap_or_else(|| {
+                // Only worth a warning where the format is used.
+                if inject_trailer {
+                    log::warn(&format!(
+                        "unknown {} value {value:?}; using session-id",
+                        trailer::TRAILER_FORMAT_KEY
+                    ));
+                }
+                defaults.trailer_format
+            }),
+        };
+        Settings {
+            enabled: self
+                .value(ENABLED_KEY)
+                .is_none_or(|v| config::parse_bool_value(&v).unwrap_or(true)),
+            hook_log_to_file: self
+                .value(HOOK_LOG_KEY)
+                .is_some_and(|m| m.trim().eq_ignore_ascii_case("file")),
+            hook_timeout: parse_hook_timeout(self.value(HOOK_TIMEOUT_KEY).as_deref()),
+            recheck_delay: parse_recheck_delay(self.value(RECHECK_DELAY_KEY).as_deref()),
+            inject_trailer,
+            trailer_format,
+            match_parent_cwd: self.flag(MATCH_PARENT_CWD_KEY),
+            subject_fallback: self.flag(SUBJECT_FALLBACK_KEY),
+            time_source: self
+                .value(TIME_SOURCE_KEY)
+                .and_then(|v| git::TimeSource::parse(&v))
+                .unwrap_or(defaults.time_source),
+            window_before_secs: parse_window_secs(self.value(WINDOW_BEFORE_KEY).as_deref())
+                .unwrap_or(defaults.window_before_secs),
+            window_after_secs: parse_window_secs(self.value(WINDOW_AFTER_KEY).as_deref()),
+            skip_authors: self.values(SKIP_AUTHORS_KEY, Scope::Repo),
+            only_my_commits: self.flag(ONLY_MY_COMMITS_KEY),
+            min_confidence: match self.value(MIN_CONFIDENCE_KEY) {
+                None => defaults.min_confidence,
+                Some(value) => Confidence::parse(&value).unwrap_or_else(|| {
+                    log::warn(&format!(
+                        "unknown {MIN_CONFIDENCE_KEY} value {value:?}; storing every match"
+                    ));
+                    defaults.min_confidence
+                }),
+            },
+            session_body: self
+                .value(note::SESSION_BODY_KEY)
+                .and_then(|v| note::SessionBody::parse(&v))
+                .unwrap_or(defaults.session_body),
+            redact_secrets: self.flag(note::REDACT_SECRETS_KEY),
+            redact_patterns: self.values(note::REDACT_PATTERN_KEY, Scope::Repo),
+            report_attribution: self.flag(attribution::REPORT_ATTRIBUTION_KEY),
+            retry_jitter_percent: parse_retry_jitter_percent(
+                self.value(RETRY_JITTER_KEY).as_deref(),
+            ),
+            session_remote: self
+                .value(git::SESSION_REMOTE_KEY)
+                .filter(|remote| !remote.is_empty() && remote != "."),
+            autopush_never: self
+                .values(push::AUTOPUSH_KEY, Scope::Global)
+                .last()
+                .is_some_and(|v| push::is_autopush_never(v)),
+            org: self
+                .values(ORG_KEY, Scope::Global)
+                .pop()
+                .filter(|org| !org.trim().is_empty()),
+        }
+    }
+
+    /// Every value for `key` from the first source that sets it.
+    fn values(&self, key: &str, scope: Scope) -> Vec<String> {
+        if let Some(value) = (self.env)(&env_var_name(key)).filter(|v| !v.trim().is_empty()) {
+            return value
+                .lines()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(String::from)
+                .collect();
+        }
+        let entries = match scope {
+            Scope::Global => &self.global,
+            Scope::Repo => &self.config,
+        };
+        let mut values: Vec<String> = entries
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.clone())
+            .collect();
+        if scope == Scope::Repo {
+            if let Some(repo_values) =
+                repo_config::lookup(self.repo, key, values.last().map(String::as_str))
+            {
+                return repo_values;
+            }
+            values.extend(repo_config::additions(self.repo, key));
+        }
+        values
+    }
+
+    /// The effective value of a single-valued, repository-scoped `key`.
+    fn value(&self, key: &str) -> Option<String> {
+        self.values(key, Scope::Repo).pop()
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        self.value(key)
+            .is_some_and(|v| config::parse_bool_value(&v).unwrap_or(false))
+    }
+}
+
+/// `ai.cadence.windowBefore` is overridden by `CADENCE_WINDOW_BEFORE`.
+pub fn env_var_name(key: &str) -> String {
+    let name = key.strip_prefix(KEY_PREFIX).unwrap_or(key);
+    let mut env = String::from("CADENCE_");
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            env.push('_');
+        }
+        env.push(c.to_ascii_uppercase());
+    }
+    env
+}
+
+/// Resolve the post-commit time budget. Unset or invalid values use the
+/// default; `0` disables the budget.
+fn parse_hook_timeout(value: Option<&str>) -> Option<Duration> {
+    let ms = value
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_HOOK_TIMEOUT_MS);
+    (ms > 0).then(|| Duration::from_millis(ms))
+}
+
+/// Parse the recheck delay. Unset, invalid or `0` values turn it off.
+fn parse_recheck_delay(value: Option<&str>) -> Option<Duration> {
+    value
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|ms| *ms > 0)
+        .map(Duration::from_millis)
+}
+
+/// Parse a window size in seconds. Unset or invalid values give `None`.
+fn parse_window_secs(value: Option<&str>) -> Option<i64> {
+    value
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|secs| *secs >= 0)
+}
+
+/// Resolve the jitter percentage. Unset or invalid values use the default;
+/// values above 100 are capped.
+fn parse_retry_jitter_percent(value: Option<&str>) -> u64 {
+    value
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_RETRY_JITTER_PERCENT)
+        .min(100)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn env_var_names_follow_the_key() {
+        assert_eq!(env_var_name(WINDOW_BEFORE_KEY), "CADENCE_WINDOW_BEFORE");
+        assert_eq!(env_var_name(HOOK_TIMEOUT_KEY), "CADENCE_HOOK_TIMEOUT_MS");
+        assert_eq!(env_var_name(ORG_KEY), "CADENCE_ORG");
+    }
+
+    #[test]
+    fn parsers_fall_back_to_defaults() {
+        assert_eq!(parse_hook_timeout(None), Some(Duration::from_millis(2_000)));
+        assert_eq!(
+            parse_hook_timeout(Some("500")),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            parse_hook_timeout(Some("soon")),
+            Some(Duration::from_millis(2_000))
+        );
+        assert_eq!(parse_hook_timeout(Some("0")), None);
+
+        assert_eq!(parse_recheck_delay(None), None);
+        assert_eq!(parse_recheck_delay(Some("0")), None);
+        assert_eq!(parse_recheck_delay(Some("later")), None);
+        assert_eq!(
+            parse_recheck_delay(Some(" 250 ")),
+            Some(Duration::from_millis(250))
+        );
+
+        assert_eq!(parse_window_secs(Some(" 600 ")), Some(600));
+        assert_eq!(parse_window_secs(Some("0")), Some(0));
+        assert_eq!(parse_window_secs(Some("-5")), None);
+        assert_eq!(parse_window_secs(Some("10m")), None);
+        assert_eq!(parse_window_secs(None), None);
+
+        assert_eq!(parse_retry_jitter_percent(None), 20);
+        assert_eq!(parse_retry_jitter_percent(Some("5")), 5);
+        assert_eq!(parse_retry_jitter_percent(Some("500")), 100);
+        assert_eq!(parse_retry_jitter_percent(Some("lots")), 20);
+    }
+
+    #[tokio::test]
+    async fn load_reads_repository_git_config() {
+        let repo = TempDir::new().unwrap();
+        let root = repo.path();
+        let git = |args: &'static [&'static str]| async move {
+            let output = git::run_git_output_at(Some(root), args, &[]).await.unwrap();
+            assert!(output.status.success());
+        };
+        git(&["init", "-q"]).await;
+        git(&["config", "ai.cadence.windowAfter", "120"]).await;
+        git(&["config", "ai.cadence.trailerFormat", "full"]).await;
+        git(&["config", "--add", "ai.cadence.redact", "token=(\\S+)"]).await;
+        git(&["config", "--add", "ai.cadence.redact", "secret"]).await;
+
+        let settings = Settings::load(Some(root)).await;
+        assert_eq!(settings.window_after_secs, Some(120));
+        assert_eq!(settings.trailer_format, TrailerFormat::Full);
+        assert_eq!(settings.redact_patterns, [r"token=(\S+)", "secret"]);
+        assert_eq!(settings.window_before_secs, POST_COMMIT_MATCH_WINDOW_SECS);
+    }
+
+    #[tokio::test]
+    async fn load_reads_enabled_from_repository_git_config() {
+        let repo = TempDir::new().unwrap();
+        let root = repo.path();
+        let git = |args: &'static [&'static str]| async move {
+            let output = git::run_git_output_at(Some(root), args, &[]).await.unwrap();
+            assert!(output.status.success());
+        };
+        git(&["init", "-q"]).await;
+        assert!(Settings::load(Some(root)).await.enabled);
+        git(&["config", "ai.cadence.enabled", "yes"]).await;
+        assert!(Settings::load(Some(root)).await.enabled);
+        git(&["config", "ai.cadence.enabled", "false"]).await;
+        assert!(!Settings::load(Some(root)).await.enabled);
+    }
+
+    #[test]
+    fn enabled_is_resolved_like_other_settings() {
+        let resolve = |env: &dyn Fn(&str) -> Option<String>, value: Option<&str>| {
+            Sources {
+                repo: None,
+                env,
+                config: value
+                    .map(|v| (ENABLED_KEY.to_ascii_lowercase(), v.to_string()))
+                    .into_iter()
+                    .collect(),
+                global: Vec::new(),
+            }
+            .resolve()
+            .enabled
+        };
+        let no_env = |_: &str| None;
+        assert!(resolve(&no_env, None));
+        assert!(resolve(&no_env, Some("true")));
+        assert!(resolve(&no_env, Some("maybe")));
+        assert!(!resolve(&no_env, Some("false")));
+        assert!(!resolve(&no_env, Some("no")));
+
+        let disabled = |name: &str| (name == "CADENCE_ENABLED").then(|| "false".to_string());
+        assert!(!resolve(&disabled, Some("true")));
+    }
+
+    #[test]
+    fn each_source_overrides_the_ones_after_it() {
+        let repo = TempDir::new().unwrap();
+        std::fs::write(
+            repo.path().join(repo_config::REPO_CONFIG_FILE),
+            "window-before = 300\nremote = \"attacker\"\nredact = ['repo-secret']\n",
+        )
+        .unwrap();
+        let entry = |key: &str, value: &str| (key.to_ascii_lowercase(), value.to_string());
+        let env = |name: &str| (name == "CADENCE_HOOK_TIMEOUT_MS").then(|| "0".to_string());
+        let sources = Sources {
+            repo: Some(repo.path()),
+            env: &env,
+            // Global entries come first, repository entries override them.
+            config: vec![
+                entry(HOOK_TIMEOUT_KEY, "900"),
+                entry(WINDOW_BEFORE_KEY, "900"),
+                entry(TIME_SOURCE_KEY, "commit"),
+                entry(SKIP_AUTHORS_KEY, "bot@example.com"),
+                entry(TIME_SOURCE_KEY, "author"),
+                entry(SKIP_AUTHORS_KEY, "ci"),
+                entry(ONLY_MY_COMMITS_KEY, "true"),
+                entry(MIN_CONFIDENCE_KEY, "high"),
+                entry(note::REDACT_PATTERN_KEY, "my-secret"),
+                entry(ORG_KEY, "repo-org"),
+                entry(push::AUTOPUSH_KEY, "never"),
+            ],
+            global: vec![entry(ORG_KEY, "example-org")],
+        };
+
+        let settings = sources.resolve();
+        assert_eq!(settings.hook_timeout, None);
+        assert_eq!(settings.window_before_secs, 300);
+        assert_eq!(settings.time_source, git::TimeSource::Author);
+        assert_eq!(settings.skip_authors, ["bot@example.com", "ci"]);
+        assert!(settings.only_my_commits);
+        assert_eq!(settings.min_confidence, Confidence::High);
+        assert_eq!(settings.redact_patterns, ["my-secret", "repo-secret"]);
+        // `.cadence.toml` cannot pin the remote, and repository config
+        // cannot change global-only settings.
+        assert_eq!(settings.session_remote, None);
+        assert_eq!(settings.org.as_deref(), Some("example-org"));
+        assert!(!settings.autopush_never);
+        assert_eq!(settings.window_after_secs, None);
+        assert_eq!(settings.retry_jitter_percent, 20);
+    }
+
+    #[test]
+    fn repo_file_cannot_loosen_global_settings() {
+        let resolve = |file: &str, global: &[(&str, &str)]| {
+            let repo = TempDir::new().unwrap();
+            std::fs::write(repo.path().join(repo_config::REPO_CONFIG_FILE), file).unwrap();
+            Sources {
+                repo: Some(repo.path()),
+                env: &|_| None,
+                config: global
+                    .iter()
+                    .map(|(key, value)| (key.to_ascii_lowercase(), value.to_string()))
+                    .collect(),
+                global: Vec::new(),
+            }
+            .resolve()
+        };
+        let global = [
+            (WINDOW_BEFORE_KEY, "600"),
+            (WINDOW_AFTER_KEY, "60"),
+            (MIN_CONFIDENCE_KEY, "medium"),
+            (MATCH_PARENT_CWD_KEY, "false"),
+        ];
+
+        let loose = resolve(
+            "window-before = 3600\nwindow-after = 600\nmin-confidence = \"low\"\n\
+             match-parent-cwd = true\nsubject-fallback = true\ninject-trailer = true\n",
+            &global,
+        );
+        assert_eq!(loose.window_before_secs, 600);
+        assert_eq!(loose.window_after_secs, Some(60));
+        assert_eq!(loose.min_confidence, Confidence::Medium);
+        assert!(!loose.match_parent_cwd);
+        assert!(!loose.subject_fallback);
+        assert!(!loose.inject_trailer);
+
+        let strict = resolve(
+            "window-before = 120\nwindow-after = 30\nmin-confidence = \"high\"\n",
+            &global,
+        );
+        assert_eq!(strict.window_before_secs, 120);
+        assert_eq!(strict.window_after_secs, Some(30));
+        assert_eq!(strict.min_confidence, Confidence::High);
+
+        // Without a user value, the default is what the file must tighten.
+        let unset = resolve("window-before = 3600\nwindow-after = 300\n", &[]);
+        assert_eq!(unset.window_before_secs, POST_COMMIT_MATCH_WINDOW_SECS);
+        assert_eq!(unset.window_after_secs, Some(300));
+    }
+}
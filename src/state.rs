@@ -0,0 +1,204 @@
+//! Location of local Cadence state: pending sync jobs, locks, ingest cursors
+//! and sync logs.
+//!
+//! On Linux (and other XDG platforms) state lives under
+//! `$XDG_DATA_HOME/cadence/cli`, defaulting to `~/.local/share/cadence/cli`.
+//! macOS and Windows keep `~/.cadence/cli`, next to the config file.
+//!
+//! Older versions kept state in `~/.cadence/cli` everywhere. The first time
+//! the state directory is resolved, pending jobs and cursors are moved over.
+//! The move is best-effort: if a rename fails (e.g. across filesystems) the
+//! old records stay where they were.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::agents;
+
+/// State subdirectories carried over from the legacy location. Locks and
+/// logs are short-lived and are simply recreated.
+const MIGRATED_STATE_DIRS: [&str; 2] = ["pending-sync", "sync-cursors"];
+
+/// Resolve the state directory for `home`, honoring `xdg_data_home` when it
+/// is an absolute path.
+pub fn state_dir_in(home: &Path, xdg_data_home: Option<&Path>) -> PathBuf {
+    if cfg!(target_os = "macos") || cfg!(target_os = "windows") {
+        return legacy_state_dir_in(home);
+    }
+    let base = xdg_data_home
+        .filter(|p| p.is_absolute())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| home.join(".local").join("share"));
+    base.join("cadence").join("cli")
+}
+
+fn legacy_state_dir_in(home: &Path) -> PathBuf {
+    home.join(".cadence").join("cli")
+}
+
+/// Resolve the state directory from the environment without touching disk.
+///
+/// Returns `None` if the home directory cannot be determined.
+pub fn state_dir_path() -> Option<PathBuf> {
+    let home = agents::home_dir()?;
+    let xdg = std::env::var_os("XDG_DATA_HOME").map(PathBuf::from);
+    Some(state_dir_in(&home, xdg.as_deref()))
+}
+
+/// The pre-XDG state directory, `~/.cadence/cli`, without touching disk.
+///
+/// Returns `None` if the home directory cannot be determined.
+pub fn legacy_state_dir_path() -> Option<PathBuf> {
+    agents::home_dir().map(|home| legacy_state_dir_in(&home))
+}
+
+/// Resolve and create the state directory, migrating legacy records first.
+pub async fn state_dir() -> Result<PathBuf> {
+    let home =
+        agents::home_dir().ok_or_else(|| anyhow::anyhow!("cannot resolve home directory"))?;
+    let xdg = std::env::var_os("XDG_DATA_HOME").map(PathBuf::from);
+    let dir = state_dir_in(&home, xdg.as_deref());
+    migrate_legacy_state(&legacy_state_dir_in(&home), &dir).await;
+    tokio::fs::create_dir_all(&dir).await?;
+    Ok(dir)
+}
+
+async fn migrate_legacy_state(legacy: &Path, dir: &Path) {
+    if legacy == dir {
+        return;
+    }
+    for name in MIGRATED_STATE_DIRS {
+        let from = legacy.join(name);
+        let to = dir.join(name);
+        if !tokio::fs::try_exists(&from).await.unwrap_or(false)
+            || tokio::fs::try_exists(&to).await.unwrap_or(false)
+        {
+            continue;
+        }
+        if tokio::fs::create_dir_all(dir).await.is_ok() {
+            let _ = tokio::fs::rename(&from, &to).await;
+        }
+    }
+}
+
+/// Write JSON atomically via a uniquely named temp file + rename.
+///
+/// Concurrent writers to the same path never see a torn file: each writes its
+/// own temp file and the last rename wins.
+pub async fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let data = serde_json::to_vec_pretty(value)?;
+    let parent = path
+        .parent()
+        .ok_or_else(|| anyhow::anyhow!("missing parent for {}", path.display()))?;
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("pending");
+    let pid = std::process::id();
+
+    for attempt in 0..8u32 {
+        let nonce = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let tmp_name = format!(".{file_name}.{pid}.{nonce}.{attempt}.tmp");
+        let tmp = parent.join(tmp_name);
+
+        let mut opts = tokio::fs::OpenOptions::new();
+        opts.write(true).create_new(true);
+        match opts.open(&tmp).await {
+            Ok(mut file) => {
+                tokio::io::AsyncWriteExt::write_all(&mut file, &data).await?;
+                drop(file);
+                tokio::fs::rename(&tmp, path).await?;
+                return Ok(());
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("create temp file for {}", path.display()));
+            }
+        }
+    }
+
+    Err(anyhow::anyhow!(
+        "failed to create unique temp file for {}",
+        path.display()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use tokio::task::JoinSet;
+
+    #[test]
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    fn state_dir_follows_xdg_data_home() {
+        let home = Path::new("/home/dev");
+        assert_eq!(
+            state_dir_in(home, None),
+            PathBuf::from("/home/dev/.local/share/cadence/cli")
+        );
+        assert_eq!(
+            state_dir_in(home, Some(Path::new("/data"))),
+            PathBuf::from("/data/cadence/cli")
+        );
+        // Relative values are invalid per the XDG spec and ignored.
+        assert_eq!(
+            state_dir_in(home, Some(Path::new("data"))),
+            PathBuf::from("/home/dev/.local/share/cadence/cli")
+        );
+    }
+
+    #[tokio::test]
+    async fn legacy_records_are_moved_once() {
+        let tmp = TempDir::new().unwrap();
+        let legacy = tmp.path().join("legacy");
+        let dir = tmp.path().join("state");
+        tokio::fs::create_dir_all(legacy.join("pending-sync"))
+            .await
+            .unwrap();
+        tokio::fs::write(legacy.join("pending-sync").join("job.json"), "{}")
+            .await
+            .unwrap();
+
+        migrate_legacy_state(&legacy, &dir).await;
+        assert!(dir.join("pending-sync").join("job.json").exists());
+        assert!(!legacy.join("pending-sync").exists());
+
+        // A second legacy copy never overwrites already-migrated state.
+        tokio::fs::create_dir_all(legacy.join("pending-sync"))
+            .await
+            .unwrap();
+        tokio::fs::write(legacy.join("pending-sync").join("old.json"), "{}")
+            .await
+            .unwrap();
+        migrate_legacy_state(&legacy, &dir).await;
+        assert!(!dir.join("pending-sync").join("old.json").exists());
+    }
+
+    #[tokio::test]
+    async fn write_json_atomic_allows_concurrent_writers() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("record.json");
+
+        let mut set = JoinSet::new();
+        for i in 0..24u32 {
+            let path = path.clone();
+            set.spawn(
+                async move { write_json_atomic(&path, &serde_json::json!({ "i": i })).await },
+            );
+        }
+
+        while let Some(done) = set.join_next().await {
+            done.unwrap().unwrap();
+        }
+
+        let content = tokio::fs::read_to_string(&path).await.unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&content).unwrap();
+        assert!(parsed.get("i").and_then(|v| v.as_u64()).is_some());
+    }
+}
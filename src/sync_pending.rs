@@ -1,7 +1,13 @@
-//! Persistent index-ingest cursors.
+//! Persistent index-ingest cursors and pending ingest records.
 //!
-//! Records are scoped by `(repo_root, scope_type, scope_key_hash)` and stored under:
-//! `~/.cadence/cli/sync-cursors/<repo-hash>--<scope>--<key-hash>.json`
+//! Cursors are scoped by `(repo_root, scope_type, scope_key_hash)` and stored under:
+//! `<state dir>/sync-cursors/<repo-hash>--<scope>--<key-hash>.json`
+//! (see [`crate::state`]).
+//!
+//! Pending records name ingest work a hook left for a later run, such as a
+//! session log that matched below `minConfidence`. They are stored under
+//! `<state dir>/pending-ingest/<repo-hash>--<target-hash>.json` and retried
+//! by the pre-push ingest until they succeed or expire.
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
@@ -33,18 +39,12 @@ pub struct SyncCursorRecord {
     pub updated_at: String,
 }
 
-async fn cursor_dir_in(home: &Path) -> Result<PathBuf> {
-    let dir = home.join(".cadence/cli").join("sync-cursors");
+async fn cursor_dir() -> Result<PathBuf> {
+    let dir = crate::state::state_dir().await?.join("sync-cursors");
     tokio::fs::create_dir_all(&dir).await?;
     Ok(dir)
 }
 
-async fn cursor_dir() -> Result<PathBuf> {
-    let home = crate::agents::home_dir()
-        .ok_or_else(|| anyhow::anyhow!("cannot determine home directory"))?;
-    cursor_dir_in(&home).await
-}
-
 fn short_hash(input: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(input.as_bytes());
@@ -99,7 +99,6 @@ pub async fn upsert_cursor(
 ) -> Result<()> {
     let dir = cursor_dir().await?;
     let path = record_path(&dir, repo_root, scope_type, scope_key_hash);
-    let tmp = path.with_extension("json.tmp");
     let record = SyncCursorRecord {
         repo_root: repo_root.to_string(),
         scope_type,
@@ -107,8 +106,100 @@ pub async fn upsert_cursor(
         last_scanned_mtime_epoch,
         updated_at: crate::note::now_rfc3339(),
     };
-    let json = serde_json::to_string_pretty(&record)?;
-    tokio::fs::write(&tmp, json).await?;
-    tokio::fs::rename(&tmp, &path).await?;
-    Ok(())
+    crate::state::write_json_atomic(&path, &record).await
+}
+
+/// Directory under the state dir holding [`PendingIngestRecord`]s.
+pub const PENDING_INGEST_DIR: &str = "pending-ingest";
+
+/// What a pending record asks a later run to ingest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PendingIngest {
+    /// A session log that matched the repository below `minConfidence`.
+    SessionLog { path: String, agent: String },
+    /// A commit whose sessions the post-commit hook ran out of time to find;
+    /// its window is anchored at `anchor_epoch`.
+    Commit { sha: String, anchor_epoch: i64 },
+}
+
+impl PendingIngest {
+    fn key(&self) -> String {
+        match self {
+            PendingIngest::SessionLog { path, .. } => format!("session-log:{path}"),
+            PendingIngest::Commit { sha, .. } => format!("commit:{sha}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingIngestRecord {
+    pub repo_root: String,
+    pub target: PendingIngest,
+    pub created_at_epoch: i64,
+}
+
+async fn pending_ingest_dir() -> Result<PathBuf> {
+    let dir = crate::state::state_dir().await?.join(PENDING_INGEST_DIR);
+    tokio::fs::create_dir_all(&dir).await?;
+    Ok(dir)
+}
+
+/// Record `target` for `repo_root`. Recording the same target again keeps
+/// the original creation time, so repeated misses do not postpone expiry.
+pub async fn add_pending_ingest(repo_root: &str, target: PendingIngest, now: i64) -> Result<()> {
+    let dir = pending_ingest_dir().await?;
+    let path = dir.join(format!(
+        "{}--{}.json",
+        short_hash(repo_root),
+        short_hash(&target.key())
+    ));
+    if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        return Ok(());
+    }
+    let record = PendingIngestRecord {
+        repo_root: repo_root.to_string(),
+        target,
+        created_at_epoch: now,
+    };
+    crate::state::write_json_atomic(&path, &record).await
+}
+
+/// Every pending record for `repo_root`, with the file it is stored in.
+/// Unreadable records are skipped.
+pub async fn pending_ingests(repo_root: &str) -> Result<Vec<(PathBuf, PendingIngestRecord)>> {
+    let dir = pending_ingest_dir().await?;
+    let prefix = format!("{}--", short_hash(repo_root));
+    let mut records = Vec::new();
+    let mut entries = tokio::fs::read_dir(&dir)
+        .await
+        .with_context(|| format!("failed to read {}", dir.display()))?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !name.starts_with(&prefix) || !name.ends_with(".json") {
+            continue;
+        }
+        let Ok(content) = tokio::fs::read_to_string(entry.path()).await else {
+            continue;
+        };
+        if let Ok(record) = serde_json::from_str::<PendingIngestRecord>(&content)
+            && record.repo_root == repo_root
+        {
+            records.push((entry.path(), record));
+        }
+    }
+    records.sort_by_key(|(_, record)| record.created_at_epoch);
+    Ok(records)
+}
+
+/// Drop a pending record once its work is done or has expired.
+pub async fn remove_pending_ingest(path: &Path) -> Result<()> {
+    match tokio::fs::remove_file(path).await {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => {
+            Err(err).with_context(|| format!("failed to remove pending record {}", path.display()))
+        }
+    }
 }
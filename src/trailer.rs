@@ -0,0 +1,175 @@
+//! `Session-Id:` commit message trailers, written by the `prepare-commit-msg`
+//! hook when `ai.cadence.injectTrailer` is enabled.
+//!
+//! `ai.cadence.trailerFormat` picks what is written: `session-id` (the
+//! default) adds only `Session-Id:`, `full` also adds `Agent:`. Either way the
+//! result reads back with `git interpret-trailers --parse`.
+//!
+//! The trailer is added with `git interpret-trailers`, so git's own rules for
+//! comment lines, the `--verbose` scissors line and existing trailer blocks
+//! apply. Messages without any content are left alone: a commit whose editor
+//! is closed untouched must still abort as empty.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::git;
+
+/// Git config key that opts a user or repository into trailer injection.
+pub const INJECT_TRAILER_KEY: &str = "ai.cadence.injectTrailer";
+
+/// Git config key selecting which trailers are written; see [`TrailerFormat`].
+pub const TRAILER_FORMAT_KEY: &str = "ai.cadence.trailerFormat";
+
+pub const SESSION_ID_TRAILER: &str = "Session-Id";
+pub const AGENT_TRAILER: &str = "Agent";
+
+/// Which trailers describe the session behind a commit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrailerFormat {
+    /// `Session-Id:` only.
+    #[default]
+    SessionId,
+    /// `Agent:` followed by `Session-Id:`.
+    Full,
+}
+
+impl TrailerFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "session-id" => Some(TrailerFormat::SessionId),
+            "full" => Some(TrailerFormat::Full),
+            _ => None,
+        }
+    }
+
+    /// `(key, value)` trailers for a session in this format.
+    pub fn trailers(self, agent: &str, session_id: &str) -> Vec<(&'static str, String)> {
+        let mut trailers = Vec::with_capacity(2);
+        if self == TrailerFormat::Full {
+            trailers.push((AGENT_TRAILER, agent.to_string()));
+        }
+        trailers.push((SESSION_ID_TRAILER, session_id.to_string()));
+        trailers
+    }
+}
+
+/// Whether `message` has anything besides blank lines and `#` comments.
+fn message_has_content(message: &str) -> bool {
+    message
+        .lines()
+        .map(str::trim)
+        .any(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+/// Add `trailers` to the commit message file at `msg_file`, in order.
+///
+/// Returns `Ok(false)` when the message is empty and nothing was written. A
+/// trailer with the same value is never added twice.
+pub async fn append_session_trailers(msg_file: &Path, trailers: &[(&str, String)]) -> Result<bool> {
+    let message = tokio::fs::read_to_string(msg_file).await?;
+    if !message_has_content(&message) {
+        return Ok(false);
+    }
+
+    let trailers: Vec<String> = trailers
+        .iter()
+        .map(|(key, value)| format!("{key}: {value}"))
+        .collect();
+    let file = msg_file.to_string_lossy();
+    let mut args = vec![
+        "interpret-trailers",
+        "--in-place",
+        "--if-exists",
+        "addIfDifferent",
+    ];
+    for trailer in &trailers {
+        args.extend(["--trailer", trailer.as_str()]);
+    }
+    args.push(&file);
+    let output = git::run_git_output_at(None, &args, &[]).await?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git interpret-trailers failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn trailer_is_appended_once_after_the_message() {
+        let tmp = TempDir::new().unwrap();
+        let msg_file = tmp.path().join("COMMIT_EDITMSG");
+        tokio::fs::write(
+            &msg_file,
+            "Fix parser\n\nLonger body.\n\nSigned-off-by: Dev <dev@example.com>\n\
+             # Please enter the commit message for your changes.\n",
+        )
+        .await
+        .unwrap();
+
+        let trailers = TrailerFormat::SessionId.trailers("codex", "abc-123");
+        assert!(append_session_trailers(&msg_file, &trailers).await.unwrap());
+        assert!(append_session_trailers(&msg_file, &trailers).await.unwrap());
+
+        let content = tokio::fs::read_to_string(&msg_file).await.unwrap();
+        assert!(content.starts_with("Fix parser\n\nLonger body.\n\n"));
+        assert!(content.contains("Signed-off-by: Dev <dev@example.com>\nSession-Id: abc-123\n"));
+        assert_eq!(content.matches("Session-Id:").count(), 1);
+        assert!(content.contains("# Please enter the commit message"));
+    }
+
+    #[tokio::test]
+    async fn empty_messages_are_left_untouched() {
+        let tmp = TempDir::new().unwrap();
+        let msg_file = tmp.path().join("COMMIT_EDITMSG");
+        let original = "\n# Please enter the commit message for your changes.\n";
+        tokio::fs::write(&msg_file, original).await.unwrap();
+
+        let trailers = TrailerFormat::SessionId.trailers("codex", "abc-123");
+        assert!(!append_session_trailers(&msg_file, &trailers).await.unwrap());
+        assert_eq!(
+            tokio::fs::read_to_string(&msg_file).await.unwrap(),
+            original
+        );
+    }
+
+    #[tokio::test]
+    async fn full_format_round_trips_through_interpret_trailers() {
+        assert_eq!(TrailerFormat::parse("Full"), Some(TrailerFormat::Full));
+        assert_eq!(
+            TrailerFormat::parse("session-id"),
+            Some(TrailerFormat::SessionId)
+        );
+        assert_eq!(TrailerFormat::parse("notes"), None);
+
+        let tmp = TempDir::new().unwrap();
+        let msg_file = tmp.path().join("COMMIT_EDITMSG");
+        tokio::fs::write(&msg_file, "Fix parser\n").await.unwrap();
+        let trailers = TrailerFormat::Full.trailers("claude-code", "abc-123");
+        assert!(append_session_trailers(&msg_file, &trailers).await.unwrap());
+
+        let message = tokio::fs::read_to_string(&msg_file).await.unwrap();
+        let mut child = tokio::process::Command::new("git")
+            .args(["interpret-trailers", "--parse"])
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        tokio::io::AsyncWriteExt::write_all(child.stdin.as_mut().unwrap(), message.as_bytes())
+            .await
+            .unwrap();
+        drop(child.stdin.take());
+        let output = child.wait_with_output().await.unwrap();
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "Agent: claude-code\nSession-Id: abc-123\n"
+        );
+    }
+}
@@ -2690,8 +2690,10 @@ mod tests {
 
     #[tokio::test]
     async fn update_confirmation_config_preserves_manual_auto_update_preference() {
-        let mut cfg = CliConfig::default();
-        cfg.auto_update = Some(true);
+        let mut cfg = CliConfig {
+            auto_update: Some(true),
+            ..Default::default()
+        };
         assert_eq!(
             update_confirmation_config(InstallMode::Interactive, Some(&cfg)),
             Some(true)